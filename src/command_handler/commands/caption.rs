@@ -0,0 +1,123 @@
+use serenity::{
+    async_trait,
+    builder::{CreateCommand, CreateCommandOption, EditInteractionResponse},
+    client::Context,
+    model::{
+        application::{CommandDataOption, CommandInteraction, CommandOptionType},
+        prelude::Message,
+    },
+    Error,
+};
+
+use crate::command_handler::explicit_command_list::CommandInterface;
+use crate::utils::caption::{caption_emoji, Caption, CaptionPlacement, MAX_CAPTION_LENGTH};
+use crate::utils::scene_core::emoji_format_filter;
+use crate::utils::upload_limit::guild_upload_limit;
+
+struct CaptionEmoji;
+
+pub fn get_command() -> Box<dyn CommandInterface + Sync + Send> {
+    Box::new(CaptionEmoji)
+}
+
+#[async_trait]
+impl CommandInterface for CaptionEmoji {
+    async fn run(
+        &self,
+        ctx: &Context,
+        options: &[CommandDataOption],
+        command: &CommandInteraction,
+    ) -> Result<Message, Error> {
+        let find_str = |name: &str| {
+            options
+                .iter()
+                .find(|option| option.name == name)
+                .and_then(|option| option.value.as_str())
+        };
+
+        let img_url = match emoji_format_filter(find_str("emoji").unwrap_or_default()) {
+            Ok((_, img_url)) => img_url,
+            Err(()) => {
+                return command
+                    .edit_response(
+                        &ctx.http,
+                        EditInteractionResponse::default()
+                            .content("제대로 된 이모지를 입력해주세요"),
+                    )
+                    .await;
+            }
+        };
+
+        let caption = Caption {
+            top: find_str("top").map(str::to_string),
+            bottom: find_str("bottom").map(str::to_string),
+            placement: CaptionPlacement::string_to_value(
+                find_str("placement").unwrap_or("Overlay"),
+            ),
+        };
+        let is_blank = |text: &Option<String>| text.as_deref().is_none_or(|t| t.trim().is_empty());
+        if is_blank(&caption.top) && is_blank(&caption.bottom) {
+            return command
+                .edit_response(
+                    &ctx.http,
+                    EditInteractionResponse::default()
+                        .content("위나 아래에 넣을 문구를 하나 이상 입력해주세요"),
+                )
+                .await;
+        }
+
+        let captioned = caption_emoji(
+            img_url.as_ref(),
+            caption,
+            guild_upload_limit(ctx, command.guild_id),
+        )
+        .await;
+
+        command
+            .edit_response(
+                &ctx.http,
+                match captioned {
+                    Ok((captioned, None)) => {
+                        EditInteractionResponse::default().new_attachment(captioned)
+                    }
+                    Ok((captioned, Some(compromise))) => EditInteractionResponse::default()
+                        .content(compromise.get_notice_message())
+                        .new_attachment(captioned),
+                    Err(why) => EditInteractionResponse::default().content(why.get_error_message()),
+                },
+            )
+            .await
+    }
+
+    fn name(&self) -> String {
+        String::from("caption")
+    }
+
+    fn register(&self) -> CreateCommand {
+        let options = Vec::from([
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "emoji",
+                "문구를 넣을 이모지를 선택해주세요.",
+            )
+            .required(true),
+            CreateCommandOption::new(CommandOptionType::String, "top", "위에 넣을 문구")
+                .max_length(MAX_CAPTION_LENGTH)
+                .required(false),
+            CreateCommandOption::new(CommandOptionType::String, "bottom", "아래에 넣을 문구")
+                .max_length(MAX_CAPTION_LENGTH)
+                .required(false),
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "placement",
+                "문구를 이모지 위에 겹칠지, 바깥에 띠를 붙여서 쓸지 골라주세요. (기본 겹치기)",
+            )
+            .add_string_choice("이모지 위에 겹치기", "Overlay")
+            .add_string_choice("바깥에 띠 붙이기", "Outside")
+            .required(false),
+        ]);
+        CreateCommand::new(self.name())
+            .description("이모지 위아래에 짤 같은 문구를 넣어서 전송합니다")
+            .set_options(options)
+    }
+}
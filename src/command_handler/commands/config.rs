@@ -2,7 +2,8 @@ use serenity::{
     async_trait,
     builder::{
         CreateActionRow, CreateButton, CreateCommand, CreateEmbed, CreateInteractionResponse,
-        EditInteractionResponse, CreateInteractionResponseMessage,
+        EditInteractionResponse, CreateInteractionResponseMessage, CreateSelectMenu,
+        CreateSelectMenuKind, CreateSelectMenuOption,
     },
     client::Context,
     futures::StreamExt,
@@ -10,8 +11,11 @@ use serenity::{
         application::{
             ButtonStyle, 
             CommandDataOption,
-            CommandInteraction
+            CommandInteraction,
+            ComponentInteractionDataKind
         },
+        channel::ChannelType,
+        id::{ChannelId, RoleId},
         permissions::Permissions,
         prelude::Message,
     },
@@ -28,7 +32,7 @@ use mongodb::{
 use bson::Document;
 
 use crate::command_handler::explicit_command_list::CommandInterface;
-use crate::utils::{scene_core::ImageSize, guild_config::GuildConfig};
+use crate::utils::{scene_core::{AnimatedFormat, DoubleEmojiArrangement, DoubleEmojiLayout, EmojiAlignment, EmojiLayout, ImageSize, ResizeFilter}, guild_config::{ChannelOverride, GuildConfig}};
 use crate::GlobalGuildConfigs;
 
 use log::{error, info};
@@ -76,12 +80,8 @@ impl CommandInterface for GuildConfigSetting {
                     .edit_response(
                         &ctx.http,
                         EditInteractionResponse::default()
-                            .add_embed(config_embed(
-                                gclock.auto_magnitute_enable,
-                                gclock.auto_transfer_webp,
-                                gclock.auto_magnitute_config.clone(),
-                            ))
-                            .components(vec![config_components()])
+                            .add_embed(config_embed(&gclock))
+                            .components(config_components())
                         )
                     .await
                 {
@@ -155,6 +155,370 @@ impl CommandInterface for GuildConfigSetting {
                                             error!("sending error: {:?}", why);
                                         }
                                 }
+                            } else if button_reaction.data.custom_id == "set_multi_emoji_layout" {
+                                let msg = button_reaction.get_response(&ctx.http).await.unwrap();
+                                let mut interaction_stream = msg
+                                    .await_component_interactions(ctx)
+                                    .timeout(Duration::from_secs(60 * 5))
+                                    .filter(move |f| {
+                                        f.message.id == msg.id
+                                        && f.member.as_ref().unwrap().user.id == command.user.id
+                                    }).stream();
+
+                                //배치랑 간격을 따로 고를 수 있게 완료 누를때까지 계속 받음
+                                while let Some(layout_reaction) = interaction_stream.next().await {
+                                    let custom_id = layout_reaction.data.custom_id.as_str();
+                                    match custom_id {
+                                        "multilayout_row" => gclock.multi_emoji_layout = EmojiLayout::Row,
+                                        "multilayout_column" => gclock.multi_emoji_layout = EmojiLayout::Column,
+                                        "multilayout_grid" => gclock.multi_emoji_layout = EmojiLayout::Grid,
+                                        _ => if let Some(spacing) = custom_id.strip_prefix("multispacing_") {
+                                            gclock.multi_emoji_spacing = spacing.parse().unwrap_or(0);
+                                        }
+                                    }
+
+                                    let response = if custom_id == "multilayout_done" {
+                                        CreateInteractionResponseMessage::new()
+                                            .content(format!(
+                                                "여러 이모지 배치를 {}, 간격 {}px로 설정했습니다.",
+                                                layout_name(gclock.multi_emoji_layout),
+                                                gclock.multi_emoji_spacing
+                                            ))
+                                            .components(vec![]).embeds(vec![])
+                                    } else {
+                                        CreateInteractionResponseMessage::new()
+                                            .content(layout_notice(&gclock))
+                                            .components(layout_component())
+                                    };
+
+                                    if let Err(why) = layout_reaction
+                                        .create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(response))
+                                        .await {
+                                            error!("sending error: {:?}", why);
+                                        }
+
+                                    if custom_id == "multilayout_done" {
+                                        break;
+                                    }
+                                }
+                            } else if button_reaction.data.custom_id == "set_double_emoji_layout" {
+                                let msg = button_reaction.get_response(&ctx.http).await.unwrap();
+                                let mut interaction_stream = msg
+                                    .await_component_interactions(ctx)
+                                    .timeout(Duration::from_secs(60 * 5))
+                                    .filter(move |f| {
+                                        f.message.id == msg.id
+                                        && f.member.as_ref().unwrap().user.id == command.user.id
+                                    }).stream();
+
+                                //배치, 정렬, 간격, 배경, 겹치기 위치를 따로 고를 수 있게 완료 누를때까지 계속 받음
+                                while let Some(layout_reaction) = interaction_stream.next().await {
+                                    let custom_id = layout_reaction.data.custom_id.as_str();
+                                    let layout = &mut gclock.double_emoji_layout;
+                                    if let Some(arrangement) = custom_id.strip_prefix("doublearrange_") {
+                                        layout.arrangement = DoubleEmojiArrangement::string_to_value(arrangement);
+                                    } else if let Some(alignment) = custom_id.strip_prefix("doublealign_") {
+                                        layout.alignment = EmojiAlignment::string_to_value(alignment);
+                                    } else if let Some(gap) = custom_id.strip_prefix("doublegap_") {
+                                        layout.gap = gap.parse().unwrap_or(0);
+                                    } else if let Some(background) = custom_id.strip_prefix("doublebg_") {
+                                        layout.background = DoubleEmojiLayout::background_from_string(background);
+                                    } else if let Some(overlay) = custom_id.strip_prefix("doubleoverlay_") {
+                                        //크기_가로오프셋_세로오프셋
+                                        let values: Vec<i32> = overlay.split('_').filter_map(|v| v.parse().ok()).collect();
+                                        if let [scale, x, y] = values[..] {
+                                            layout.arrangement = DoubleEmojiArrangement::Overlay;
+                                            layout.overlay_scale = scale as u32;
+                                            layout.overlay_offset = (x, y);
+                                        }
+                                    }
+
+                                    let response = if custom_id == "doublelayout_done" {
+                                        CreateInteractionResponseMessage::new()
+                                            .content(format!(
+                                                "두 이모지 배치를 {}(으)로 설정했습니다.",
+                                                double_layout_summary(&gclock.double_emoji_layout)
+                                            ))
+                                            .components(vec![]).embeds(vec![])
+                                    } else {
+                                        CreateInteractionResponseMessage::new()
+                                            .content(double_layout_notice(&gclock))
+                                            .components(double_layout_component())
+                                    };
+
+                                    if let Err(why) = layout_reaction
+                                        .create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(response))
+                                        .await {
+                                            error!("sending error: {:?}", why);
+                                        }
+
+                                    if custom_id == "doublelayout_done" {
+                                        break;
+                                    }
+                                }
+                            } else if button_reaction.data.custom_id == "set_resize_filter" {
+                                let msg = button_reaction.get_response(&ctx.http).await.unwrap();
+                                let mut interaction_stream = msg
+                                    .await_component_interactions(ctx)
+                                    .timeout(Duration::from_secs(60 * 5))
+                                    .filter(move |f| {
+                                        f.message.id == msg.id
+                                        && f.member.as_ref().unwrap().user.id == command.user.id
+                                    }).stream();
+
+                                if let Some(filter_reaction) = interaction_stream.next().await {
+                                    gclock.resize_filter = ResizeFilter::string_to_value(
+                                        filter_reaction.data.custom_id.trim_start_matches("setfilter_")
+                                    );
+
+                                    if let Err(why) = filter_reaction
+                                        .create_response(
+                                            &ctx.http,
+                                            CreateInteractionResponse::UpdateMessage(
+                                                CreateInteractionResponseMessage::new()
+                                                    .content(format!(
+                                                        "이모지 크기 조절 필터를 {}(으)로 설정했습니다.",
+                                                        filter_name(gclock.resize_filter)
+                                                    ))
+                                                    .components(vec![]).embeds(vec![])
+                                            ),
+                                        ).await {
+                                            error!("sending error: {:?}", why);
+                                        }
+                                }
+                            } else if button_reaction.data.custom_id == "set_apng_output_format" {
+                                let msg = button_reaction.get_response(&ctx.http).await.unwrap();
+                                let mut interaction_stream = msg
+                                    .await_component_interactions(ctx)
+                                    .timeout(Duration::from_secs(60 * 5))
+                                    .filter(move |f| {
+                                        f.message.id == msg.id
+                                        && f.member.as_ref().unwrap().user.id == command.user.id
+                                    }).stream();
+
+                                if let Some(format_reaction) = interaction_stream.next().await {
+                                    gclock.apng_output_format = AnimatedFormat::string_to_value(
+                                        format_reaction.data.custom_id.trim_start_matches("setapng_")
+                                    );
+
+                                    if let Err(why) = format_reaction
+                                        .create_response(
+                                            &ctx.http,
+                                            CreateInteractionResponse::UpdateMessage(
+                                                CreateInteractionResponseMessage::new()
+                                                    .content(format!(
+                                                        "움직이는 APNG를 {}(으)로 변환하도록 설정했습니다.",
+                                                        apng_output_name(gclock.apng_output_format)
+                                                    ))
+                                                    .components(vec![]).embeds(vec![])
+                                            ),
+                                        ).await {
+                                            error!("sending error: {:?}", why);
+                                        }
+                                }
+                            } else if button_reaction.data.custom_id == "set_repost_expire" {
+                                let msg = button_reaction.get_response(&ctx.http).await.unwrap();
+                                let mut interaction_stream = msg
+                                    .await_component_interactions(ctx)
+                                    .timeout(Duration::from_secs(60 * 5))
+                                    .filter(move |f| {
+                                        f.message.id == msg.id
+                                        && f.member.as_ref().unwrap().user.id == command.user.id
+                                    }).stream();
+
+                                if let Some(expire_reaction) = interaction_stream.next().await {
+                                    gclock.repost_expire_seconds = expire_reaction.data.custom_id
+                                        .trim_start_matches("repostexpire_")
+                                        .parse()
+                                        .unwrap_or(0);
+
+                                    if let Err(why) = expire_reaction
+                                        .create_response(
+                                            &ctx.http,
+                                            CreateInteractionResponse::UpdateMessage(
+                                                CreateInteractionResponseMessage::new()
+                                                    .content(match gclock.repost_expire_seconds {
+                                                        0 => "다시 보낸 이모지를 자동으로 지우지 않도록 설정했습니다.".to_string(),
+                                                        seconds => format!(
+                                                            "다시 보낸 이모지를 {} 뒤에 자동으로 지우도록 설정했습니다.",
+                                                            expire_name(seconds)
+                                                        ),
+                                                    })
+                                                    .components(vec![]).embeds(vec![])
+                                            ),
+                                        ).await {
+                                            error!("sending error: {:?}", why);
+                                        }
+                                }
+                            } else if button_reaction.data.custom_id == "set_channel_overrides" {
+                                let msg = button_reaction.get_response(&ctx.http).await.unwrap();
+                                let mut interaction_stream = msg
+                                    .await_component_interactions(ctx)
+                                    .timeout(Duration::from_secs(60 * 5))
+                                    .filter(move |f| {
+                                        f.message.id == msg.id
+                                        && f.member.as_ref().unwrap().user.id == command.user.id
+                                    }).stream();
+
+                                //채널을 고르고 항목을 바꾸는 걸 완료 누를때까지 계속 받음
+                                let mut selected: Option<ChannelId> = None;
+                                while let Some(override_reaction) = interaction_stream.next().await {
+                                    let custom_id = override_reaction.data.custom_id.as_str();
+                                    match (&override_reaction.data.kind, selected) {
+                                        (ComponentInteractionDataKind::ChannelSelect { values }, _) => {
+                                            selected = values.first().copied();
+                                        }
+                                        (ComponentInteractionDataKind::StringSelect { values }, Some(channel_id)) => {
+                                            let value = values.first().map(String::as_str).unwrap_or("Inherit");
+                                            let channel_override = gclock.channel_overrides.entry(channel_id).or_default();
+                                            if custom_id == "override_size" {
+                                                channel_override.auto_magnitute_config =
+                                                    (value != "Inherit").then(|| ImageSize::string_to_value(value));
+                                            } else if custom_id == "override_double" {
+                                                channel_override.double_emoji_arrangement =
+                                                    (value != "Inherit").then(|| DoubleEmojiArrangement::string_to_value(value));
+                                            }
+                                        }
+                                        (ComponentInteractionDataKind::Button, Some(channel_id)) => {
+                                            let channel_override = gclock.channel_overrides.entry(channel_id).or_default();
+                                            match custom_id {
+                                                "override_enable" => {
+                                                    channel_override.auto_magnitute_enable =
+                                                        next_override_state(channel_override.auto_magnitute_enable);
+                                                }
+                                                "override_merge" => {
+                                                    channel_override.merge_emoji_enable =
+                                                        next_override_state(channel_override.merge_emoji_enable);
+                                                }
+                                                "override_webp" => {
+                                                    channel_override.auto_transfer_webp =
+                                                        next_override_state(channel_override.auto_transfer_webp);
+                                                }
+                                                "override_clear" => *channel_override = ChannelOverride::default(),
+                                                _ => {}
+                                            }
+                                        }
+                                        _ => {}
+                                    }
+                                    //서버 설정을 그대로 따르는 채널은 남겨두지 않음
+                                    gclock.channel_overrides.retain(|_, channel_override| !channel_override.is_empty());
+
+                                    let response = if custom_id == "override_done" {
+                                        CreateInteractionResponseMessage::new()
+                                            .content(format!(
+                                                "채널별 설정을 저장했습니다. 따로 설정한 채널과 카테고리는 {}개입니다.",
+                                                gclock.channel_overrides.len()
+                                            ))
+                                            .components(vec![]).embeds(vec![])
+                                    } else {
+                                        CreateInteractionResponseMessage::new()
+                                            .content(channel_override_notice(&gclock, selected))
+                                            .components(channel_override_component(&gclock, selected))
+                                    };
+
+                                    if let Err(why) = override_reaction
+                                        .create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(response))
+                                        .await {
+                                            error!("sending error: {:?}", why);
+                                        }
+
+                                    if custom_id == "override_done" {
+                                        break;
+                                    }
+                                }
+                            } else if button_reaction.data.custom_id == "set_role_filter" {
+                                let msg = button_reaction.get_response(&ctx.http).await.unwrap();
+                                let mut interaction_stream = msg
+                                    .await_component_interactions(ctx)
+                                    .timeout(Duration::from_secs(60 * 5))
+                                    .filter(move |f| {
+                                        f.message.id == msg.id
+                                        && f.member.as_ref().unwrap().user.id == command.user.id
+                                    }).stream();
+
+                                //허용이랑 제외를 따로 고를 수 있게 완료 누를때까지 계속 받음
+                                while let Some(role_reaction) = interaction_stream.next().await {
+                                    let custom_id = role_reaction.data.custom_id.as_str();
+                                    if let ComponentInteractionDataKind::RoleSelect { values } = &role_reaction.data.kind {
+                                        match custom_id {
+                                            "role_allow" => gclock.auto_allowed_roles = values.clone(),
+                                            "role_deny" => gclock.auto_denied_roles = values.clone(),
+                                            _ => {}
+                                        }
+                                    } else if custom_id == "role_clear" {
+                                        gclock.auto_allowed_roles.clear();
+                                        gclock.auto_denied_roles.clear();
+                                    }
+
+                                    let response = if custom_id == "role_done" {
+                                        CreateInteractionResponseMessage::new()
+                                            .content(format!(
+                                                "자동 확대 역할을 설정했습니다.\n{}",
+                                                role_filter_summary(&gclock)
+                                            ))
+                                            .components(vec![]).embeds(vec![])
+                                    } else {
+                                        CreateInteractionResponseMessage::new()
+                                            .content(role_filter_notice(&gclock))
+                                            .components(role_filter_component(&gclock))
+                                    };
+
+                                    if let Err(why) = role_reaction
+                                        .create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(response))
+                                        .await {
+                                            error!("sending error: {:?}", why);
+                                        }
+
+                                    if custom_id == "role_done" {
+                                        break;
+                                    }
+                                }
+                            } else if button_reaction.data.custom_id == "set_auto_size_bounds" {
+                                let msg = button_reaction.get_response(&ctx.http).await.unwrap();
+                                let mut interaction_stream = msg
+                                    .await_component_interactions(ctx)
+                                    .timeout(Duration::from_secs(60 * 5))
+                                    .filter(move |f| {
+                                        f.message.id == msg.id
+                                        && f.member.as_ref().unwrap().user.id == command.user.id
+                                    }).stream();
+
+                                //최소랑 최대를 따로 고를 수 있게 완료 누를때까지 계속 받음
+                                while let Some(bounds_reaction) = interaction_stream.next().await {
+                                    let custom_id = bounds_reaction.data.custom_id.as_str();
+                                    //최소가 최대보다 커지면 나머지 한쪽을 같이 맞춤
+                                    if let Some(min) = custom_id.strip_prefix("autosizemin_").and_then(|v| v.parse().ok()) {
+                                        gclock.auto_size_min = min;
+                                        gclock.auto_size_max = gclock.auto_size_max.max(min);
+                                    } else if let Some(max) = custom_id.strip_prefix("autosizemax_").and_then(|v| v.parse().ok()) {
+                                        gclock.auto_size_max = max;
+                                        gclock.auto_size_min = gclock.auto_size_min.min(max);
+                                    }
+
+                                    let response = if custom_id == "autosize_done" {
+                                        CreateInteractionResponseMessage::new()
+                                            .content(format!(
+                                                "자동 크기 범위를 {}px ~ {}px로 설정했습니다.",
+                                                gclock.auto_size_min,
+                                                gclock.auto_size_max
+                                            ))
+                                            .components(vec![]).embeds(vec![])
+                                    } else {
+                                        CreateInteractionResponseMessage::new()
+                                            .content(auto_bounds_notice(&gclock))
+                                            .components(auto_bounds_component())
+                                    };
+
+                                    if let Err(why) = bounds_reaction
+                                        .create_response(&ctx.http, CreateInteractionResponse::UpdateMessage(response))
+                                        .await {
+                                            error!("sending error: {:?}", why);
+                                        }
+
+                                    if custom_id == "autosize_done" {
+                                        break;
+                                    }
+                                }
                             }
 
                             
@@ -178,7 +542,26 @@ impl CommandInterface for GuildConfigSetting {
                                     "guild_id" : gclock.guild_id.get() as f64,
                                     "auto_magnitute_enable" : gclock.auto_magnitute_enable,
                                     "auto_magnitute_config" : ImageSize::value_to_string(&(gclock.auto_magnitute_config)),
-                                    "auto_transfer_webp" : gclock.auto_transfer_webp
+                                    "auto_transfer_webp" : gclock.auto_transfer_webp,
+                                    "multi_emoji_layout" : EmojiLayout::value_to_string(&(gclock.multi_emoji_layout)),
+                                    "multi_emoji_spacing" : gclock.multi_emoji_spacing as i32,
+                                    "resize_filter" : ResizeFilter::value_to_string(&(gclock.resize_filter)),
+                                    "auto_size_min" : gclock.auto_size_min as i32,
+                                    "auto_size_max" : gclock.auto_size_max as i32,
+                                    "apng_output_format" : AnimatedFormat::value_to_string(&(gclock.apng_output_format)),
+                                    "double_emoji_arrangement" : DoubleEmojiArrangement::value_to_string(&(gclock.double_emoji_layout.arrangement)),
+                                    "double_emoji_alignment" : EmojiAlignment::value_to_string(&(gclock.double_emoji_layout.alignment)),
+                                    "double_emoji_gap" : gclock.double_emoji_layout.gap as i32,
+                                    "double_emoji_background" : DoubleEmojiLayout::background_to_string(&(gclock.double_emoji_layout.background)),
+                                    "double_emoji_overlay_scale" : gclock.double_emoji_layout.overlay_scale as i32,
+                                    "double_emoji_overlay_offset_x" : gclock.double_emoji_layout.overlay_offset.0,
+                                    "double_emoji_overlay_offset_y" : gclock.double_emoji_layout.overlay_offset.1,
+                                    "webhook_repost" : gclock.webhook_repost,
+                                    "magnify_replies" : gclock.magnify_replies,
+                                    "repost_expire_seconds" : gclock.repost_expire_seconds as i32,
+                                    "channel_overrides" : gclock.channel_overrides_document(),
+                                    "auto_allowed_roles" : role_ids(&gclock.auto_allowed_roles),
+                                    "auto_denied_roles" : role_ids(&gclock.auto_denied_roles)
                                 }
                             }, None
                         ).await.unwrap();
@@ -207,14 +590,14 @@ impl CommandInterface for GuildConfigSetting {
     }
 }
 
-fn config_embed(autoemoji: bool, autowebpsend: bool, default_emoji_size: ImageSize) -> CreateEmbed {
+fn config_embed(gclock: &GuildConfig) -> CreateEmbed {
     CreateEmbed::default()
         .title("봇 설정")
         .description("설정하고 싶은 것을 선택해주세요")
         .fields([
             (
                 "자동 이모지 크기 조절 설정 : 켜져있으면 끄고, 꺼져있으면 킵니다.",
-                match autoemoji {
+                match gclock.auto_magnitute_enable {
                     true => "현재 상태 : 켜짐",
                     false => "현재 상태 : 꺼짐",
                 },
@@ -222,7 +605,23 @@ fn config_embed(autoemoji: bool, autowebpsend: bool, default_emoji_size: ImageSi
             ),
             (
                 "자동 WebP 변환 전송 설정 : 켜져있으면 끄고, 꺼져있으면 킵니다.",
-                match autowebpsend {
+                match gclock.auto_transfer_webp {
+                    true => "현재 상태 : 켜짐",
+                    false => "현재 상태 : 꺼짐",
+                },
+                false,
+            ),
+            (
+                "웹후크로 보낸 사람처럼 다시 보내기 설정 : 켜져있으면 끄고, 꺼져있으면 킵니다.",
+                match gclock.webhook_repost {
+                    true => "현재 상태 : 켜짐",
+                    false => "현재 상태 : 꺼짐",
+                },
+                false,
+            ),
+            (
+                "답장 이모지 확대 설정 : 켜져있으면 끄고, 꺼져있으면 킵니다.",
+                match gclock.magnify_replies {
                     true => "현재 상태 : 켜짐",
                     false => "현재 상태 : 꺼짐",
                 },
@@ -230,22 +629,66 @@ fn config_embed(autoemoji: bool, autowebpsend: bool, default_emoji_size: ImageSi
             ),
             (
                 "이모지 크기 조정 기본값",
-                match default_emoji_size {
-                    ImageSize::HyperTechniqueOfLisaSuFinger => "절라 짝음",
-                    ImageSize::Small => "작음",
-                    ImageSize::Medium => "적당함",
-                    ImageSize::Large => "큼",
-                    ImageSize::HyperSuperUltraSexFeaturedFuckingLarge => "절라 큼",
-                    ImageSize::Auto => "자동",
+                size_name(&gclock.auto_magnitute_config),
+                false,
+            ),
+            (
+                "여러 이모지 배치",
+                &format!(
+                    "{}, 간격 {}px",
+                    layout_name(gclock.multi_emoji_layout),
+                    gclock.multi_emoji_spacing
+                ),
+                false,
+            ),
+            (
+                "두 이모지 배치",
+                &double_layout_summary(&gclock.double_emoji_layout),
+                false,
+            ),
+            (
+                "이모지 크기 조절 필터",
+                filter_name(gclock.resize_filter),
+                false,
+            ),
+            (
+                "자동 크기 범위",
+                &format!("{}px ~ {}px", gclock.auto_size_min, gclock.auto_size_max),
+                false,
+            ),
+            (
+                "움직이는 APNG 변환 형식",
+                apng_output_name(gclock.apng_output_format),
+                false,
+            ),
+            (
+                "다시 보낸 이모지 자동 삭제",
+                &match gclock.repost_expire_seconds {
+                    0 => "지우지 않음".to_string(),
+                    seconds => format!("{} 뒤에 지움", expire_name(seconds)),
                 },
                 false,
             ),
+            (
+                "채널별 설정",
+                &match gclock.channel_overrides.len() {
+                    0 => "없음".to_string(),
+                    count => format!("채널과 카테고리 {}개에 따로 설정됨", count),
+                },
+                false,
+            ),
+            (
+                "자동 확대 역할",
+                &role_filter_summary(gclock),
+                false,
+            ),
         ])
         .color((255, 255, 255)).clone()
 }
 
-fn config_components() -> CreateActionRow {
-    CreateActionRow::Buttons(vec![
+fn config_components() -> Vec<CreateActionRow> {
+    vec![
+        CreateActionRow::Buttons(vec![
         CreateButton::new("autoemoji_enabled")
             .label("자동 이모지 크기 조절 켜거나 끄기")
             .style(ButtonStyle::Primary).clone(),
@@ -254,8 +697,525 @@ fn config_components() -> CreateActionRow {
             .style(ButtonStyle::Primary).clone(),
         CreateButton::new("set_default_autoemoji_size")
             .label("크기 기본값 설정하기")
+            .style(ButtonStyle::Secondary).clone(),
+        CreateButton::new("set_multi_emoji_layout")
+            .label("여러 이모지 배치 설정하기")
+            .style(ButtonStyle::Secondary).clone(),
+        CreateButton::new("set_resize_filter")
+            .label("크기 조절 필터 설정하기")
             .style(ButtonStyle::Secondary).clone()
-    ])
+        ]),
+        CreateActionRow::Buttons(vec![
+        CreateButton::new("set_auto_size_bounds")
+            .label("자동 크기 범위 설정하기")
+            .style(ButtonStyle::Secondary).clone(),
+        CreateButton::new("set_apng_output_format")
+            .label("APNG 출력 형식 설정하기")
+            .style(ButtonStyle::Secondary).clone(),
+        CreateButton::new("set_double_emoji_layout")
+            .label("두 이모지 배치 설정하기")
+            .style(ButtonStyle::Secondary).clone(),
+        CreateButton::new("webhook_repost_enabled")
+            .label("웹후크로 다시 보내기 켜거나 끄기")
+            .style(ButtonStyle::Primary).clone(),
+        CreateButton::new("magnify_replies_enabled")
+            .label("답장 이모지 확대 켜거나 끄기")
+            .style(ButtonStyle::Primary).clone()
+        ]),
+        CreateActionRow::Buttons(vec![
+        CreateButton::new("set_repost_expire")
+            .label("자동 삭제 시간 설정하기")
+            .style(ButtonStyle::Secondary).clone(),
+        CreateButton::new("set_channel_overrides")
+            .label("채널별 설정하기")
+            .style(ButtonStyle::Secondary).clone(),
+        CreateButton::new("set_role_filter")
+            .label("자동 확대 역할 설정하기")
+            .style(ButtonStyle::Secondary).clone()
+        ])
+    ]
+}
+
+fn auto_bounds_notice(gclock: &GuildConfig) -> String {
+    format!("
+**__크기 기본값이 \"자동\"일 때 쓸 범위를 선택해주세요.__**\n
+이모지의 긴 변이 최소 크기보다 작으면 최소 크기까지 키우고, 최대 크기보다 크면 최대 크기로 줄입니다.\n
+그 사이에 있는 이모지는 크기를 바꾸지 않고 그대로 보냅니다.\n
+현재 설정 : {}px ~ {}px\n
+다 골랐으면 \"완료\"를 눌러주세요.
+    ", gclock.auto_size_min, gclock.auto_size_max)
+}
+
+fn auto_bounds_component() -> Vec<CreateActionRow> {
+    vec![
+        CreateActionRow::Buttons(
+            [32, 64, 128, 256].iter().map(|min| {
+                CreateButton::new(format!("autosizemin_{}", min))
+                    .label(format!("최소 {}px", min))
+                    .style(ButtonStyle::Secondary)
+            }).collect()
+        ),
+        CreateActionRow::Buttons(
+            [128, 256, 512, 1024].iter().map(|max| {
+                CreateButton::new(format!("autosizemax_{}", max))
+                    .label(format!("최대 {}px", max))
+                    .style(ButtonStyle::Secondary)
+            }).collect()
+        ),
+        CreateActionRow::Buttons(
+            vec![
+                CreateButton::new("autosize_done")
+                    .label("완료")
+                    .style(ButtonStyle::Primary)
+            ]
+        )
+    ]
+}
+
+fn filter_name(filter: ResizeFilter) -> &'static str {
+    match filter {
+        ResizeFilter::Auto => "자동",
+        ResizeFilter::Nearest => "Nearest",
+        ResizeFilter::Bilinear => "Bilinear",
+        ResizeFilter::CatmullRom => "CatmullRom",
+        ResizeFilter::Mitchell => "Mitchell",
+        ResizeFilter::Lanczos3 => "Lanczos3",
+    }
+}
+
+fn filter_notice() -> String {
+    "
+**__이모지 크기를 바꿀 때 쓸 필터를 선택해주세요.__**\n
+\"**Nearest**\"는 도트 이모지를 뭉개지 않고 그대로 키웁니다.\n
+\"**Bilinear**\"는 기존 방식이고, \"**CatmullRom**\", \"**Mitchell**\", \"**Lanczos3**\" 순으로 더 선명하지만 조금 느립니다.\n
+\"자동\"은 색이 적고 경계가 딱 떨어지는 도트 이모지를 알아서 감지해 정수배로 키우고, 나머지는 Bilinear를 씁니다.\n
+    ".to_string()
+}
+
+fn filter_component() -> Vec<CreateActionRow> {
+    vec![
+        CreateActionRow::Buttons(
+            ["Nearest", "Bilinear", "CatmullRom", "Mitchell", "Lanczos3"].iter().map(|filter| {
+                CreateButton::new(format!("setfilter_{}", filter))
+                    .label(*filter)
+                    .style(ButtonStyle::Secondary)
+            }).collect()
+        ),
+        CreateActionRow::Buttons(
+            vec![
+                CreateButton::new("setfilter_Auto")
+                    .label("자동")
+                    .style(ButtonStyle::Primary)
+            ]
+        )
+    ]
+}
+
+fn apng_output_name(format: AnimatedFormat) -> &'static str {
+    match format {
+        AnimatedFormat::Gif => "GIF",
+        AnimatedFormat::Apng => "APNG",
+        AnimatedFormat::WebP => "WebP",
+    }
+}
+
+fn apng_output_notice() -> String {
+    "
+**__움직이는 APNG(움짤 스티커 등)를 변환할 때 내보낼 형식을 선택해주세요.__**\n
+디스코드는 APNG를 움직이지 않게 보여주니까 \"**GIF**\"가 기본입니다.\n
+\"**WebP**\"는 반투명한 가장자리가 깔끔하게 남고, \"**APNG**\"는 원본 형식 그대로 크기만 바꿉니다.\n
+WebP 자동 변환이 켜져있으면 채팅에 올라온 움직이는 APNG도 이 형식으로 바꿉니다.\n
+    ".to_string()
+}
+
+fn apng_output_component() -> Vec<CreateActionRow> {
+    vec![
+        CreateActionRow::Buttons(
+            [("Gif", "GIF"), ("WebP", "WebP"), ("Apng", "APNG")].iter().map(|(format, label)| {
+                CreateButton::new(format!("setapng_{}", format))
+                    .label(*label)
+                    .style(ButtonStyle::Secondary)
+            }).collect()
+        )
+    ]
+}
+
+fn size_name(size: &ImageSize) -> &'static str {
+    match size {
+        ImageSize::HyperTechniqueOfLisaSuFinger => "절라 짝음",
+        ImageSize::Small => "작음",
+        ImageSize::Medium => "적당함",
+        ImageSize::Large => "큼",
+        ImageSize::HyperSuperUltraSexFeaturedFuckingLarge => "절라 큼",
+        ImageSize::Auto => "자동",
+        ImageSize::Custom(..) | ImageSize::Scale(..) | ImageSize::AutoBounded(..) => "사용자 지정",
+    }
+}
+
+fn arrangement_name(arrangement: DoubleEmojiArrangement) -> &'static str {
+    match arrangement {
+        DoubleEmojiArrangement::Horizontal => "가로",
+        DoubleEmojiArrangement::Vertical => "세로",
+        DoubleEmojiArrangement::Overlay => "겹치기",
+    }
+}
+
+//서버 설정 따름 → 켜짐 → 꺼짐 순서로 돌아감
+fn next_override_state(state: Option<bool>) -> Option<bool> {
+    match state {
+        None => Some(true),
+        Some(true) => Some(false),
+        Some(false) => None,
+    }
+}
+
+fn override_state_name(state: Option<bool>) -> &'static str {
+    match state {
+        None => "서버 설정 따름",
+        Some(true) => "켜짐",
+        Some(false) => "꺼짐",
+    }
+}
+
+fn channel_override_notice(gclock: &GuildConfig, selected: Option<ChannelId>) -> String {
+    let overridden = match gclock.channel_overrides.len() {
+        0 => "없음".to_string(),
+        _ => gclock.channel_overrides.keys().map(|channel_id| format!("<#{}>", channel_id)).collect::<Vec<_>>().join(", "),
+    };
+    let current = match selected {
+        None => "설정할 채널이나 카테고리를 먼저 골라주세요.".to_string(),
+        Some(channel_id) => {
+            let channel_override = gclock.channel_overrides.get(&channel_id).cloned().unwrap_or_default();
+            format!(
+                "<#{}> 설정 : 자동 확대 {}, 크기 {}, 이모지 합치기 {}, 두 이모지 배치 {}, WebP 자동 변환 {}",
+                channel_id,
+                override_state_name(channel_override.auto_magnitute_enable),
+                channel_override.auto_magnitute_config.as_ref().map_or("서버 설정 따름", size_name),
+                override_state_name(channel_override.merge_emoji_enable),
+                channel_override.double_emoji_arrangement.map_or("서버 설정 따름", arrangement_name),
+                override_state_name(channel_override.auto_transfer_webp)
+            )
+        }
+    };
+    format!("
+**__채널이나 카테고리마다 서버 설정을 덮어쓸 수 있습니다.__**\n
+정하지 않은 항목은 카테고리 설정을, 카테고리에도 없으면 서버 설정을 따릅니다. 스레드는 부모 채널 설정을 따릅니다.\n
+따로 설정한 곳 : {}\n
+{}\n
+다 골랐으면 \"완료\"를 눌러주세요.
+    ", overridden, current)
+}
+
+fn channel_override_component(gclock: &GuildConfig, selected: Option<ChannelId>) -> Vec<CreateActionRow> {
+    let mut components = vec![
+        CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                "override_channel",
+                CreateSelectMenuKind::Channel {
+                    channel_types: Some(vec![
+                        ChannelType::Text,
+                        ChannelType::News,
+                        ChannelType::Forum,
+                        ChannelType::Voice,
+                        ChannelType::Category,
+                    ]),
+                    default_channels: selected.map(|channel_id| vec![channel_id]),
+                },
+            )
+            .placeholder("설정할 채널이나 카테고리 고르기")
+        ),
+    ];
+
+    if let Some(channel_id) = selected {
+        let channel_override = gclock.channel_overrides.get(&channel_id).cloned().unwrap_or_default();
+        let size_value = channel_override.auto_magnitute_config.as_ref().map(ImageSize::value_to_string);
+        let arrangement_value = channel_override.double_emoji_arrangement.as_ref().map(DoubleEmojiArrangement::value_to_string);
+
+        components.push(CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                "override_size",
+                CreateSelectMenuKind::String {
+                    options: [
+                        ("Inherit", "크기 : 서버 설정 따름"),
+                        ("HyperTechniqueOfLisaSuFinger", "크기 : 절라 짝음"),
+                        ("Small", "크기 : 작음"),
+                        ("Medium", "크기 : 적당함"),
+                        ("Large", "크기 : 큼"),
+                        ("HyperSuperUltraSexFeaturedFuckingLarge", "크기 : 절라 큼"),
+                        ("Auto", "크기 : 자동"),
+                    ].iter().map(|(value, label)| {
+                        CreateSelectMenuOption::new(*label, *value)
+                            .default_selection(size_value.as_deref().unwrap_or("Inherit") == *value)
+                    }).collect(),
+                },
+            )
+        ));
+        components.push(CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                "override_double",
+                CreateSelectMenuKind::String {
+                    options: [
+                        ("Inherit", "두 이모지 배치 : 서버 설정 따름"),
+                        ("Horizontal", "두 이모지 배치 : 가로"),
+                        ("Vertical", "두 이모지 배치 : 세로"),
+                        ("Overlay", "두 이모지 배치 : 겹치기"),
+                    ].iter().map(|(value, label)| {
+                        CreateSelectMenuOption::new(*label, *value)
+                            .default_selection(arrangement_value.as_deref().unwrap_or("Inherit") == *value)
+                    }).collect(),
+                },
+            )
+        ));
+        components.push(CreateActionRow::Buttons(vec![
+            CreateButton::new("override_enable")
+                .label(format!("자동 확대 : {}", override_state_name(channel_override.auto_magnitute_enable)))
+                .style(ButtonStyle::Primary),
+            CreateButton::new("override_merge")
+                .label(format!("이모지 합치기 : {}", override_state_name(channel_override.merge_emoji_enable)))
+                .style(ButtonStyle::Primary),
+            CreateButton::new("override_webp")
+                .label(format!("WebP 자동 변환 : {}", override_state_name(channel_override.auto_transfer_webp)))
+                .style(ButtonStyle::Primary),
+            CreateButton::new("override_clear")
+                .label("이 채널 설정 지우기")
+                .style(ButtonStyle::Danger),
+        ]));
+    }
+
+    components.push(CreateActionRow::Buttons(vec![
+        CreateButton::new("override_done")
+            .label("완료")
+            .style(ButtonStyle::Primary)
+    ]));
+    components
+}
+
+//DB에는 i64 배열로 저장함
+fn role_ids(roles: &[RoleId]) -> Vec<i64> {
+    roles.iter().map(|role| role.get() as i64).collect()
+}
+
+fn role_mentions(roles: &[RoleId]) -> String {
+    match roles.len() {
+        0 => "없음".to_string(),
+        _ => roles.iter().map(|role| format!("<@&{}>", role)).collect::<Vec<_>>().join(", "),
+    }
+}
+
+fn role_filter_summary(gclock: &GuildConfig) -> String {
+    format!(
+        "허용 : {}\n제외 : {}",
+        match gclock.auto_allowed_roles.len() {
+            0 => "모두".to_string(),
+            _ => role_mentions(&gclock.auto_allowed_roles),
+        },
+        role_mentions(&gclock.auto_denied_roles)
+    )
+}
+
+fn role_filter_notice(gclock: &GuildConfig) -> String {
+    format!("
+**__자동 이모지 확대를 쓸 수 있는 역할을 선택해주세요.__**\n
+허용 역할을 고르면 그 중 하나라도 가진 사람만 자동으로 확대합니다. 비워두면 모두 확대합니다.\n
+제외 역할 중 하나라도 가진 사람은 허용 역할이 있어도 확대하지 않습니다.\n
+{}\n
+다 골랐으면 \"완료\"를 눌러주세요.
+    ", role_filter_summary(gclock))
+}
+
+fn role_filter_component(gclock: &GuildConfig) -> Vec<CreateActionRow> {
+    vec![
+        CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                "role_allow",
+                CreateSelectMenuKind::Role { default_roles: Some(gclock.auto_allowed_roles.clone()) },
+            )
+            .placeholder("허용 역할 고르기 (비우면 모두)")
+            .min_values(0)
+            .max_values(25)
+        ),
+        CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                "role_deny",
+                CreateSelectMenuKind::Role { default_roles: Some(gclock.auto_denied_roles.clone()) },
+            )
+            .placeholder("제외 역할 고르기")
+            .min_values(0)
+            .max_values(25)
+        ),
+        CreateActionRow::Buttons(vec![
+            CreateButton::new("role_clear")
+                .label("역할 설정 지우기")
+                .style(ButtonStyle::Danger),
+            CreateButton::new("role_done")
+                .label("완료")
+                .style(ButtonStyle::Primary),
+        ]),
+    ]
+}
+
+fn expire_name(seconds: u32) -> String {
+    match seconds {
+        s if s >= 3600 && s % 3600 == 0 => format!("{}시간", s / 3600),
+        s if s >= 60 && s % 60 == 0 => format!("{}분", s / 60),
+        s => format!("{}초", s),
+    }
+}
+
+fn repost_expire_notice() -> String {
+    "
+**__다시 보낸 이모지를 얼마 뒤에 자동으로 지울지 선택해주세요.__**\n
+\"**안 지움**\"이 기본입니다. 봇이 다시 켜져도 남은 시간이 지나면 지웁니다.\n
+자동 삭제와 상관없이, 이모지를 보낸 사람과 메시지 관리 권한이 있는 사람은 ❌ 버튼으로 바로 지울 수 있습니다.\n
+    ".to_string()
+}
+
+fn repost_expire_component() -> Vec<CreateActionRow> {
+    vec![
+        CreateActionRow::Buttons(
+            [0, 30, 60, 600, 3600].iter().map(|seconds| {
+                CreateButton::new(format!("repostexpire_{}", seconds))
+                    .label(match seconds {
+                        0 => "안 지움".to_string(),
+                        seconds => expire_name(*seconds),
+                    })
+                    .style(ButtonStyle::Secondary)
+            }).collect()
+        )
+    ]
+}
+
+fn layout_name(layout: EmojiLayout) -> &'static str {
+    match layout {
+        EmojiLayout::Row => "가로 한 줄",
+        EmojiLayout::Column => "세로 한 줄",
+        EmojiLayout::Grid => "격자",
+    }
+}
+
+fn layout_notice(gclock: &GuildConfig) -> String {
+    format!("
+**__이모지를 3개 이상 보냈을 때 합칠 방법을 선택해주세요.__**\n
+\"**가로 한 줄**\", \"**세로 한 줄**\", \"**격자**\" 중 하나로 배치하고, 이모지 사이 간격을 정할 수 있습니다.\n
+현재 설정 : {}, 간격 {}px\n
+다 골랐으면 \"완료\"를 눌러주세요.
+    ", layout_name(gclock.multi_emoji_layout), gclock.multi_emoji_spacing)
+}
+
+fn layout_component() -> Vec<CreateActionRow> {
+    vec![
+        CreateActionRow::Buttons(
+            vec![
+                CreateButton::new("multilayout_row")
+                    .label("가로 한 줄")
+                    .style(ButtonStyle::Secondary),
+                CreateButton::new("multilayout_column")
+                    .label("세로 한 줄")
+                    .style(ButtonStyle::Secondary),
+                CreateButton::new("multilayout_grid")
+                    .label("격자")
+                    .style(ButtonStyle::Secondary),
+            ]),
+        CreateActionRow::Buttons(
+            [0, 4, 8, 16, 32].iter().map(|spacing| {
+                CreateButton::new(format!("multispacing_{}", spacing))
+                    .label(format!("간격 {}px", spacing))
+                    .style(ButtonStyle::Secondary)
+            }).collect()
+        ),
+        CreateActionRow::Buttons(
+            vec![
+                CreateButton::new("multilayout_done")
+                    .label("완료")
+                    .style(ButtonStyle::Primary)
+            ]
+        )
+    ]
+}
+
+fn double_layout_summary(layout: &DoubleEmojiLayout) -> String {
+    let background = match layout.background {
+        Some(_) => format!("배경 {}", DoubleEmojiLayout::background_to_string(&layout.background)),
+        None => "배경 투명".to_string(),
+    };
+    match layout.arrangement {
+        DoubleEmojiArrangement::Overlay => format!(
+            "겹치기, 크기 {}%, 위치 ({}%, {}%), {}",
+            layout.overlay_scale, layout.overlay_offset.0, layout.overlay_offset.1, background
+        ),
+        arrangement => format!(
+            "{}, {} 정렬, 간격 {}px, {}",
+            match arrangement {
+                DoubleEmojiArrangement::Vertical => "세로",
+                _ => "가로",
+            },
+            match layout.alignment {
+                EmojiAlignment::Start => "위/왼쪽",
+                EmojiAlignment::Center => "가운데",
+                EmojiAlignment::End => "아래/오른쪽",
+            },
+            layout.gap,
+            background
+        ),
+    }
+}
+
+fn double_layout_notice(gclock: &GuildConfig) -> String {
+    format!("
+**__이모지를 2개 보냈을 때 합칠 방법을 선택해주세요.__**\n
+\"**가로**\", \"**세로**\"는 두 이모지를 나란히 놓고, 크기가 다르면 정렬 기준에 맞춰 줄을 맞춥니다.\n
+\"**겹치기**\"는 두 번째 이모지를 첫 번째 이모지 위에 작게 올립니다. 아래 줄에서 크기와 위치를 고를 수 있습니다.\n
+현재 설정 : {}\n
+다 골랐으면 \"완료\"를 눌러주세요.
+    ", double_layout_summary(&gclock.double_emoji_layout))
+}
+
+fn double_layout_component() -> Vec<CreateActionRow> {
+    vec![
+        CreateActionRow::Buttons(
+            vec![
+                CreateButton::new("doublearrange_Horizontal")
+                    .label("가로")
+                    .style(ButtonStyle::Secondary),
+                CreateButton::new("doublearrange_Vertical")
+                    .label("세로")
+                    .style(ButtonStyle::Secondary),
+                CreateButton::new("doublearrange_Overlay")
+                    .label("겹치기")
+                    .style(ButtonStyle::Secondary),
+                CreateButton::new("doublelayout_done")
+                    .label("완료")
+                    .style(ButtonStyle::Primary),
+            ]),
+        CreateActionRow::Buttons(
+            [("Start", "위/왼쪽 정렬"), ("Center", "가운데 정렬"), ("End", "아래/오른쪽 정렬")].iter().map(|(alignment, label)| {
+                CreateButton::new(format!("doublealign_{}", alignment))
+                    .label(*label)
+                    .style(ButtonStyle::Secondary)
+            }).collect()
+        ),
+        CreateActionRow::Buttons(
+            [0, 4, 8, 16, 32].iter().map(|gap| {
+                CreateButton::new(format!("doublegap_{}", gap))
+                    .label(format!("간격 {}px", gap))
+                    .style(ButtonStyle::Secondary)
+            }).collect()
+        ),
+        CreateActionRow::Buttons(
+            [("None", "배경 투명"), ("#FFFFFF", "흰 배경"), ("#000000", "검은 배경"), ("#313338", "디스코드 배경")].iter().map(|(background, label)| {
+                CreateButton::new(format!("doublebg_{}", background))
+                    .label(*label)
+                    .style(ButtonStyle::Secondary)
+            }).collect()
+        ),
+        CreateActionRow::Buttons(
+            [("50_25_25", "겹치기: 오른쪽 아래"), ("50_-25_25", "겹치기: 왼쪽 아래"), ("50_25_-25", "겹치기: 오른쪽 위"), ("60_0_0", "겹치기: 가운데"), ("100_0_0", "겹치기: 통째로")].iter().map(|(overlay, label)| {
+                CreateButton::new(format!("doubleoverlay_{}", overlay))
+                    .label(*label)
+                    .style(ButtonStyle::Secondary)
+            }).collect()
+        ),
+    ]
 }
 
 fn size_notice() -> String {
@@ -263,8 +1223,8 @@ fn size_notice() -> String {
 **__설정하고싶은 크기를 선택해주세요.__**\n\n
 순서대로 \"**절라 짝게**\", \"**작게**\", \"**중간**\",\"**큼**\", \"**절라 큼**\" 입니다.\n
 \"**작게**\"는 일반 이모지 사이즈, \"**중간**\" 일반 스티커 사이즈입니다.\n
-\"자동\"은 이모지의 원래 크기에 따라 자동으로 바꿔집니다.\n
-즉, 이미지 크기 변환 과정이 없어 전송 속도가 가장 빠릅니다.\n
+\"자동\"은 이모지의 원래 크기를 보고 너무 작으면 키우고, 너무 크면 줄이고, 적당하면 그대로 보냅니다.\n
+범위는 \"자동 크기 범위 설정하기\"에서 바꿀 수 있습니다.\n
     ".to_string()
 }
 
@@ -328,6 +1288,66 @@ fn config_complete_component(custom_id: &str, gclock: &mut GuildConfig) -> Creat
                         } 
                     }
                 ).components(vec![]).embeds(vec![]),
+        "webhook_repost_enabled" => 
+            CreateInteractionResponseMessage::default()
+                .content(
+                    match gclock.webhook_repost { 
+                        false => {
+                            gclock.webhook_repost = true;
+                            "웹후크로 다시 보내기가 켜졌습니다.\n이제 확대한 이모지를 보낸 사람의 이름과 프로필 사진으로 보냅니다.\n봇에게 웹후크 관리하기 권한이 없는 채널에서는 원래처럼 보냅니다." 
+                        },
+                        true => {
+                            gclock.webhook_repost = false;
+                            "웹후크로 다시 보내기가 꺼졌습니다."
+                        } 
+                    }
+                ).components(vec![]).embeds(vec![]),
+        "magnify_replies_enabled" => 
+            CreateInteractionResponseMessage::default()
+                .content(
+                    match gclock.magnify_replies { 
+                        false => {
+                            gclock.magnify_replies = true;
+                            "답장 이모지 확대가 켜졌습니다.\n이제 답장으로 보낸 이모지도 확대해서, 같은 메시지에 답장으로 다시 보냅니다." 
+                        },
+                        true => {
+                            gclock.magnify_replies = false;
+                            "답장 이모지 확대가 꺼졌습니다."
+                        } 
+                    }
+                ).components(vec![]).embeds(vec![]),
+        "set_multi_emoji_layout" => CreateInteractionResponseMessage::default()
+                .content(layout_notice(gclock))
+                .components(layout_component())
+                .embeds(vec![]),
+        "set_resize_filter" => CreateInteractionResponseMessage::default()
+                .content(filter_notice())
+                .components(filter_component())
+                .embeds(vec![]),
+        "set_apng_output_format" => CreateInteractionResponseMessage::default()
+                .content(apng_output_notice())
+                .components(apng_output_component())
+                .embeds(vec![]),
+        "set_double_emoji_layout" => CreateInteractionResponseMessage::default()
+                .content(double_layout_notice(gclock))
+                .components(double_layout_component())
+                .embeds(vec![]),
+        "set_repost_expire" => CreateInteractionResponseMessage::default()
+                .content(repost_expire_notice())
+                .components(repost_expire_component())
+                .embeds(vec![]),
+        "set_channel_overrides" => CreateInteractionResponseMessage::default()
+                .content(channel_override_notice(gclock, None))
+                .components(channel_override_component(gclock, None))
+                .embeds(vec![]),
+        "set_role_filter" => CreateInteractionResponseMessage::default()
+                .content(role_filter_notice(gclock))
+                .components(role_filter_component(gclock))
+                .embeds(vec![]),
+        "set_auto_size_bounds" => CreateInteractionResponseMessage::default()
+                .content(auto_bounds_notice(gclock))
+                .components(auto_bounds_component())
+                .embeds(vec![]),
         _ => CreateInteractionResponseMessage::default()
                 .content(size_notice())
                 .components(size_component())
@@ -58,8 +58,32 @@ fn get_help_embed() -> Vec<CreateEmbed> {
             .field(
                 "/config 명령어로 봇 설정하기",
                 "/config 명령어로 이모지 봇 설정을 할 수 있습니다.".to_owned() + "\n" +
-                "- \"자동 이모지 크기 조절 켜거나 끄기\" : 켜져있으면 사용자가 이모지를 보낼 때마다 설정된 크기로 이모지를 확대합니다." + "\n" +
-                "- \"크기 기본값 설정하기\" : 자동 이모지 크기 조절이 켜져있을 때의 확대값을 설정합니다.",
+                "- \"자동 이모지 크기 조절 켜거나 끄기\" : 켜져있으면 사용자가 이모지를 보낼 때마다 설정된 크기로 이모지를 확대합니다. 스티커만 보내도 같은 크기로 확대하고, 움직이는 스티커는 설정한 형식(기본 gif)으로 바꿉니다. ||로 가린 이모지는 결과도 스포일러로 가려서 보냅니다." + "\n" +
+                "- \"웹후크로 다시 보내기 켜거나 끄기\" : 켜져있으면 확대한 이모지를 \"**이름** :\" 줄 없이 보낸 사람의 이름과 프로필 사진으로 보냅니다. 스레드에서도 되고, 봇에게 웹후크 관리하기 권한이 없으면 원래처럼 보냅니다." + "\n" +
+                "- \"답장 이모지 확대 켜거나 끄기\" : 켜져있으면 답장으로 보낸 이모지도 확대해서 같은 메시지에 답장으로 보냅니다. 원래 답장의 멘션 여부도 그대로 따릅니다." + "\n" +
+                "- \"크기 기본값 설정하기\" : 자동 이모지 크기 조절이 켜져있을 때의 확대값을 설정합니다." + "\n" +
+                "- \"여러 이모지 배치 설정하기\" : 이모지만 3개 이상(최대 9개) 보냈을 때 한 장으로 합치는 배치와 간격을 설정합니다." + "\n" +
+                "- \"두 이모지 배치 설정하기\" : 이모지만 2개 보냈을 때 가로, 세로로 나란히 놓을지 겹칠지와 정렬, 간격, 배경색, 겹칠 크기와 위치를 설정합니다." + "\n" +
+                "- \"크기 조절 필터 설정하기\" : 이모지 크기를 바꿀 때 쓰는 필터를 설정합니다. 도트 이모지는 Nearest나 자동을 추천합니다." + "\n" +
+                "- \"자동 크기 범위 설정하기\" : 크기 기본값이 자동일 때, 이 범위보다 작은 이모지는 키우고 큰 이모지는 줄입니다. 스티커는 줄이기만 하고, 여러 이모지를 합칠 때는 개수에 맞춰 범위를 좁힙니다. 필터가 자동이면 키울 때와 줄일 때 알맞은 필터를 고릅니다." + "\n" +
+                "- \"APNG 출력 형식 설정하기\" : 움직이는 APNG 스티커를 GIF, WebP, APNG 중 어떤 형식으로 바꿔 보낼지 설정합니다. WebP 자동 변환이 켜져있으면 채팅에 올라온 APNG도 바꿉니다.",
+                false
+            )
+            .field(
+                "다시 보낸 이모지 지우기",
+                "확대해서 다시 보낸 이모지에는 ❌ 버튼이 달립니다. 이모지를 보낸 사람과 메시지 관리 권한이 있는 사람만 누를 수 있습니다.".to_owned() + "\n" +
+                "- \"자동 삭제 시간 설정하기\" : 다시 보낸 이모지를 정한 시간 뒤에 자동으로 지웁니다. 봇이 다시 켜져도 그대로 지웁니다.",
+                false
+            )
+            .field(
+                "채널별로 다르게 설정하기",
+                "- \"채널별 설정하기\" : 고른 채널이나 카테고리에서만 자동 확대, 크기, 이모지 합치기, 두 이모지 배치, WebP 자동 변환을 다르게 설정합니다.".to_owned() + "\n" +
+                "정하지 않은 항목은 카테고리, 서버 설정 순서로 따르고, 스레드는 부모 채널 설정을 따릅니다.",
+                false
+            )
+            .field(
+                "역할별로 자동 확대 제한하기",
+                "- \"자동 확대 역할 설정하기\" : 허용 역할을 고르면 그 역할이 있는 사람만, 제외 역할을 고르면 그 역할이 없는 사람만 자동으로 확대합니다. 제외 역할이 허용 역할보다 먼저입니다.",
                 false
             )
             .image("https://media.discordapp.net/attachments/1258021816283304027/1258022587473199249/Screenshot_2024-07-03_at_20.32.29.png"),
@@ -70,9 +94,51 @@ fn get_help_embed() -> Vec<CreateEmbed> {
                 "/send 명령어로 원하는 크기로 이모지 전송하기",
                 "/send 명령어로 원하는 크기로 이모지를 확대 혹은 축소해 전송할 수 있습니다.".to_owned() + "\n" +
                 "니트로가 없는 사용자도 입력값으로 이모지 이름(이미지 예시로는 :kalbrr:)을 입력하면 움짤 이모지를 전송할 수 있습니다." + "\n" +
-                "움짤 이모지 크기 조절 기능은 현재는 구현되어있지 않지만, 추후 업데이트 예정입니다.",
+                "움짤 이모지도 프레임마다 크기를 조절해서 원하는 크기로 전송할 수 있습니다." + "\n" +
+                "size 대신 width/height(px)나 scale(배율)로 크기를 직접 정할 수 있고, fit으로 비율이 다른 이모지를 맞추는 방법을 고를 수 있습니다." + "\n" +
+                "emoji에 😂, 👍🏽, 🇰🇷 같은 유니코드 이모지를 넣어도 되고, 자동 이모지 크기 조절도 유니코드 이모지 하나만 보낸 메시지를 확대합니다. (Twemoji 그림, CC-BY 4.0)" + "\n" +
+                "spoiler를 켜거나 이모지를 ||로 가려서 넣으면 결과도 스포일러로 가려서 보냅니다." + "\n" +
+                "emoji 대신 sticker에 서버 스티커 이름을 넣으면 스티커도 보낼 수 있고, 움직이는 스티커는 서버에서 설정한 형식(기본 gif)으로 바뀝니다." + "\n" +
+                "결과가 서버 업로드 제한보다 크면 GIF로 바꿔보고, 색 수, 프레임 수, 크기 순으로 줄여서 보내고 무엇을 바꿨는지 알려줍니다. 여러 이모지를 합친 결과나 자동 변환한 움짤도 똑같이 맞춥니다.",
+                false
+            )
+            .image("https://media.discordapp.net/attachments/1258021816283304027/1258023032681922591/Screenshot_2024-07-03_at_20.34.16.png"),
+        //4페이지 : /transform 명령어
+        CreateEmbed::default()
+            .title("봇 사용법 4 : /transform 명령어")
+            .field(
+                "/transform 명령어로 이모지에 효과 주기",
+                "/transform 명령어로 이모지를 뒤집거나 돌리고, 색을 바꿔서 전송할 수 있습니다.".to_owned() + "\n" +
+                "effects에 효과를 순서대로 적으면 한 번에 이어서 적용합니다. (예: fliph rotate:45 hue:120)" + "\n" +
+                "- fliph, flipv : 좌우, 상하 뒤집기" + "\n" +
+                "- rotate:각도 : 시계 방향으로 회전 (기본 90도)" + "\n" +
+                "- gray, invert : 흑백, 색 반전" + "\n" +
+                "- hue:각도, saturate:배율 : 색조 돌리기, 채도 조절 (0이면 흑백)" + "\n" +
+                "- pixelate:칸 크기, blur:세기 : 모자이크, 흐리게" + "\n" +
+                "- deepfry : 바삭하게 튀기기" + "\n" +
+                "움짤 이모지는 프레임마다 같은 효과를 줘서 gif로 보냅니다.",
+                false
+            ),
+        //5페이지 : /meme 명령어
+        CreateEmbed::default()
+            .title("봇 사용법 5 : /meme 명령어")
+            .field(
+                "/meme 명령어로 이모지 움짤 만들기",
+                "/meme 명령어로 정적 이모지를 쓰다듬기, 흔들기, 돌리기, 통통 튀기, 확대하기, 무지개 움짤로 만들 수 있습니다.".to_owned() + "\n" +
+                "intensity(1~10)로 움직임의 세기를, delay(1/100초)로 프레임 간격을 정할 수 있습니다." + "\n" +
+                "움짤 이모지를 넣으면 첫 프레임으로 만듭니다.",
+                false
+            ),
+        //6페이지 : /caption 명령어
+        CreateEmbed::default()
+            .title("봇 사용법 6 : /caption 명령어")
+            .field(
+                "/caption 명령어로 이모지에 문구 넣기",
+                "/caption 명령어로 이모지 위아래에 짤 같은 문구를 넣을 수 있습니다.".to_owned() + "\n" +
+                "top, bottom에 위아래 문구를 적고, placement로 이모지 위에 겹칠지 바깥에 흰 띠를 붙일지 고를 수 있습니다." + "\n" +
+                "문구가 길면 줄을 나누고 글자 크기를 알아서 줄이며, 한글도 쓸 수 있습니다." + "\n" +
+                "움짤 이모지는 프레임마다 같은 문구를 넣어서 gif로 보냅니다.",
                 false
             )
-            .image("https://media.discordapp.net/attachments/1258021816283304027/1258023032681922591/Screenshot_2024-07-03_at_20.34.16.png")
     ]
 }
@@ -0,0 +1,140 @@
+use serenity::{
+    async_trait,
+    builder::{CreateCommand, CreateCommandOption, EditInteractionResponse},
+    client::Context,
+    model::{
+        application::{CommandDataOption, CommandInteraction, CommandOptionType},
+        prelude::Message,
+    },
+    Error,
+};
+
+use crate::command_handler::explicit_command_list::CommandInterface;
+use crate::utils::meme::{
+    generate_meme, MemeParams, MemeTemplate, MAX_MEME_DELAY, MAX_MEME_INTENSITY, MIN_MEME_DELAY,
+    MIN_MEME_INTENSITY,
+};
+use crate::utils::scene_core::emoji_format_filter;
+use crate::utils::upload_limit::guild_upload_limit;
+
+struct MakeMeme;
+
+pub fn get_command() -> Box<dyn CommandInterface + Sync + Send> {
+    Box::new(MakeMeme)
+}
+
+#[async_trait]
+impl CommandInterface for MakeMeme {
+    async fn run(
+        &self,
+        ctx: &Context,
+        options: &[CommandDataOption],
+        command: &CommandInteraction,
+    ) -> Result<Message, Error> {
+        let find_option = |name: &str| {
+            options
+                .iter()
+                .find(|option| option.name == name)
+                .map(|option| &option.value)
+        };
+
+        let img_url = match emoji_format_filter(
+            find_option("emoji")
+                .and_then(|value| value.as_str())
+                .unwrap_or_default(),
+        ) {
+            Ok((_, img_url)) => img_url,
+            Err(()) => {
+                return command
+                    .edit_response(
+                        &ctx.http,
+                        EditInteractionResponse::default()
+                            .content("제대로 된 이모지를 입력해주세요"),
+                    )
+                    .await;
+            }
+        };
+
+        let template = MemeTemplate::string_to_value(
+            find_option("template")
+                .and_then(|value| value.as_str())
+                .unwrap_or("PetPet"),
+        );
+        let mut params = MemeParams::default();
+        if let Some(intensity) = find_option("intensity").and_then(|value| value.as_i64()) {
+            params.intensity = intensity as u32;
+        }
+        params.delay = find_option("delay")
+            .and_then(|value| value.as_i64())
+            .map(|delay| delay as u32);
+
+        let generated = generate_meme(
+            img_url.as_ref(),
+            template,
+            params,
+            guild_upload_limit(ctx, command.guild_id),
+        )
+        .await;
+
+        command
+            .edit_response(
+                &ctx.http,
+                match generated {
+                    Ok((generated, None)) => {
+                        EditInteractionResponse::default().new_attachment(generated)
+                    }
+                    Ok((generated, Some(compromise))) => EditInteractionResponse::default()
+                        .content(compromise.get_notice_message())
+                        .new_attachment(generated),
+                    Err(why) => EditInteractionResponse::default().content(why.get_error_message()),
+                },
+            )
+            .await
+    }
+
+    fn name(&self) -> String {
+        String::from("meme")
+    }
+
+    fn register(&self) -> CreateCommand {
+        let options = Vec::from([
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "template",
+                "만들 움짤 종류를 골라주세요.",
+            )
+            .add_string_choice("쓰다듬기", "PetPet")
+            .add_string_choice("흔들기", "Shake")
+            .add_string_choice("돌리기", "Spin")
+            .add_string_choice("통통 튀기", "Bounce")
+            .add_string_choice("확대하기", "ZoomIn")
+            .add_string_choice("무지개", "Rainbow")
+            .required(true),
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "emoji",
+                "움짤로 만들 이모지를 선택해주세요.",
+            )
+            .required(true),
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "intensity",
+                "효과의 세기를 정해주세요. 클수록 많이 움직입니다. (기본 5)",
+            )
+            .min_int_value(MIN_MEME_INTENSITY as u64)
+            .max_int_value(MAX_MEME_INTENSITY as u64)
+            .required(false),
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "delay",
+                "프레임 사이 간격을 1/100초 단위로 정해주세요. 작을수록 빠릅니다.",
+            )
+            .min_int_value(MIN_MEME_DELAY as u64)
+            .max_int_value(MAX_MEME_DELAY as u64)
+            .required(false),
+        ]);
+        CreateCommand::new(self.name())
+            .description("이모지로 쓰다듬기, 흔들기 같은 움짤을 만들어 전송합니다")
+            .set_options(options)
+    }
+}
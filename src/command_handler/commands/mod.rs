@@ -1,3 +1,6 @@
+pub mod caption;
 pub mod config;
 pub mod help;
+pub mod meme;
 pub mod send;
+pub mod transform;
@@ -3,18 +3,33 @@ use serenity::{
     builder::{CreateCommand, CreateCommandOption, EditInteractionResponse},
     client::Context,
     model::{
-        application::{CommandDataOption, CommandInteraction, CommandOptionType},
-        permissions::Permissions,
+        application::{
+            CommandDataOption, CommandDataOptionValue, CommandInteraction, CommandOptionType,
+        },
         prelude::Message,
     },
     Error,
 };
 
 use crate::command_handler::explicit_command_list::CommandInterface;
-use crate::utils::scene_core::{emoji_format_filter, get_resized_image, ImageSize};
+use crate::utils::emoji_tokenizer::{only_emojis, tokenize, EmojiToken};
+use crate::utils::scene_core::{
+    get_resized_image, get_twemoji_image, spoiler_attachment, AnimatedFormat, FitMode, ImageSize,
+    ResizeFilter, MAX_CUSTOM_SIZE, MAX_SCALE_PERCENT, MIN_SCALE_PERCENT,
+};
+use crate::utils::upload_limit::guild_upload_limit;
+use crate::GlobalGuildConfigs;
+
+use std::num::NonZeroU64;
+use twemoji_assets::svg::SvgTwemojiAsset;
 
 struct SendSizedEmoji;
 
+enum EmojiSource {
+    Url(String),
+    Twemoji(&'static SvgTwemojiAsset),
+}
+
 pub fn get_command() -> Box<dyn CommandInterface + Sync + Send> {
     Box::new(SendSizedEmoji)
 }
@@ -27,49 +42,113 @@ impl CommandInterface for SendSizedEmoji {
         options: &[CommandDataOption],
         command: &CommandInteraction,
     ) -> Result<Message, Error> {
-        let emoji = options[0].value.as_str();
-        let size_num = options[1].value.as_i64();
-
-        if size_num.is_none() || emoji.is_none() {
-            return command
-                .edit_response(
-                    &ctx.http,
-                    EditInteractionResponse::default().content("명령어를 조건에 맞게 입력해주세요"),
-                )
-                .await;
-        }
+        let emoji = find_option(options, "emoji").and_then(|value| value.as_str());
+        let sticker = find_option(options, "sticker").and_then(|value| value.as_str());
 
-        let filtered = emoji_format_filter(emoji.unwrap());
-        if filtered.is_err() {
-            return command
-                .edit_response(
-                    &ctx.http,
-                    EditInteractionResponse::default().content("제대로 된 이모지를 입력해주세요"),
-                )
-                .await;
-        }
-        let (is_png, img_url) = filtered.unwrap();
-        let resized_emoji = get_resized_image(
-            ctx,
-            img_url.as_ref(),
-            &(if !is_png {
-                ImageSize::Auto
-            } else {
-                match size_num.unwrap() {
-                    0 => ImageSize::HyperTechniqueOfLisaSuFinger,
-                    1 => ImageSize::Small,
-                    2 => ImageSize::Medium,
-                    3 => ImageSize::Large,
-                    _ => ImageSize::HyperSuperUltraSexFeaturedFuckingLarge,
+        let tokens = emoji.map(tokenize).unwrap_or_default();
+        let parsed = only_emojis(&tokens);
+        //입력한 이모지를 ||로 가렸으면 spoiler를 안 골라도 가려서 보냄
+        let spoiler = find_option(options, "spoiler")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false)
+            || parsed.as_ref().is_some_and(|only| only.spoiler);
+
+        //서버 이모지는 주소로 가져오고, 유니코드 이모지는 같이 넣어둔 Twemoji로 그림
+        let source = match (emoji, sticker) {
+            (Some(_), _) => match parsed.as_ref().map(|only| only.emojis.as_slice()) {
+                Some([EmojiToken::Custom(emoji)]) => EmojiSource::Url(emoji.url()),
+                Some([EmojiToken::Unicode { emoji, .. }]) => EmojiSource::Twemoji(emoji),
+                _ => {
+                    return command
+                        .edit_response(
+                            &ctx.http,
+                            EditInteractionResponse::default()
+                                .content("제대로 된 이모지를 입력해주세요"),
+                        )
+                        .await;
                 }
-            }),
-        )
-        .await;
+            },
+            (None, Some(sticker)) => match guild_sticker_url(ctx, command, sticker).await {
+                Some(img_url) => EmojiSource::Url(img_url),
+                None => {
+                    return command
+                        .edit_response(
+                            &ctx.http,
+                            EditInteractionResponse::default()
+                                .content("이 서버에서 그 이름의 스티커를 찾을 수 없습니다"),
+                        )
+                        .await;
+                }
+            },
+            (None, None) => {
+                return command
+                    .edit_response(
+                        &ctx.http,
+                        EditInteractionResponse::default()
+                            .content("이모지나 스티커 중 하나는 입력해주세요"),
+                    )
+                    .await;
+            }
+        };
+
+        let img_size = match requested_size(options) {
+            Ok(img_size) => img_size,
+            Err(why) => {
+                return command
+                    .edit_response(&ctx.http, EditInteractionResponse::default().content(why))
+                    .await;
+            }
+        };
+
+        //필터를 따로 안 골랐으면 서버 설정을 따름. APNG 출력 형식은 항상 서버 설정
+        let (guild_filter, output) = guild_image_settings(ctx, command).await;
+        let filter = match find_option(options, "filter").and_then(|value| value.as_str()) {
+            Some(filter) => ResizeFilter::string_to_value(filter),
+            None => guild_filter,
+        };
+        let fit = FitMode::string_to_value(
+            find_option(options, "fit")
+                .and_then(|value| value.as_str())
+                .unwrap_or("Contain"),
+        );
+
+        let upload_limit = guild_upload_limit(ctx, command.guild_id);
+        let resized_emoji = match source {
+            EmojiSource::Twemoji(emoji) => {
+                get_twemoji_image(emoji, &img_size, &fit, upload_limit).await
+            }
+            EmojiSource::Url(img_url) => {
+                get_resized_image(
+                    ctx,
+                    &img_url,
+                    &img_size,
+                    &filter,
+                    &fit,
+                    &output,
+                    upload_limit,
+                )
+                .await
+            }
+        };
+
+        let resized_emoji = resized_emoji.map(|(resized_emoji, compromise)| match spoiler {
+            true => (spoiler_attachment(resized_emoji), compromise),
+            false => (resized_emoji, compromise),
+        });
 
         command
             .edit_response(
                 &ctx.http,
-                EditInteractionResponse::default().new_attachment(resized_emoji),
+                match resized_emoji {
+                    Ok((resized_emoji, None)) => {
+                        EditInteractionResponse::default().new_attachment(resized_emoji)
+                    }
+                    //업로드 제한 때문에 화질을 낮췄으면 뭘 포기했는지 같이 보여줌
+                    Ok((resized_emoji, Some(compromise))) => EditInteractionResponse::default()
+                        .content(compromise.get_notice_message())
+                        .new_attachment(resized_emoji),
+                    Err(why) => EditInteractionResponse::default().content(why.get_error_message()),
+                },
             )
             .await
     }
@@ -83,9 +162,15 @@ impl CommandInterface for SendSizedEmoji {
             CreateCommandOption::new(
                 CommandOptionType::String,
                 "emoji",
-                "보낼 이모지를 선택해주세요.",
+                "보낼 이모지를 선택해주세요. 서버 이모지와 유니코드 이모지 둘 다 됩니다.",
+            )
+            .required(false),
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "sticker",
+                "이모지 대신 보낼 이 서버의 스티커 이름을 입력해주세요.",
             )
-            .required(true),
+            .required(false),
             CreateCommandOption::new(
                 CommandOptionType::Integer,
                 "size",
@@ -93,10 +178,139 @@ impl CommandInterface for SendSizedEmoji {
             )
             .min_int_value(0)
             .max_int_value(4)
-            .required(true),
+            .required(false),
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "width",
+                "가로 크기(px)를 직접 정합니다. 세로만 비워두면 원본 비율대로 맞춥니다.",
+            )
+            .min_int_value(1)
+            .max_int_value(MAX_CUSTOM_SIZE as u64)
+            .required(false),
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "height",
+                "세로 크기(px)를 직접 정합니다. 가로만 비워두면 원본 비율대로 맞춥니다.",
+            )
+            .min_int_value(1)
+            .max_int_value(MAX_CUSTOM_SIZE as u64)
+            .required(false),
+            CreateCommandOption::new(
+                CommandOptionType::Number,
+                "scale",
+                "원본 대비 배율을 정합니다. (예: 2, 3.5)",
+            )
+            .min_number_value(MIN_SCALE_PERCENT as f64 / 100.0)
+            .max_number_value(MAX_SCALE_PERCENT as f64 / 100.0)
+            .required(false),
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "spoiler",
+                "결과 이미지를 스포일러로 가려서 보냅니다.",
+            )
+            .required(false),
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "fit",
+                "가로 세로 비율이 다른 이모지를 맞추는 방법을 골라주세요.",
+            )
+            .add_string_choice("비율 유지 (전부 보이게)", "Contain")
+            .add_string_choice("비율 유지 (꽉 채우고 자르기)", "Cover")
+            .add_string_choice("늘리기", "Stretch")
+            .required(false),
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "filter",
+                "크기를 바꿀 때 쓸 필터를 골라주세요. 안 고르면 서버 설정을 따릅니다.",
+            )
+            .add_string_choice("자동 (도트 이모지 감지)", "Auto")
+            .add_string_choice("Nearest (도트용)", "Nearest")
+            .add_string_choice("Bilinear", "Bilinear")
+            .add_string_choice("CatmullRom", "CatmullRom")
+            .add_string_choice("Mitchell", "Mitchell")
+            .add_string_choice("Lanczos3", "Lanczos3")
+            .required(false),
         ]);
         CreateCommand::new(self.name())
-            .description("이모지의 크기를 변경해 전송합니다")
+            .description("이모지나 스티커의 크기를 변경해 전송합니다")
             .set_options(options)
     }
 }
+
+fn find_option<'a>(
+    options: &'a [CommandDataOption],
+    name: &str,
+) -> Option<&'a CommandDataOptionValue> {
+    options
+        .iter()
+        .find(|option| option.name == name)
+        .map(|option| &option.value)
+}
+
+//scale > width/height > size 순으로 봄
+fn requested_size(options: &[CommandDataOption]) -> Result<ImageSize, &'static str> {
+    let size = find_option(options, "size").and_then(|value| value.as_i64());
+    let width = find_option(options, "width").and_then(|value| value.as_i64());
+    let height = find_option(options, "height").and_then(|value| value.as_i64());
+    let scale = find_option(options, "scale").and_then(|value| value.as_f64());
+
+    if let Some(scale) = scale {
+        if width.is_some() || height.is_some() {
+            return Err("scale과 width/height는 같이 쓸 수 없습니다");
+        }
+        return Ok(ImageSize::Scale((scale * 100.0).round() as u32));
+    }
+
+    if width.is_some() || height.is_some() {
+        let side = |value: Option<i64>| value.unwrap_or(0).clamp(0, MAX_CUSTOM_SIZE as i64) as u32;
+        return Ok(ImageSize::Custom(side(width), side(height)));
+    }
+
+    match size {
+        Some(0) => Ok(ImageSize::HyperTechniqueOfLisaSuFinger),
+        Some(1) => Ok(ImageSize::Small),
+        Some(2) => Ok(ImageSize::Medium),
+        Some(3) => Ok(ImageSize::Large),
+        Some(_) => Ok(ImageSize::HyperSuperUltraSexFeaturedFuckingLarge),
+        None => Err("size, width/height, scale 중 하나는 입력해주세요"),
+    }
+}
+
+//움직이는 스티커(APNG, 로티)는 gif로 바뀌어서 나감
+async fn guild_sticker_url(
+    ctx: &Context,
+    command: &CommandInteraction,
+    name: &str,
+) -> Option<String> {
+    let stickers = command.guild_id?.stickers(&ctx.http).await.ok()?;
+    stickers
+        .into_iter()
+        .find(|sticker| sticker.name == name.trim())?
+        .image_url()
+}
+
+async fn guild_image_settings(
+    ctx: &Context,
+    command: &CommandInteraction,
+) -> (ResizeFilter, AnimatedFormat) {
+    let Some(guild_id) = command.guild_id.and_then(|id| NonZeroU64::new(id.get())) else {
+        return (ResizeFilter::Auto, AnimatedFormat::Gif);
+    };
+
+    let counter_lock = {
+        let data_read = ctx.data.read().await;
+        data_read
+            .get::<GlobalGuildConfigs>()
+            .expect("poisened")
+            .clone()
+    };
+
+    let guilds_config = counter_lock.read().await;
+    match guilds_config.get(&guild_id) {
+        Some(gconfig_lock) => {
+            let gclock = gconfig_lock.lock().await;
+            (gclock.resize_filter, gclock.apng_output_format)
+        }
+        None => (ResizeFilter::Auto, AnimatedFormat::Gif),
+    }
+}
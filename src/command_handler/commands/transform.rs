@@ -0,0 +1,118 @@
+use serenity::{
+    async_trait,
+    builder::{CreateCommand, CreateCommandOption, EditInteractionResponse},
+    client::Context,
+    model::{
+        application::{CommandDataOption, CommandInteraction, CommandOptionType},
+        prelude::Message,
+    },
+    Error,
+};
+
+use crate::command_handler::explicit_command_list::CommandInterface;
+use crate::utils::effects::{parse_effects, transform_emoji};
+use crate::utils::scene_core::emoji_format_filter;
+use crate::utils::upload_limit::guild_upload_limit;
+
+struct TransformEmoji;
+
+pub fn get_command() -> Box<dyn CommandInterface + Sync + Send> {
+    Box::new(TransformEmoji)
+}
+
+#[async_trait]
+impl CommandInterface for TransformEmoji {
+    async fn run(
+        &self,
+        ctx: &Context,
+        options: &[CommandDataOption],
+        command: &CommandInteraction,
+    ) -> Result<Message, Error> {
+        let find_str = |name: &str| {
+            options
+                .iter()
+                .find(|option| option.name == name)
+                .and_then(|option| option.value.as_str())
+                .unwrap_or_default()
+        };
+
+        let img_url = match emoji_format_filter(find_str("emoji")) {
+            Ok((_, img_url)) => img_url,
+            Err(()) => {
+                return command
+                    .edit_response(
+                        &ctx.http,
+                        EditInteractionResponse::default()
+                            .content("제대로 된 이모지를 입력해주세요"),
+                    )
+                    .await;
+            }
+        };
+
+        let effects = match parse_effects(find_str("effects")) {
+            Ok(effects) => effects,
+            Err(why) => {
+                return command
+                    .edit_response(&ctx.http, EditInteractionResponse::default().content(why))
+                    .await;
+            }
+        };
+
+        let transformed = transform_emoji(
+            img_url.as_ref(),
+            &effects,
+            guild_upload_limit(ctx, command.guild_id),
+        )
+        .await;
+
+        //어떤 효과를 어떤 순서로 걸었는지 같이 보여줌
+        let applied = effects
+            .iter()
+            .map(|effect| effect.name())
+            .collect::<Vec<String>>()
+            .join(" > ");
+
+        command
+            .edit_response(
+                &ctx.http,
+                match transformed {
+                    Ok((transformed, None)) => EditInteractionResponse::default()
+                        .content(applied)
+                        .new_attachment(transformed),
+                    Ok((transformed, Some(compromise))) => EditInteractionResponse::default()
+                        .content(format!(
+                            "{}\n-# {}",
+                            applied,
+                            compromise.get_notice_message()
+                        ))
+                        .new_attachment(transformed),
+                    Err(why) => EditInteractionResponse::default().content(why.get_error_message()),
+                },
+            )
+            .await
+    }
+
+    fn name(&self) -> String {
+        String::from("transform")
+    }
+
+    fn register(&self) -> CreateCommand {
+        let options = Vec::from([
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "emoji",
+                "효과를 줄 이모지를 선택해주세요.",
+            )
+            .required(true),
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "effects",
+                "적용할 효과를 순서대로 적어주세요. (예: fliph rotate:45 hue:120 deepfry)",
+            )
+            .required(true),
+        ]);
+        CreateCommand::new(self.name())
+            .description("이모지에 뒤집기, 회전, 색 바꾸기 같은 효과를 줘서 전송합니다")
+            .set_options(options)
+    }
+}
@@ -36,7 +36,7 @@ pub struct CommandList {
 
 impl CommandList {
     pub async fn register_commands(&'static self, gid: GuildId, ctx: &Context) {
-        for (_, cmd) in &self.commands {
+        for cmd in self.commands.values() {
             if let Err(why) = gid.create_command(&ctx.http, cmd.register()).await {
                 error!("Couldn't create application command: {:#?}", why);
             }
@@ -52,6 +52,9 @@ lazy_static! {
             ("send", commands::send::get_command()),
             ("config", commands::config::get_command()),
             ("help", commands::help::get_command()),
+            ("transform", commands::transform::get_command()),
+            ("meme", commands::meme::get_command()),
+            ("caption", commands::caption::get_command()),
             //("webp", commands::webp_transfer::get_command())
         ])
     };
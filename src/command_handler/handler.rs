@@ -8,12 +8,6 @@ use serenity::{
 
 use super::explicit_command_list::COMMAND_LIST;
 
-pub enum CommandType {
-    InitSetting,
-    NormalCommand,
-    MusicCommand,
-}
-
 pub async fn seperate_command(command: CommandInteraction, ctx: &Context) {
     command.defer(&ctx.http).await.unwrap();
 
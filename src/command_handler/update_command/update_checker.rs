@@ -28,7 +28,7 @@ pub async fn check_updates(ctx: &Context, gid: GuildId) -> UpdateStatus {
             guild_cmds_list.sort();
             server_cmds_list.sort();
 
-            return if cmds.len() == 1 {
+            if cmds.len() == 1 {
                 UpdateStatus::FirstSetting
             //개수로 판단하는거는 문제가 있음. 기존꺼 삭제하고 하나 추가하면..
             } else if guild_cmds_list == server_cmds_list {
@@ -43,7 +43,7 @@ pub async fn check_updates(ctx: &Context, gid: GuildId) -> UpdateStatus {
                     }
                 }
                 UpdateStatus::UpdateAvailable(unassigned_commands)
-            };
+            }
         }
         Err(why) => {
             error!(
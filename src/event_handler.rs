@@ -9,6 +9,7 @@ use serenity::{
 use crate::command_handler::handler::seperate_command;
 use crate::command_handler::update_command::update_cmds::update_command;
 use crate::utils::guild_config::GuildConfig;
+use crate::utils::repost_control::{delete_repost, resume_expiries, REPOST_DELETE_ID};
 
 use log::info;
 
@@ -16,7 +17,7 @@ pub struct DiscordEventHandler {
     pub database: mongodb::Client,
 }
 
-use crate::events::{autosend, guild_delete, guildbotadd};
+use crate::events::{autosend, guild_delete, guildbotadd, webp_transfer};
 
 #[async_trait]
 impl EventHandler for DiscordEventHandler {
@@ -28,7 +29,10 @@ impl EventHandler for DiscordEventHandler {
             ctx.cache.guilds().len()
         ))));
 
-        tokio::join!(GuildConfig::boot(&ctx, &(self.database)));
+        tokio::join!(
+            GuildConfig::boot(&ctx, &(self.database)),
+            resume_expiries(&ctx)
+        );
     }
 
     async fn guild_create(&self, ctx: Context, guild: Guild, is_new: Option<bool>) {
@@ -52,17 +56,26 @@ impl EventHandler for DiscordEventHandler {
     async fn message(&self, ctx: Context, msg: Message) {
         tokio::join!(
             autosend::auto_send_transfered_image(&ctx, &msg),
-            autosend::auto_send_double_emoji(&ctx, &msg)
+            autosend::auto_send_unicode_emoji(&ctx, &msg),
+            autosend::auto_send_double_emoji(&ctx, &msg),
+            autosend::auto_send_multi_emoji(&ctx, &msg),
+            autosend::auto_send_sticker(&ctx, &msg),
+            webp_transfer::auto_transfer_webp(&ctx, &msg)
         );
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if let Interaction::Command(command) = interaction {
-            match command.data.name.as_str() {
+        match interaction {
+            Interaction::Command(command) => match command.data.name.as_str() {
                 /* 봇 업데이트 및 처음 초기 세팅 관련 명령어 */
                 "update" => update_command(command, &ctx).await,
                 _ => seperate_command(command, &ctx).await,
+            },
+            //다시 보낸 이모지의 삭제 버튼. 나머지 버튼은 각 명령어가 직접 받음
+            Interaction::Component(component) if component.data.custom_id == REPOST_DELETE_ID => {
+                delete_repost(&ctx, &component).await
             }
+            _ => {}
         }
     }
 }
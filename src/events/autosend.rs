@@ -1,98 +1,121 @@
 use log::error;
-use regex::Regex;
 use serenity::{
-    builder::{CreateAttachment, CreateMessage},
+    builder::{CreateAllowedMentions, CreateAttachment, CreateMessage},
     client::Context,
-    model::channel::Message,
+    model::{
+        channel::Message,
+        id::{ChannelId, GuildId, UserId},
+        sticker::StickerFormatType,
+    },
 };
 
+use crate::utils::emoji_cache::CachedImage;
+use crate::utils::emoji_tokenizer::{only_emojis, tokenize, CustomEmoji, EmojiOnly, EmojiToken};
+use crate::utils::guild_config::{channel_lineage, GuildConfig};
+use crate::utils::repost_control::{delete_button, record_repost};
 use crate::utils::scene_core::ImageSize::{
     self, Auto, HyperTechniqueOfLisaSuFinger, Medium, Small,
 };
+use crate::utils::upload_limit::{fit_to_upload_limit, guild_upload_limit, SizeCompromise};
+use crate::utils::webhook_repost::repost_as_author;
 use crate::GlobalGuildConfigs;
 
-use crate::utils::scene_core::{get_resized_image, merge_two_emojis, EmojiFilter};
+use crate::utils::scene_core::{
+    get_resized_image, get_twemoji_image, merge_emojis, merge_two_emojis, spoiler_attachment,
+    AutoContext, DoubleEmojiArrangement, EmojiFilter, FitMode, ImageTransferError,
+    MAX_MERGED_EMOJIS,
+};
 use std::num::NonZeroU64;
+use twemoji_assets::svg::SvgTwemojiAsset;
 
 impl EmojiFilter for Message {
     fn emoji_format_filter(&self) -> Result<(bool, String), ()> {
-        let msg_content_vec: Vec<&str> = self.content.split(':').collect();
-        let content_regex: Regex = Regex::new(r"^<a?:.+?:\d+>$").unwrap();
-
-        if self.referenced_message.is_some()
-            || self.author.bot
-            || !self.attachments.is_empty()
-            || !content_regex.is_match(&self.content)
-            || !self.mentions.is_empty()
-            || msg_content_vec.len() != 3
-        {
-            return Err(());
+        match custom_emojis(self).as_deref() {
+            Some([emoji]) => Ok((!emoji.animated, emoji.url())),
+            _ => Err(()),
         }
+    }
 
-        let mut id = msg_content_vec[2].to_string();
-        id.pop();
-        let is_png = !self.content.contains("<a:");
+    //이모지 사이 공백은 여러 이모지랑 똑같이 허용
+    fn double_emoji_format_filter(&self) -> Result<(bool, String, String), ()> {
+        match custom_emojis(self).as_deref() {
+            // 둘 다 PNG일때만 true
+            Some([first, second]) => Ok((
+                !first.animated && !second.animated,
+                first.url(),
+                second.url(),
+            )),
+            _ => Err(()),
+        }
+    }
 
-        let img_url = format!(
-            "https://cdn.discordapp.com/emojis/{}.{}",
-            id,
-            if is_png { "webp" } else { "gif" }
-        );
+    //이모지 3개 이상(최대 9개)으로만 이루어진 메시지. 이모지 사이 공백은 허용
+    fn multi_emoji_format_filter(&self) -> Result<(bool, Vec<String>), ()> {
+        let emojis = custom_emojis(self).ok_or(())?;
+        if emojis.len() < 3 || emojis.len() > MAX_MERGED_EMOJIS {
+            return Err(());
+        }
 
-        Ok((is_png, img_url))
+        let is_png = emojis.iter().all(|emoji| !emoji.animated);
+        Ok((is_png, emojis.iter().map(CustomEmoji::url).collect()))
     }
 
-    fn double_emoji_format_filter(&self) -> Result<(bool, String, String), ()> {
-        let content_regex: Regex = Regex::new(r"^(<a?:.+?:\d+>)(<a?:.+?:\d+>)$").unwrap();
-
-        if !content_regex.is_match(&self.content)
+    //글자 없이 스티커 하나만 보낸 메시지
+    fn sticker_format_filter(&self) -> Result<(bool, String), ()> {
+        if !self.content.trim().is_empty()
             || self.author.bot
             || !self.attachments.is_empty()
-            || !self.mentions.is_empty()
-            || self.referenced_message.is_some()
+            || self.sticker_items.len() != 1
         {
             return Err(());
         }
 
-        let captures = content_regex.captures(&self.content).unwrap();
-        let first_emoji = captures.get(1).unwrap().as_str();
-        let second_emoji = captures.get(2).unwrap().as_str();
+        let sticker = &self.sticker_items[0];
+        let img_url = sticker.image_url().ok_or(())?;
 
-        let first_is_animated = first_emoji.starts_with("<a:");
-        let second_is_animated = second_emoji.starts_with("<a:");
+        Ok((sticker.format_type == StickerFormatType::Png, img_url))
+    }
 
-        if first_is_animated != second_is_animated {
-            return Err(());
+    //유니코드 이모지 하나만 보낸 메시지. ZWJ 조합이나 피부색, 국기도 한 개로 봄
+    fn unicode_emoji_format_filter(&self) -> Result<&'static SvgTwemojiAsset, ()> {
+        match message_emojis(self).map(|only| only.emojis).as_deref() {
+            Some([EmojiToken::Unicode { emoji, .. }]) => Ok(emoji),
+            _ => Err(()),
         }
+    }
 
-        let first_id = {
-            let parts: Vec<&str> = first_emoji.split(':').collect();
-            if parts.len() != 3 {
-                return Err(());
-            }
-            let mut id = parts[2].to_string();
-            id.pop(); // '>' 제거
-            id
-        };
-
-        let second_id = {
-            let parts: Vec<&str> = second_emoji.split(':').collect();
-            if parts.len() != 3 {
-                return Err(());
-            }
-            let mut id = parts[2].to_string();
-            id.pop(); // '>' 제거
-            id
-        };
+    //||로 가린 이모지가 있는 메시지. 다시 보낼 때도 가려서 보냄
+    fn is_spoiler_emoji(&self) -> bool {
+        message_emojis(self).is_some_and(|only| only.spoiler)
+    }
+}
 
-        let is_png = !first_is_animated; // 둘 다 PNG이면 true, 둘 다 GIF이면 false
-        let format = if is_png { "webp" } else { "gif" };
+//이모지랑 공백만 있는 메시지면 이모지만 순서대로 꺼냄
+//봇, 첨부파일, 멘션이 있는 메시지는 건드리지 않음. 답장은 서버 설정을 보고 따로 거름
+fn message_emojis(msg: &Message) -> Option<EmojiOnly<'_>> {
+    if msg.author.bot
+        || !msg.attachments.is_empty()
+        || msg
+            .mentions
+            .iter()
+            .any(|user| Some(user.id) != replied_author(msg))
+    {
+        return None;
+    }
 
-        let first_url = format!("https://cdn.discordapp.com/emojis/{}.{}", first_id, format);
-        let second_url = format!("https://cdn.discordapp.com/emojis/{}.{}", second_id, format);
+    only_emojis(&tokenize(&msg.content))
+}
 
-        Ok((is_png, first_url, second_url))
-    }
+//서버 이모지만 있는 메시지. 유니코드 이모지가 섞여 있으면 None
+fn custom_emojis(msg: &Message) -> Option<Vec<CustomEmoji<'_>>> {
+    message_emojis(msg)?
+        .emojis
+        .into_iter()
+        .map(|token| match token {
+            EmojiToken::Custom(emoji) => Some(emoji),
+            _ => None,
+        })
+        .collect()
 }
 
 pub async fn auto_send_transfered_image(ctx: &Context, msg: &Message) {
@@ -111,25 +134,22 @@ pub async fn auto_send_transfered_image(ctx: &Context, msg: &Message) {
         None => return,
     };
 
-    let config = match get_guild_config(ctx, guild_id).await {
+    let config = match get_guild_config(ctx, guild_id, msg.channel_id)
+        .await
+        .filter(|config| allows_reply(config, msg) && allows_member(config, msg))
+    {
         Some(config) => config,
         None => return, // 자동 확대 기능이 비활성화된 경우
     };
 
-    let (_, size_config) = config;
-
-    // 3. delete message
-    if let Err(why) = msg.delete(&ctx.http).await {
-        error!("couldn't delete message. {:?}", why);
-    }
-
-    // 4. send emoji
-    let (is_png, img_url) = filtered;
+    //Auto는 서버에서 정한 범위를 보고 원본 크기에 맞춰 판단함
+    let size_config = auto_size_config(&config, AutoContext::SingleEmoji);
+    let (_, img_url) = filtered;
 
-    let result = if matches!(
-        size_config,
-        HyperTechniqueOfLisaSuFinger | Small | Medium | Auto
-    ) {
+    //스포일러는 주소로 보내면 미리보기가 안 가려지니까 항상 파일로 보냄
+    let result = if !msg.is_spoiler_emoji()
+        && matches!(size_config, HyperTechniqueOfLisaSuFinger | Small | Medium)
+    {
         let size = match size_config {
             HyperTechniqueOfLisaSuFinger => "?size=16",
             Small => "?size=64",
@@ -137,9 +157,36 @@ pub async fn auto_send_transfered_image(ctx: &Context, msg: &Message) {
             _ => "",
         };
 
-        send_emoji_as_url(ctx, msg, &img_url, size).await
+        // 3. delete message
+        if let Err(why) = msg.delete(&ctx.http).await {
+            error!("couldn't delete message. {:?}", why);
+        }
+
+        // 4. send emoji
+        send_emoji_as_url(ctx, msg, &img_url, size, &config).await
     } else {
-        send_emoji_as_file(ctx, msg, is_png, &img_url, &size_config).await
+        // 변환이 실패하면 원본 메시지는 지우지 않고 이유만 알려줌
+        let resized = match get_resized_image(
+            ctx,
+            &img_url,
+            &size_config,
+            &config.resize_filter,
+            &FitMode::Contain,
+            &config.apng_output_format,
+            guild_upload_limit(ctx, msg.guild_id),
+        )
+        .await
+        {
+            Ok(resized) => resized,
+            Err(why) => return send_error_message(ctx, msg, why).await,
+        };
+
+        if let Err(why) = msg.delete(&ctx.http).await {
+            error!("couldn't delete message. {:?}", why);
+        }
+
+        let (resized, compromise) = resized;
+        send_emoji_as_file(ctx, msg, resized, compromise, &config).await
     };
 
     if let Err(why) = result {
@@ -147,18 +194,171 @@ pub async fn auto_send_transfered_image(ctx: &Context, msg: &Message) {
     }
 }
 
+pub async fn auto_send_unicode_emoji(ctx: &Context, msg: &Message) {
+    let emoji = match msg.unicode_emoji_format_filter() {
+        Ok(emoji) => emoji,
+        Err(()) => return,
+    };
+
+    let guild_id = match msg.guild_id {
+        Some(id) => match NonZeroU64::new(id.get()) {
+            Some(id) => id,
+            None => return,
+        },
+        None => return,
+    };
+
+    let config = match get_guild_config(ctx, guild_id, msg.channel_id)
+        .await
+        .filter(|config| allows_reply(config, msg) && allows_member(config, msg))
+    {
+        Some(config) => config,
+        None => return,
+    };
+
+    //유니코드 이모지는 URL로 크기를 바꿀 수 없으니 프리셋 크기도 직접 그려서 보냄
+    let size_config = auto_size_config(&config, AutoContext::SingleEmoji);
+
+    let rendered = match get_twemoji_image(
+        emoji,
+        &size_config,
+        &FitMode::Contain,
+        guild_upload_limit(ctx, msg.guild_id),
+    )
+    .await
+    {
+        Ok(rendered) => rendered,
+        Err(why) => return send_error_message(ctx, msg, why).await,
+    };
+
+    if let Err(why) = msg.delete(&ctx.http).await {
+        error!("couldn't delete message. {:?}", why);
+    }
+
+    let (rendered, compromise) = rendered;
+    if let Err(why) = send_emoji_as_file(ctx, msg, rendered, compromise, &config).await {
+        error!("send message error: {:?}", why);
+    }
+}
+
+pub async fn auto_send_sticker(ctx: &Context, msg: &Message) {
+    let (_, img_url) = match msg.sticker_format_filter() {
+        Ok(result) => result,
+        Err(()) => return,
+    };
+
+    let guild_id = match msg.guild_id {
+        Some(id) => match NonZeroU64::new(id.get()) {
+            Some(id) => id,
+            None => return,
+        },
+        None => return,
+    };
+
+    let config = match get_guild_config(ctx, guild_id, msg.channel_id)
+        .await
+        .filter(|config| allows_reply(config, msg) && allows_member(config, msg))
+    {
+        Some(config) => config,
+        None => return,
+    };
+
+    //스티커는 URL로 크기를 바꿀 수 없으니 항상 변환해서 보냄
+    let size_config = auto_size_config(&config, AutoContext::Sticker);
+
+    let resized = match get_resized_image(
+        ctx,
+        &img_url,
+        &size_config,
+        &config.resize_filter,
+        &FitMode::Contain,
+        &config.apng_output_format,
+        guild_upload_limit(ctx, msg.guild_id),
+    )
+    .await
+    {
+        Ok(resized) => resized,
+        Err(why) => return send_error_message(ctx, msg, why).await,
+    };
+
+    if let Err(why) = msg.delete(&ctx.http).await {
+        error!("couldn't delete message. {:?}", why);
+    }
+
+    let (resized, compromise) = resized;
+    if let Err(why) = send_emoji_as_file(ctx, msg, resized, compromise, &config).await {
+        error!("send message error: {:?}", why);
+    }
+}
+
 pub async fn auto_send_double_emoji(ctx: &Context, msg: &Message) {
     let filtered = match msg.double_emoji_format_filter() {
         Ok(result) => result,
         Err(()) => return,
     };
 
-    // PNG only
-    let (is_png, first_url, second_url) = filtered;
-    if !is_png {
+    let (_, first_url, second_url) = filtered;
+
+    let guild_id = match msg.guild_id {
+        Some(id) => match NonZeroU64::new(id.get()) {
+            Some(id) => id,
+            None => return,
+        },
+        None => return,
+    };
+
+    let Some(config) = get_guild_config(ctx, guild_id, msg.channel_id)
+        .await
+        .filter(|config| {
+            config.merge_emoji_enable && allows_reply(config, msg) && allows_member(config, msg)
+        })
+    else {
         return;
+    };
+
+    //겹치기는 결과가 이모지 하나 크기니까 칸 크기도 이모지 하나 기준으로 맞춤
+    let cell_count = match config.double_emoji_layout.arrangement {
+        DoubleEmojiArrangement::Overlay => 1,
+        _ => 2,
+    };
+    let (is_png, merged_image) = match merge_two_emojis(
+        &first_url,
+        &second_url,
+        config.double_emoji_layout,
+        merged_cell_size(&config, cell_count).as_ref(),
+        config.resize_filter,
+    )
+    .await
+    {
+        Ok(merged) => merged,
+        Err(why) => return send_error_message(ctx, msg, why).await,
+    };
+
+    let (merged_image, compromise) =
+        match fit_merged_emoji(is_png, merged_image, guild_upload_limit(ctx, msg.guild_id)).await {
+            Ok(fitted) => fitted,
+            Err(why) => return send_error_message(ctx, msg, why).await,
+        };
+
+    if let Err(why) = msg.delete(&ctx.http).await {
+        error!("couldn't delete message. {:?}", why);
     }
 
+    // 5. 합쳐진 이미지 전송
+    let result = send_emoji_as_file(ctx, msg, merged_image, compromise, &config).await;
+
+    // 6. 에러 처리
+    if let Err(why) = result {
+        error!("send message error: {:?}", why);
+    }
+}
+
+pub async fn auto_send_multi_emoji(ctx: &Context, msg: &Message) {
+    let (_, img_urls) = match msg.multi_emoji_format_filter() {
+        Ok(result) => result,
+        Err(()) => return,
+    };
+
     let guild_id = match msg.guild_id {
         Some(id) => match NonZeroU64::new(id.get()) {
             Some(id) => id,
@@ -167,34 +367,101 @@ pub async fn auto_send_double_emoji(ctx: &Context, msg: &Message) {
         None => return,
     };
 
-    let Some(config) = get_guild_config(ctx, guild_id).await else {
+    let Some(config) = get_guild_config(ctx, guild_id, msg.channel_id)
+        .await
+        .filter(|config| {
+            config.merge_emoji_enable && allows_reply(config, msg) && allows_member(config, msg)
+        })
+    else {
         return;
     };
 
-    let (_, size_config) = config;
+    let (is_png, merged_image) = match merge_emojis(
+        &img_urls,
+        config.multi_emoji_layout,
+        config.multi_emoji_spacing,
+        merged_cell_size(&config, img_urls.len()).as_ref(),
+        config.resize_filter,
+    )
+    .await
+    {
+        Ok(merged) => merged,
+        Err(why) => return send_error_message(ctx, msg, why).await,
+    };
+
+    let (merged_image, compromise) =
+        match fit_merged_emoji(is_png, merged_image, guild_upload_limit(ctx, msg.guild_id)).await {
+            Ok(fitted) => fitted,
+            Err(why) => return send_error_message(ctx, msg, why).await,
+        };
 
     if let Err(why) = msg.delete(&ctx.http).await {
         error!("couldn't delete message. {:?}", why);
     }
 
-    let result = match merge_two_emojis(&first_url, &second_url).await {
-        Ok(merged_image) => {
-            // 5. 합쳐진 이미지 전송
-            send_merged_emoji(ctx, msg, merged_image).await
-        }
-        Err(e) => {
-            error!("Failed to merge emojis: {:?}", e);
-            return;
-        }
-    };
+    let result = send_emoji_as_file(ctx, msg, merged_image, compromise, &config).await;
 
-    // 6. 에러 처리
     if let Err(why) = result {
         error!("send message error: {:?}", why);
     }
 }
 
-async fn get_guild_config(ctx: &Context, guild_id: NonZeroU64) -> Option<(bool, ImageSize)> {
+//답장으로 보낸 이모지는 서버에서 켜놨을 때만 확대함
+fn allows_reply(config: &GuildConfig, msg: &Message) -> bool {
+    msg.referenced_message.is_none() || config.magnify_replies
+}
+
+//역할 허용/제외 목록. 멤버 정보가 없으면 역할이 없는 사람으로 봄
+fn allows_member(config: &GuildConfig, msg: &Message) -> bool {
+    let roles = msg
+        .member
+        .as_ref()
+        .map_or(&[][..], |member| member.roles.as_slice());
+    config.allows_roles(roles)
+}
+
+//답장이 멘션한 사람. 답장 멘션을 켜면 mentions에 들어있어서 다른 멘션과 구분함
+fn replied_author(msg: &Message) -> Option<UserId> {
+    msg.referenced_message
+        .as_ref()
+        .map(|replied| replied.author.id)
+}
+
+//답장이었으면 같은 메시지에 답장으로 보내고, 원래 답장이 멘션했는지도 그대로 따라감
+//이름에 @everyone 같은 게 들어있어도 울리지 않게 다른 멘션은 다 막음
+fn repost_message(msg: &Message) -> CreateMessage {
+    let mentions = CreateAllowedMentions::new();
+    match &msg.referenced_message {
+        Some(replied) => CreateMessage::new()
+            .reference_message(replied.as_ref())
+            .allowed_mentions(
+                mentions.replied_user(msg.mentions.iter().any(|user| user.id == replied.author.id)),
+            ),
+        None => CreateMessage::new().allowed_mentions(mentions),
+    }
+}
+
+//크기 기본값이 Auto면 메시지 상황에 맞는 범위로 바꾸고, 아니면 설정 그대로 씀
+fn auto_size_config(config: &GuildConfig, context: AutoContext) -> ImageSize {
+    match config.auto_magnitute_config {
+        Auto => ImageSize::auto_for(context, config.auto_size_min, config.auto_size_max),
+        ref size_config => size_config.clone(),
+    }
+}
+
+//합칠 때는 Auto일 때만 칸 크기를 맞추고, 고정 크기 설정이면 이모지를 그대로 합침
+fn merged_cell_size(config: &GuildConfig, count: usize) -> Option<ImageSize> {
+    matches!(config.auto_magnitute_config, Auto)
+        .then(|| auto_size_config(config, AutoContext::Merged(count)))
+}
+
+//채널, 카테고리별 설정까지 덮어쓴 설정. 그 채널에서 자동 확대가 꺼져있으면 None
+async fn get_guild_config(
+    ctx: &Context,
+    guild_id: NonZeroU64,
+    channel_id: ChannelId,
+) -> Option<GuildConfig> {
+    let lineage = channel_lineage(ctx, GuildId::new(guild_id.get()), channel_id);
     let counter_lock = {
         let data_read = ctx.data.read().await;
         data_read
@@ -209,83 +476,186 @@ async fn get_guild_config(ctx: &Context, guild_id: NonZeroU64) -> Option<(bool,
         None => return None,
     };
 
-    let gconfig = gconfig_lock.lock().await;
+    let gconfig = gconfig_lock.lock().await.for_channel(&lineage);
 
     if !gconfig.auto_magnitute_enable {
         return None;
     }
 
-    Some((
-        gconfig.auto_magnitute_enable,
-        gconfig.auto_magnitute_config.clone(),
-    ))
+    Some(gconfig)
 }
 
+//webhook_repost면 웹후크로 보낸 사람처럼 보내보고, 안 되면 이름 줄을 먼저 보내는 원래 방식으로 보냄
+//보낸 메시지에는 삭제 버튼을 달고, 누가 보낸 건지 기록해둠
 async fn send_emoji_as_url(
     ctx: &Context,
     msg: &Message,
     img_url: &str,
     size: &str,
+    config: &GuildConfig,
 ) -> Result<Message, serenity::Error> {
     let display_name = get_user_display_name(msg);
-
-    // 유저 이름 먼저 보내기
-    msg.channel_id
-        .say(&ctx.http, format!("**{}** :", display_name))
+    let emoji_url = format!("{}{}", img_url, size);
+
+    //웹후크는 답장을 못 보내니까 답장은 원래 방식으로 보냄
+    if config.webhook_repost && msg.referenced_message.is_none() {
+        if let Some(sent) = repost_as_author(
+            ctx,
+            msg,
+            &display_name,
+            Some(emoji_url.clone()),
+            vec![],
+            vec![delete_button()],
+        )
         .await
-        .unwrap();
+        {
+            record_repost(
+                ctx,
+                msg.author.id,
+                &sent,
+                None,
+                config.repost_expire_seconds,
+            )
+            .await;
+            return Ok(sent);
+        }
+    }
+
+    // 유저 이름 먼저 보내기 (답장이면 이 줄이 답장이 됨)
+    let name_line = msg
+        .channel_id
+        .send_message(
+            &ctx.http,
+            repost_message(msg).content(format!("**{}** :", display_name)),
+        )
+        .await?;
 
     // 이모지 URL 보내기
-    msg.channel_id
-        .say(&ctx.http, format!("{}{}", img_url, size))
-        .await
+    let sent = msg
+        .channel_id
+        .send_message(
+            &ctx.http,
+            CreateMessage::new()
+                .content(emoji_url)
+                .allowed_mentions(CreateAllowedMentions::new())
+                .components(vec![delete_button()]),
+        )
+        .await?;
+
+    record_repost(
+        ctx,
+        msg.author.id,
+        &sent,
+        Some(&name_line),
+        config.repost_expire_seconds,
+    )
+    .await;
+    Ok(sent)
 }
 
 async fn send_emoji_as_file(
     ctx: &Context,
     msg: &Message,
-    is_png: bool,
-    img_url: &str,
-    size_config: &ImageSize,
+    resized: CreateAttachment,
+    compromise: Option<SizeCompromise>,
+    config: &GuildConfig,
 ) -> Result<Message, serenity::Error> {
     let display_name = get_user_display_name(msg);
 
-    let files = [if is_png {
-        get_resized_image(ctx, img_url, size_config).await
+    let resized = if msg.is_spoiler_emoji() {
+        spoiler_attachment(resized)
     } else {
-        CreateAttachment::url(&ctx.http, img_url).await.unwrap()
-    }];
+        resized
+    };
+
+    //업로드 제한 때문에 화질을 낮췄으면 작은 글씨로 알려줌
+    let notice = compromise.map(|compromise| format!("-# {}", compromise.get_notice_message()));
+
+    if config.webhook_repost && msg.referenced_message.is_none() {
+        if let Some(sent) = repost_as_author(
+            ctx,
+            msg,
+            &display_name,
+            notice.clone(),
+            vec![resized.clone()],
+            vec![delete_button()],
+        )
+        .await
+        {
+            record_repost(
+                ctx,
+                msg.author.id,
+                &sent,
+                None,
+                config.repost_expire_seconds,
+            )
+            .await;
+            return Ok(sent);
+        }
+    }
+
+    let content = match notice {
+        Some(notice) => format!("**{}** :\n{}", display_name, notice),
+        None => format!("**{}** :", display_name),
+    };
 
-    msg.channel_id
+    let sent = msg
+        .channel_id
         .send_files(
             &ctx.http,
-            files,
-            CreateMessage::new().content(format!("**{}** :", display_name)),
+            [resized],
+            repost_message(msg)
+                .content(content)
+                .components(vec![delete_button()]),
         )
-        .await
+        .await?;
+
+    record_repost(
+        ctx,
+        msg.author.id,
+        &sent,
+        None,
+        config.repost_expire_seconds,
+    )
+    .await;
+    Ok(sent)
 }
 
-async fn send_merged_emoji(
-    ctx: &Context,
-    msg: &Message,
+//합친 이미지도 이모지 개수만큼 커지니까 업로드 제한에 맞춰서 보냄
+async fn fit_merged_emoji(
+    is_png: bool,
     merged_image: Vec<u8>,
-) -> Result<Message, serenity::Error> {
-    let display_name = get_user_display_name(msg);
+    upload_limit: u64,
+) -> Result<(CreateAttachment, Option<SizeCompromise>), ImageTransferError> {
+    let merged = CachedImage {
+        bytes: merged_image,
+        filename: if is_png {
+            "merged_emoji.png"
+        } else {
+            "merged_emoji.gif"
+        }
+        .to_string(),
+    };
 
-    // 합쳐진 이미지를 첨부 파일로 생성
-    let attachment = CreateAttachment::bytes(merged_image, "double_emoji.png");
+    let (fitted, compromise) =
+        tokio::task::spawn_blocking(move || fit_to_upload_limit(merged, upload_limit))
+            .await
+            .map_err(|_| ImageTransferError::Mollu)??;
 
-    // 메시지와 함께 이미지 전송
-    msg.channel_id
-        .send_files(
-            &ctx.http,
-            vec![attachment],
-            CreateMessage::new().content(format!("**{}** :", display_name)),
-        )
-        .await
+    Ok((
+        CreateAttachment::bytes(fitted.bytes, fitted.filename),
+        compromise,
+    ))
+}
+
+async fn send_error_message(ctx: &Context, msg: &Message, why: ImageTransferError) {
+    error!("failed to transfer emoji: {:?}", why);
+    if let Err(why) = msg.reply(&ctx.http, why.get_error_message()).await {
+        error!("couldn't send error message. {:?}", why);
+    }
 }
 
-fn get_user_display_name(msg: &Message) -> String {
+pub fn get_user_display_name(msg: &Message) -> String {
     let global_username = msg
         .author
         .clone()
@@ -11,6 +11,7 @@ use mongodb::bson::doc;
 use std::num::NonZeroU64;
 
 use crate::utils::guild_config::GuildConfig;
+use crate::utils::scene_core::{DEFAULT_AUTO_SIZE_MAX, DEFAULT_AUTO_SIZE_MIN};
 use crate::GlobalGuildConfigs;
 
 pub async fn senddm(ctx: &Context, guild: &Guild, is_new: Option<bool>) {
@@ -88,6 +89,26 @@ pub async fn db_fetch(
                     "guild_id" : guildid as f64,
                     "auto_magnitute_enable" : false,
                     "auto_magnitute_config" : "Auto",
+                    "auto_transfer_webp" : false,
+                    "multi_emoji_layout" : "Grid",
+                    "multi_emoji_spacing" : 0,
+                    "resize_filter" : "Auto",
+                    "auto_size_min" : DEFAULT_AUTO_SIZE_MIN as i32,
+                    "auto_size_max" : DEFAULT_AUTO_SIZE_MAX as i32,
+                    "apng_output_format" : "Gif",
+                    "double_emoji_arrangement" : "Horizontal",
+                    "double_emoji_alignment" : "Center",
+                    "double_emoji_gap" : 0,
+                    "double_emoji_background" : "None",
+                    "double_emoji_overlay_scale" : 50,
+                    "double_emoji_overlay_offset_x" : 25,
+                    "double_emoji_overlay_offset_y" : 25,
+                    "webhook_repost" : false,
+                    "magnify_replies" : false,
+                    "repost_expire_seconds" : 0,
+                    "channel_overrides" : {},
+                    "auto_allowed_roles" : [],
+                    "auto_denied_roles" : [],
             },
             None,
         )
@@ -1,3 +1,4 @@
 pub mod autosend;
 pub mod guild_delete;
 pub mod guildbotadd;
+pub mod webp_transfer;
@@ -0,0 +1,221 @@
+use log::error;
+use serenity::{
+    builder::{CreateAttachment, CreateMessage},
+    client::Context,
+    model::{
+        channel::{Attachment, Message},
+        id::{ChannelId, GuildId},
+    },
+};
+
+use crate::events::autosend::get_user_display_name;
+use crate::utils::emoji_cache::CachedImage;
+use crate::utils::guild_config::channel_lineage;
+use crate::utils::scene_core::{
+    fetch_image_head, is_animated_image, transfer_apng_to_gif, transfer_apng_to_webp,
+    transfer_webp_to_gif, AnimatedFormat, ImageTransferError, WEBP_SIZE_LIMIT,
+};
+use crate::utils::upload_limit::{fit_to_upload_limit, guild_upload_limit, SizeCompromise};
+use crate::GlobalGuildConfigs;
+
+use std::num::NonZeroU64;
+
+pub async fn auto_transfer_webp(ctx: &Context, msg: &Message) {
+    if msg.author.bot {
+        return;
+    }
+
+    //움짤 APNG도 디스코드에서 안 움직이니까 같이 바꿈
+    let webp_attachments: Vec<&Attachment> = msg
+        .attachments
+        .iter()
+        .filter(|a| is_webp(a) || is_png(a))
+        .collect();
+    if webp_attachments.is_empty() {
+        return;
+    }
+
+    let guild_id = match msg.guild_id {
+        Some(id) => match NonZeroU64::new(id.get()) {
+            Some(id) => id,
+            None => return,
+        },
+        None => return,
+    };
+
+    let apng_output = match webp_transfer_output(ctx, guild_id, msg.channel_id).await {
+        Some(apng_output) => apng_output,
+        None => return,
+    };
+
+    let upload_limit = guild_upload_limit(ctx, msg.guild_id);
+    let mut files = Vec::new();
+    let mut notices = Vec::new();
+    for attachment in &webp_attachments {
+        match transfer_attachment(attachment, apng_output, upload_limit).await {
+            Ok((converted, compromise)) => {
+                //업로드 제한 때문에 형식이 바뀌었을 수 있으니 확장자는 결과 파일을 따름
+                let extension = converted
+                    .filename
+                    .rsplit_once('.')
+                    .map_or("gif", |(_, extension)| extension);
+                files.push(CreateAttachment::bytes(
+                    converted.bytes,
+                    format!("{}.{}", file_stem(&attachment.filename), extension),
+                ));
+                if let Some(compromise) = compromise {
+                    notices.push(format!("-# {}", compromise.get_notice_message()));
+                }
+            }
+            //정적 webp, png는 그냥 둠
+            Err(ImageTransferError::AutoPngNotNeeded) => {}
+            Err(why) => {
+                if let Err(why) = msg.reply(&ctx.http, why.get_error_message()).await {
+                    error!("couldn't send webp transfer error message. {:?}", why);
+                }
+            }
+        }
+    }
+
+    if files.is_empty() {
+        return;
+    }
+
+    //메시지가 움짤 webp로만 이루어져 있으면 원본을 지우고 다시 올리고,
+    //다른 내용이 섞여있으면 원본은 두고 답장으로 gif만 올림
+    let replace_original = msg.content.is_empty() && files.len() == msg.attachments.len();
+    //업로드 제한 때문에 화질을 낮췄으면 작은 글씨로 알려줌
+    let notice = notices
+        .iter()
+        .map(|notice| format!("\n{}", notice))
+        .collect::<String>();
+    let result = if replace_original {
+        if let Err(why) = msg.delete(&ctx.http).await {
+            error!("couldn't delete message. {:?}", why);
+        }
+        msg.channel_id
+            .send_files(
+                &ctx.http,
+                files,
+                CreateMessage::new().content(format!(
+                    "**{}** :{}",
+                    get_user_display_name(msg),
+                    notice
+                )),
+            )
+            .await
+    } else {
+        msg.channel_id
+            .send_files(
+                &ctx.http,
+                files,
+                CreateMessage::new()
+                    .content(notice.trim_start())
+                    .reference_message(msg),
+            )
+            .await
+    };
+
+    if let Err(why) = result {
+        error!("send message error: {:?}", why);
+    }
+}
+
+fn is_webp(attachment: &Attachment) -> bool {
+    match &attachment.content_type {
+        Some(content_type) => content_type == "image/webp",
+        None => attachment.filename.to_lowercase().ends_with(".webp"),
+    }
+}
+
+//APNG인지는 받아봐야 알 수 있음
+fn is_png(attachment: &Attachment) -> bool {
+    match &attachment.content_type {
+        Some(content_type) => content_type == "image/png" || content_type == "image/apng",
+        None => {
+            let filename = attachment.filename.to_lowercase();
+            filename.ends_with(".png") || filename.ends_with(".apng")
+        }
+    }
+}
+
+fn file_stem(filename: &str) -> &str {
+    filename.rsplit_once('.').map_or(filename, |(stem, _)| stem)
+}
+
+//acTL은 IDAT보다 앞에 있어야 하지만 그 앞에 iCCP, tEXt 같은 청크가 길게 붙을 수 있어서 넉넉하게 받음
+const ANIMATION_SNIFF_LEN: usize = 64 * 1024;
+
+//webp는 항상 gif로, APNG는 서버에서 정한 형식으로 바꿈
+//변환된 파일을 업로드 제한에 맞춰서, 줄인 게 있으면 같이 돌려줌
+async fn transfer_attachment(
+    attachment: &Attachment,
+    apng_output: AnimatedFormat,
+    upload_limit: u64,
+) -> Result<(CachedImage, Option<SizeCompromise>), ImageTransferError> {
+    let webp = is_webp(attachment);
+    //APNG로 내보내라고 했으면 원본 그대로니까 건드릴 필요 없음
+    if !webp && apng_output == AnimatedFormat::Apng {
+        return Err(ImageTransferError::AutoPngNotNeeded);
+    }
+
+    //큰 파일은 앞부분만 받아서 움짤일 때만 너무 크다고 알려주고, 정적 이미지는 조용히 넘어감
+    if attachment.size > WEBP_SIZE_LIMIT {
+        let head = fetch_image_head(&attachment.url, ANIMATION_SNIFF_LEN).await?;
+        return Err(if is_animated_image(&head) {
+            ImageTransferError::SizeLimitExceeded
+        } else {
+            ImageTransferError::AutoPngNotNeeded
+        });
+    }
+
+    let img_bytes = attachment
+        .download()
+        .await
+        .map_err(|_| ImageTransferError::GetRequestFailed)?;
+
+    tokio::task::spawn_blocking(move || {
+        let (bytes, extension) = match (webp, apng_output) {
+            (true, _) => (transfer_webp_to_gif(&img_bytes)?, "gif"),
+            (false, AnimatedFormat::WebP) => (transfer_apng_to_webp(&img_bytes)?, "webp"),
+            (false, _) => (transfer_apng_to_gif(&img_bytes)?, "gif"),
+        };
+        fit_to_upload_limit(
+            CachedImage {
+                bytes,
+                filename: format!("converted.{}", extension),
+            },
+            upload_limit,
+        )
+    })
+    .await
+    .unwrap_or(Err(ImageTransferError::Mollu))
+}
+
+//자동 변환이 꺼져있으면 None, 켜져있으면 APNG를 바꿀 형식을 돌려줌
+//채널, 카테고리별 설정도 따름
+async fn webp_transfer_output(
+    ctx: &Context,
+    guild_id: NonZeroU64,
+    channel_id: ChannelId,
+) -> Option<AnimatedFormat> {
+    let lineage = channel_lineage(ctx, GuildId::new(guild_id.get()), channel_id);
+    let counter_lock = {
+        let data_read = ctx.data.read().await;
+        data_read
+            .get::<GlobalGuildConfigs>()
+            .expect("poisened")
+            .clone()
+    };
+
+    let guilds_config = counter_lock.read().await;
+    match guilds_config.get(&guild_id) {
+        Some(gconfig_lock) => {
+            let gclock = gconfig_lock.lock().await.for_channel(&lineage);
+            gclock
+                .auto_transfer_webp
+                .then_some(gclock.apng_output_format)
+        }
+        None => None,
+    }
+}
@@ -20,6 +20,23 @@ impl TypeMapKey for GlobalGuildConfigs {
         Arc<RwLock<HashMap<NonZeroU64, Arc<Mutex<crate::utils::guild_config::GuildConfig>>>>>;
 }
 
+struct GlobalEmojiCache;
+impl TypeMapKey for GlobalEmojiCache {
+    type Value = Arc<crate::utils::emoji_cache::EmojiCache>;
+}
+
+//채널마다 이모지를 다시 보낼 때 쓰는 웹후크. 채널마다 따로 잠가서 다른 채널끼리는 안 기다림
+struct GlobalWebhooks;
+impl TypeMapKey for GlobalWebhooks {
+    type Value = Arc<Mutex<HashMap<serenity::model::id::ChannelId, Arc<Mutex<crate::utils::webhook_repost::WebhookSlot>>>>>;
+}
+
+//다시 보낸 이모지를 원래 누가 보냈는지 남기는 DB 컬렉션
+struct GlobalRepostLog;
+impl TypeMapKey for GlobalRepostLog {
+    type Value = mongodb::Collection<mongodb::bson::Document>;
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     dotenv().ok();
@@ -40,6 +57,10 @@ async fn main() -> Result<(), Box<dyn Error>> {
             .await?;
 
     let db_client = DBClient::with_options(options)?;
+    let repost_log = db_client.database("scene").collection(&format!(
+        "{}_reposts",
+        env::var("BOT_DB_NAME").expect("couldn't find db name")
+    ));
 
     let handler = event_handler::DiscordEventHandler {
         database: db_client,
@@ -53,6 +74,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
     {
         let mut data = client.data.write().await;
         data.insert::<GlobalGuildConfigs>(Arc::new(RwLock::new(HashMap::default())));
+        data.insert::<GlobalEmojiCache>(Arc::new(utils::emoji_cache::EmojiCache::from_env()));
+        data.insert::<GlobalWebhooks>(Arc::new(Mutex::new(HashMap::default())));
+        data.insert::<GlobalRepostLog>(repost_log);
     }
 
     client.start().await?;
@@ -0,0 +1,234 @@
+//APNG를 프레임 단위로 풀고 다시 묶는 부분
+//image의 ApngDecoder는 합성된 결과만 주니까, 프레임 위치/딜레이/blend/dispose를 그대로 보려고 png 크레이트를 직접 씀
+use png::{BlendOp, ColorType, DisposeOp, Transformations};
+
+use std::io::Cursor;
+
+use crate::utils::scene_core::{EmojiFrames, ImageTransferError};
+
+//합성하면 프레임마다 캔버스 전체가 하나씩 생기니까 캔버스 x 프레임 수로 제한함
+const MAX_APNG_SIDE: u32 = u16::MAX as u32;
+const MAX_APNG_COMPOSED_PIXELS: u64 = 32 * 1024 * 1024;
+
+//fcTL 그대로. rgba는 width x height 크기의 부분 프레임
+pub struct ApngFrame {
+    pub rgba: Vec<u8>,
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+    pub delay_num: u16,
+    pub delay_den: u16,
+    pub dispose: DisposeOp,
+    pub blend: BlendOp,
+}
+
+impl ApngFrame {
+    //1/100초 단위. 분모가 0이면 1/100초로 보라고 스펙에 나와있음
+    pub fn delay(&self) -> u32 {
+        let den = if self.delay_den == 0 {
+            100
+        } else {
+            self.delay_den as u32
+        };
+        let delay = (self.delay_num as u32 * 100 + den / 2) / den;
+        //gif랑 똑같이 너무 짧은 딜레이는 0.1초로 취급
+        if delay < 2 {
+            10
+        } else {
+            delay
+        }
+    }
+}
+
+pub struct Apng {
+    pub width: u32,
+    pub height: u32,
+    //0이면 무한 반복
+    pub num_plays: u32,
+    pub frames: Vec<ApngFrame>,
+}
+
+impl Apng {
+    pub fn decode(img_bytes: &[u8]) -> Result<Self, ImageTransferError> {
+        let mut decoder = png::Decoder::new(Cursor::new(img_bytes));
+        decoder.set_transformations(
+            Transformations::EXPAND | Transformations::STRIP_16 | Transformations::ALPHA,
+        );
+        let mut reader = decoder
+            .read_info()
+            .map_err(|_| ImageTransferError::DecodingError)?;
+
+        let (width, height) = (reader.info().width, reader.info().height);
+        let animation = reader
+            .info()
+            .animation_control
+            .ok_or(ImageTransferError::DecodingError)?;
+        //IDAT 앞에 fcTL이 없으면 기본 이미지는 애니메이션에 안 들어감
+        let default_image_is_frame = reader.info().frame_control.is_some();
+        let total = animation.num_frames + u32::from(!default_image_is_frame);
+
+        //작은 파일도 캔버스를 크게 잡고 1x1 프레임을 잔뜩 넣을 수 있으니
+        //프레임을 읽기 전에 합성 결과 크기(캔버스 x 프레임 수)부터 확인함
+        if width > MAX_APNG_SIDE || height > MAX_APNG_SIDE {
+            return Err(ImageTransferError::AnimationTooLarge);
+        }
+        if width as u64 * height as u64 * animation.num_frames as u64 > MAX_APNG_COMPOSED_PIXELS {
+            return Err(ImageTransferError::AnimationTooLarge);
+        }
+
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let mut frames = Vec::with_capacity(animation.num_frames as usize);
+        for index in 0..total {
+            let output = reader
+                .next_frame(&mut buffer)
+                .map_err(|_| ImageTransferError::DecodingError)?;
+            if index == 0 && !default_image_is_frame {
+                continue;
+            }
+
+            let control = reader
+                .info()
+                .frame_control
+                .ok_or(ImageTransferError::DecodingError)?;
+            let data = &buffer[..output.line_size * output.height as usize];
+            let rgba = match output.color_type {
+                ColorType::Rgba => data.to_vec(),
+                ColorType::GrayscaleAlpha => data
+                    .chunks_exact(2)
+                    .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+                    .collect(),
+                _ => return Err(ImageTransferError::DecodingError),
+            };
+
+            if control.x_offset + control.width > width
+                || control.y_offset + control.height > height
+            {
+                return Err(ImageTransferError::InvalidImageSize);
+            }
+
+            frames.push(ApngFrame {
+                rgba,
+                left: control.x_offset,
+                top: control.y_offset,
+                width: control.width,
+                height: control.height,
+                delay_num: control.delay_num,
+                delay_den: control.delay_den,
+                dispose: control.dispose_op,
+                blend: control.blend_op,
+            });
+        }
+
+        if frames.is_empty() {
+            return Err(ImageTransferError::DecodingError);
+        }
+
+        Ok(Self {
+            width,
+            height,
+            num_plays: animation.num_plays,
+            frames,
+        })
+    }
+
+    //blend/dispose를 적용해서 프레임마다 캔버스 전체를 만듦
+    pub fn compose(&self) -> EmojiFrames {
+        let canvas_len = (self.width * self.height * 4) as usize;
+        let mut canvas = vec![0; canvas_len];
+        let mut composed = Vec::with_capacity(self.frames.len());
+
+        for (index, frame) in self.frames.iter().enumerate() {
+            //첫 프레임의 Previous는 Background로 취급하라고 스펙에 나와있음
+            let dispose = match frame.dispose {
+                DisposeOp::Previous if index == 0 => DisposeOp::Background,
+                dispose => dispose,
+            };
+            let previous = (dispose == DisposeOp::Previous).then(|| canvas.clone());
+
+            for y in 0..frame.height {
+                for x in 0..frame.width {
+                    let src = ((y * frame.width + x) * 4) as usize;
+                    let dst = (((frame.top + y) * self.width + frame.left + x) * 4) as usize;
+                    let pixel = &frame.rgba[src..src + 4];
+                    match frame.blend {
+                        BlendOp::Source => canvas[dst..dst + 4].copy_from_slice(pixel),
+                        BlendOp::Over => blend_over(&mut canvas[dst..dst + 4], pixel),
+                    }
+                }
+            }
+
+            let image = image::RgbaImage::from_raw(self.width, self.height, canvas.clone())
+                .unwrap_or_else(|| image::RgbaImage::new(self.width, self.height));
+            composed.push((image, frame.delay()));
+
+            match (dispose, previous) {
+                (DisposeOp::Background, _) => {
+                    for y in frame.top..frame.top + frame.height {
+                        let start = ((y * self.width + frame.left) * 4) as usize;
+                        canvas[start..start + (frame.width * 4) as usize].fill(0);
+                    }
+                }
+                (DisposeOp::Previous, Some(previous)) => canvas = previous,
+                _ => {}
+            }
+        }
+
+        composed
+    }
+}
+
+//알파를 곱하지 않은 RGBA끼리 src를 dst 위에 덮음
+fn blend_over(dst: &mut [u8], src: &[u8]) {
+    let src_alpha = src[3] as u32;
+    if src_alpha == 255 {
+        dst.copy_from_slice(src);
+        return;
+    }
+    if src_alpha == 0 {
+        return;
+    }
+
+    let dst_alpha = dst[3] as u32 * (255 - src_alpha) / 255;
+    let out_alpha = src_alpha + dst_alpha;
+    for channel in 0..3 {
+        dst[channel] =
+            ((src[channel] as u32 * src_alpha + dst[channel] as u32 * dst_alpha) / out_alpha) as u8;
+    }
+    dst[3] = out_alpha as u8;
+}
+
+//캔버스 전체 프레임을 덮어쓰기(Source)로만 쓰는 APNG로 묶음
+pub fn encode(frames: &EmojiFrames, num_plays: u32) -> Result<Vec<u8>, ImageTransferError> {
+    let (width, height) = match frames.first() {
+        Some((img, _)) => img.dimensions(),
+        None => return Err(ImageTransferError::ApngEncodingError),
+    };
+
+    let mut result_buf = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut result_buf, width, height);
+        encoder.set_color(ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .set_animated(frames.len() as u32, num_plays)
+            .map_err(|_| ImageTransferError::ApngEncodingError)?;
+        let mut writer = encoder
+            .write_header()
+            .map_err(|_| ImageTransferError::ApngEncodingError)?;
+
+        for (img, delay) in frames {
+            writer
+                .set_frame_delay((*delay).min(u16::MAX as u32) as u16, 100)
+                .and_then(|_| writer.set_blend_op(BlendOp::Source))
+                .and_then(|_| writer.set_dispose_op(DisposeOp::None))
+                .and_then(|_| writer.write_image_data(img.as_raw()))
+                .map_err(|_| ImageTransferError::ApngEncodingError)?;
+        }
+        writer
+            .finish()
+            .map_err(|_| ImageTransferError::ApngEncodingError)?;
+    }
+
+    Ok(result_buf)
+}
@@ -0,0 +1,487 @@
+//이모지 위아래에 짤 같은 문구를 넣는 부분
+//라틴 문자는 같이 넣어둔 DejaVu Sans Bold 윤곽선으로 그리고, 폰트에 없는 글자(한글 등)는 unifont 비트맵으로 그림
+//둘 다 tiny-skia 경로로 만들어서 테두리와 채우기를 똑같이 적용함
+use ab_glyph::{Font, FontRef, GlyphId, OutlineCurve, PxScale, ScaleFont};
+use image::{imageops, RgbaImage};
+use lazy_static::lazy_static;
+use serenity::builder::CreateAttachment;
+use tiny_skia::{
+    Color, FillRule, LineJoin, Paint, Path, PathBuilder, Pixmap, PixmapPaint, Rect, Stroke,
+    Transform,
+};
+
+use std::io::Cursor;
+
+use crate::utils::effects::{from_pixmap, to_pixmap};
+use crate::utils::emoji_cache::CachedImage;
+use crate::utils::scene_core::{
+    decode_emoji_frames, encode_gif_frames, fetch_image_bytes, EmojiFrames, ImageTransferError,
+};
+use crate::utils::upload_limit::{fit_to_upload_limit, SizeCompromise};
+
+lazy_static! {
+    static ref CAPTION_FONT: FontRef<'static> =
+        FontRef::try_from_slice(include_bytes!("../../assets/fonts/DejaVuSans-Bold.ttf"))
+            .expect("bundled caption font is broken");
+}
+
+//문구 한 줄(위, 아래 각각)에 넣을 수 있는 글자 수
+pub const MAX_CAPTION_LENGTH: u16 = 100;
+
+//작은 이모지는 글자가 뭉개지니까 이 크기까지 키워서 씀
+const CAPTION_CANVAS_SIZE: u32 = 256;
+//글자 크기는 캔버스 가로에 비례해서 이 범위 안에서 줄여감
+const MAX_FONT_DIVISOR: f32 = 6.0;
+const MIN_FONT_SIZE: f32 = 12.0;
+const FONT_SHRINK_STEP: f32 = 0.9;
+//위아래 문구가 각각 차지할 수 있는 높이 비율
+const OVERLAY_BLOCK_RATIO: f32 = 0.3;
+const OUTSIDE_BLOCK_RATIO: f32 = 0.5;
+//unifont 글리프는 16px 높이에 기준선이 아래에서 2px 위
+const UNIFONT_HEIGHT: f32 = 16.0;
+const UNIFONT_ASCENT: f32 = 14.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptionPlacement {
+    //이모지 위에 흰 글씨 + 검은 테두리로 겹쳐 씀
+    Overlay,
+    //이모지 위아래에 흰 띠를 붙이고 검은 글씨로 씀
+    Outside,
+}
+
+impl CaptionPlacement {
+    pub fn string_to_value(input_str: &str) -> Self {
+        match input_str {
+            "Outside" => Self::Outside,
+            _ => Self::Overlay,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Caption {
+    pub top: Option<String>,
+    pub bottom: Option<String>,
+    pub placement: CaptionPlacement,
+}
+
+//정적 이모지는 png로, 움짤은 프레임마다 문구를 넣은 gif로 보냄
+pub async fn caption_emoji(
+    img_url: &str,
+    caption: Caption,
+    upload_limit: u64,
+) -> Result<(CreateAttachment, Option<SizeCompromise>), ImageTransferError> {
+    let img_bytes = fetch_image_bytes(&format!("{}?size={}", img_url, CAPTION_CANVAS_SIZE)).await?;
+
+    let (captioned, compromise) = tokio::task::spawn_blocking(move || {
+        let frames = caption_frames(decode_emoji_frames(&img_bytes)?, &caption)?;
+
+        let captioned = match frames.len() {
+            1 => CachedImage {
+                bytes: encode_png(&frames[0].0)?,
+                filename: "captioned.png".to_string(),
+            },
+            _ => CachedImage {
+                bytes: encode_gif_frames(frames)?,
+                filename: "captioned.gif".to_string(),
+            },
+        };
+        fit_to_upload_limit(captioned, upload_limit)
+    })
+    .await
+    .map_err(|_| ImageTransferError::Mollu)??;
+
+    Ok((
+        CreateAttachment::bytes(captioned.bytes, captioned.filename),
+        compromise,
+    ))
+}
+
+//문구는 한 번만 그려두고 프레임마다 같은 자리에 붙임
+pub fn caption_frames(
+    frames: EmojiFrames,
+    caption: &Caption,
+) -> Result<EmojiFrames, ImageTransferError> {
+    let (width, height) = match frames.first() {
+        Some((frame, _)) => frame.dimensions(),
+        None => return Err(ImageTransferError::DecodingError),
+    };
+    let (width, height) = canvas_size(width, height);
+
+    let block_ratio = match caption.placement {
+        CaptionPlacement::Overlay => OVERLAY_BLOCK_RATIO,
+        CaptionPlacement::Outside => OUTSIDE_BLOCK_RATIO,
+    };
+    let max_block_height = (height as f32 * block_ratio).round() as u32;
+    let render = |text: &Option<String>| {
+        text.as_deref()
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .map(|text| render_text_block(text, width, max_block_height, caption.placement))
+            .transpose()
+    };
+    let top = render(&caption.top)?;
+    let bottom = render(&caption.bottom)?;
+
+    let (top_band, bottom_band) = match caption.placement {
+        CaptionPlacement::Overlay => (0, 0),
+        CaptionPlacement::Outside => (
+            top.as_ref().map_or(0, Pixmap::height),
+            bottom.as_ref().map_or(0, Pixmap::height),
+        ),
+    };
+    let canvas_height = top_band + height + bottom_band;
+
+    frames
+        .into_iter()
+        .map(|(frame, delay)| {
+            let frame = match frame.dimensions() == (width, height) {
+                true => frame,
+                false => imageops::resize(&frame, width, height, imageops::FilterType::CatmullRom),
+            };
+            let frame = to_pixmap(&frame).ok_or(ImageTransferError::InvalidImageSize)?;
+
+            let mut canvas =
+                Pixmap::new(width, canvas_height).ok_or(ImageTransferError::InvalidImageSize)?;
+            if caption.placement == CaptionPlacement::Outside {
+                canvas.fill(Color::WHITE);
+            }
+            let paint = PixmapPaint::default();
+            canvas.draw_pixmap(
+                0,
+                top_band as i32,
+                frame.as_ref(),
+                &paint,
+                Transform::identity(),
+                None,
+            );
+            if let Some(top) = &top {
+                canvas.draw_pixmap(0, 0, top.as_ref(), &paint, Transform::identity(), None);
+            }
+            if let Some(bottom) = &bottom {
+                let bottom_y = canvas_height - bottom.height();
+                canvas.draw_pixmap(
+                    0,
+                    bottom_y as i32,
+                    bottom.as_ref(),
+                    &paint,
+                    Transform::identity(),
+                    None,
+                );
+            }
+
+            Ok((from_pixmap(&canvas), delay))
+        })
+        .collect()
+}
+
+//긴 쪽이 CAPTION_CANVAS_SIZE보다 작으면 비율을 지켜서 키움
+fn canvas_size(width: u32, height: u32) -> (u32, u32) {
+    let longest = width.max(height).max(1);
+    if longest >= CAPTION_CANVAS_SIZE {
+        return (width, height);
+    }
+    let scale = |side: u32| (side * CAPTION_CANVAS_SIZE / longest).max(1);
+    (scale(width), scale(height))
+}
+
+//가로 width 안에 들어가도록 줄을 나누고, 전체가 max_height 안에 들어갈 때까지 글자를 줄임
+fn render_text_block(
+    text: &str,
+    width: u32,
+    max_height: u32,
+    placement: CaptionPlacement,
+) -> Result<Pixmap, ImageTransferError> {
+    let margin = (width as f32 * 0.04).max(2.0);
+    let max_line_width = width as f32 - margin * 2.0;
+
+    let mut font_size = width as f32 / MAX_FONT_DIVISOR;
+    let lines = loop {
+        let lines = wrap_lines(text, font_size, max_line_width);
+        let fits = lines
+            .iter()
+            .all(|line| line_width(line, font_size) <= max_line_width)
+            && lines.len() as f32 * line_height(font_size) + margin <= max_height as f32;
+        if fits || font_size * FONT_SHRINK_STEP < MIN_FONT_SIZE {
+            break lines;
+        }
+        font_size *= FONT_SHRINK_STEP;
+    };
+
+    let block_height = (lines.len() as f32 * line_height(font_size) + margin).ceil() as u32;
+    let mut block =
+        Pixmap::new(width, block_height).ok_or(ImageTransferError::CaptionRenderError)?;
+
+    let mut fill = Paint::default();
+    let mut outline = Paint::default();
+    match placement {
+        CaptionPlacement::Overlay => {
+            fill.set_color(Color::WHITE);
+            outline.set_color(Color::BLACK);
+        }
+        CaptionPlacement::Outside => fill.set_color(Color::BLACK),
+    }
+    let stroke = Stroke {
+        width: (font_size / 6.0).max(2.0),
+        line_join: LineJoin::Round,
+        ..Stroke::default()
+    };
+
+    for (index, line) in lines.iter().enumerate() {
+        let Some((path, line_width)) = line_path(line, font_size) else {
+            continue;
+        };
+        //가운데 정렬. 테두리가 잘리지 않게 여백만큼 띄움
+        let transform = Transform::from_translate(
+            ((width as f32 - line_width) / 2.0).round(),
+            (margin / 2.0 + index as f32 * line_height(font_size)).round(),
+        );
+        //테두리를 먼저 그리고 그 위에 채우면 글자 안쪽 테두리는 가려짐
+        if placement == CaptionPlacement::Overlay {
+            block.stroke_path(&path, &outline, &stroke, transform, None);
+        }
+        block.fill_path(&path, &fill, FillRule::Winding, transform, None);
+    }
+
+    Ok(block)
+}
+
+fn line_height(font_size: f32) -> f32 {
+    CAPTION_FONT.as_scaled(PxScale::from(font_size)).height() * 1.1
+}
+
+fn line_width(line: &str, font_size: f32) -> f32 {
+    line_path(line, font_size).map_or(0.0, |(_, width)| width)
+}
+
+//단어 단위로 줄을 나누고, 한 단어가 한 줄보다 길면 글자 단위로 자름
+fn wrap_lines(text: &str, font_size: f32, max_width: f32) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        let candidate = match current.is_empty() {
+            true => word.to_string(),
+            false => format!("{} {}", current, word),
+        };
+        if line_width(&candidate, font_size) <= max_width {
+            current = candidate;
+            continue;
+        }
+        if !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+        for c in word.chars() {
+            current.push(c);
+            if current.chars().count() > 1 && line_width(&current, font_size) > max_width {
+                current.pop();
+                lines.push(std::mem::replace(&mut current, c.to_string()));
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+//한 줄을 font_size 크기의 경로로 만듦. (0, 0)이 줄의 왼쪽 위이고, 가로 길이를 같이 돌려줌
+fn line_path(line: &str, font_size: f32) -> Option<(Path, f32)> {
+    let font = &*CAPTION_FONT;
+    let scaled = font.as_scaled(PxScale::from(font_size));
+    let baseline = scaled.ascent();
+    //unifont 픽셀은 정수 픽셀에 맞춰야 칸 사이에 테두리가 비치지 않음
+    let bitmap_pixel = (font_size / UNIFONT_HEIGHT).round().max(1.0);
+
+    let mut builder = PathBuilder::new();
+    let mut pen_x: f32 = 0.0;
+    let mut previous: Option<GlyphId> = None;
+    for c in line.chars() {
+        let glyph_id = font.glyph_id(c);
+        if glyph_id.0 == 0 && !c.is_whitespace() {
+            if let Some(glyph) = unifont::get_glyph(c) {
+                pen_x = pen_x.round();
+                push_bitmap_glyph(
+                    &mut builder,
+                    glyph,
+                    pen_x,
+                    (baseline - UNIFONT_ASCENT * bitmap_pixel).round(),
+                    bitmap_pixel,
+                );
+                pen_x += glyph.get_width() as f32 * bitmap_pixel;
+            }
+            previous = None;
+            continue;
+        }
+
+        if let Some(previous) = previous {
+            pen_x += scaled.kern(previous, glyph_id);
+        }
+        if let Some(outline) = font.outline(glyph_id) {
+            let (h_scale, v_scale) = (scaled.h_scale_factor(), scaled.v_scale_factor());
+            let point = |p: ab_glyph::Point| (pen_x + p.x * h_scale, baseline - p.y * v_scale);
+            push_outline(&mut builder, &outline.curves, point);
+        }
+        pen_x += scaled.h_advance(glyph_id);
+        previous = Some(glyph_id);
+    }
+
+    builder.finish().map(|path| (path, pen_x))
+}
+
+//곡선이 이전 곡선의 끝에서 이어지지 않으면 새 윤곽선으로 봄
+fn push_outline(
+    builder: &mut PathBuilder,
+    curves: &[OutlineCurve],
+    point: impl Fn(ab_glyph::Point) -> (f32, f32),
+) {
+    let mut last: Option<ab_glyph::Point> = None;
+    for curve in curves {
+        let start = match curve {
+            OutlineCurve::Line(start, _)
+            | OutlineCurve::Quad(start, _, _)
+            | OutlineCurve::Cubic(start, _, _, _) => *start,
+        };
+        if last != Some(start) {
+            if last.is_some() {
+                builder.close();
+            }
+            let (x, y) = point(start);
+            builder.move_to(x, y);
+        }
+        last = Some(match curve {
+            OutlineCurve::Line(_, end) => {
+                let (x, y) = point(*end);
+                builder.line_to(x, y);
+                *end
+            }
+            OutlineCurve::Quad(_, control, end) => {
+                let ((cx, cy), (x, y)) = (point(*control), point(*end));
+                builder.quad_to(cx, cy, x, y);
+                *end
+            }
+            OutlineCurve::Cubic(_, control1, control2, end) => {
+                let ((c1x, c1y), (c2x, c2y), (x, y)) =
+                    (point(*control1), point(*control2), point(*end));
+                builder.cubic_to(c1x, c1y, c2x, c2y, x, y);
+                *end
+            }
+        });
+    }
+    if last.is_some() {
+        builder.close();
+    }
+}
+
+//가로로 이어진 픽셀은 한 사각형으로 묶어서 넣음
+fn push_bitmap_glyph(
+    builder: &mut PathBuilder,
+    glyph: &unifont::Glyph,
+    left: f32,
+    top: f32,
+    pixel: f32,
+) {
+    for y in 0..UNIFONT_HEIGHT as usize {
+        let mut x = 0;
+        while x < glyph.get_width() {
+            if !glyph.get_pixel(x, y) {
+                x += 1;
+                continue;
+            }
+            let run_start = x;
+            while x < glyph.get_width() && glyph.get_pixel(x, y) {
+                x += 1;
+            }
+            if let Some(rect) = Rect::from_xywh(
+                left + run_start as f32 * pixel,
+                top + y as f32 * pixel,
+                (x - run_start) as f32 * pixel,
+                pixel,
+            ) {
+                builder.push_rect(rect);
+            }
+        }
+    }
+}
+
+fn encode_png(frame: &RgbaImage) -> Result<Vec<u8>, ImageTransferError> {
+    let mut bytes = Vec::new();
+    frame
+        .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .map_err(|_| ImageTransferError::PngEncodingError)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn has_color(frame: &RgbaImage, color: [u8; 3]) -> bool {
+        frame
+            .pixels()
+            .any(|pixel| pixel[3] == 255 && pixel.0[..3] == color)
+    }
+
+    //라틴 문자와 한글이 모두 그려지고, 움짤이면 프레임마다 들어가야 함
+    #[test]
+    fn captions_every_frame_with_hangul() {
+        let frames = vec![
+            (
+                RgbaImage::from_pixel(64, 64, image::Rgba([0, 128, 0, 255])),
+                10,
+            ),
+            (
+                RgbaImage::from_pixel(64, 64, image::Rgba([0, 0, 128, 255])),
+                10,
+            ),
+        ];
+        let caption = Caption {
+            top: Some("LOL".to_string()),
+            bottom: Some("한글 자막".to_string()),
+            placement: CaptionPlacement::Overlay,
+        };
+        let captioned = caption_frames(frames, &caption).unwrap();
+
+        assert_eq!(captioned.len(), 2);
+        for (frame, _) in &captioned {
+            assert_eq!(
+                frame.dimensions(),
+                (CAPTION_CANVAS_SIZE, CAPTION_CANVAS_SIZE)
+            );
+            let (top, bottom) = (
+                imageops::crop_imm(frame, 0, 0, 256, 128).to_image(),
+                imageops::crop_imm(frame, 0, 128, 256, 128).to_image(),
+            );
+            for half in [top, bottom] {
+                assert!(has_color(&half, [255, 255, 255]));
+                assert!(has_color(&half, [0, 0, 0]));
+            }
+        }
+    }
+
+    //바깥 띠는 캔버스를 위아래로 늘림
+    #[test]
+    fn outside_placement_extends_canvas() {
+        let frames = vec![(RgbaImage::new(256, 256), 0)];
+        let caption = Caption {
+            top: Some("top text".to_string()),
+            bottom: None,
+            placement: CaptionPlacement::Outside,
+        };
+        let captioned = caption_frames(frames, &caption).unwrap();
+        let (frame, _) = &captioned[0];
+        assert_eq!(frame.width(), 256);
+        assert!(frame.height() > 256);
+        assert_eq!(frame.get_pixel(0, 0).0, [255, 255, 255, 255]);
+    }
+
+    //긴 문구는 줄을 나누고 글자를 줄여서 가로 안에 넣음
+    #[test]
+    fn long_caption_wraps_within_width() {
+        let text = "this caption is way too long to fit on a single line of an emoji";
+        let lines = wrap_lines(text, 24.0, 200.0);
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| line_width(line, 24.0) <= 200.0));
+        assert_eq!(lines.join(" "), text);
+    }
+}
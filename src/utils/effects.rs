@@ -0,0 +1,395 @@
+//이모지에 효과(뒤집기, 회전, 색 바꾸기, 흐리게, 튀기기 등)를 줘서 다시 보내는 부분
+//움짤이면 프레임마다 똑같이 적용함
+use image::{imageops, ImageEncoder, RgbaImage};
+use serenity::builder::CreateAttachment;
+use tiny_skia::{FilterQuality, Pixmap, PixmapPaint, Transform};
+
+use std::io::Cursor;
+
+use crate::utils::emoji_cache::CachedImage;
+use crate::utils::scene_core::{
+    decode_emoji_frames, encode_gif_frames, fetch_image_bytes, EmojiFrames, ImageTransferError,
+    MAX_CUSTOM_SIZE,
+};
+use crate::utils::upload_limit::{fit_to_upload_limit, SizeCompromise};
+
+//한 번에 이어서 걸 수 있는 효과 개수
+pub const MAX_CHAINED_EFFECTS: usize = 8;
+
+const MAX_SATURATION: f32 = 5.0;
+const MAX_PIXELATE_BLOCK: u32 = 64;
+const MAX_BLUR_SIGMA: f32 = 20.0;
+//원본 해상도로 받으면 큰 이모지는 효과마다 너무 무거워지니까 이 크기로 받아옴
+const TRANSFORM_FETCH_SIZE: u32 = 256;
+
+//튀기기 할 때 JPEG 품질. 낮을수록 더 바삭해짐
+const DEEP_FRY_JPEG_QUALITY: u8 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    FlipHorizontal,
+    FlipVertical,
+    //시계 방향, 도 단위
+    Rotate(f32),
+    Grayscale,
+    Invert,
+    //도 단위
+    HueShift(f32),
+    //1이 원본, 0이 흑백
+    Saturation(f32),
+    //블록 한 변의 픽셀 수
+    Pixelate(u32),
+    Blur(f32),
+    DeepFry,
+}
+
+impl Effect {
+    pub fn name(&self) -> String {
+        match self {
+            Self::FlipHorizontal => "좌우 뒤집기".to_string(),
+            Self::FlipVertical => "상하 뒤집기".to_string(),
+            Self::Rotate(degrees) => format!("{}도 회전", degrees),
+            Self::Grayscale => "흑백".to_string(),
+            Self::Invert => "색 반전".to_string(),
+            Self::HueShift(degrees) => format!("색조 {}도", degrees),
+            Self::Saturation(factor) => format!("채도 {}배", factor),
+            Self::Pixelate(block) => format!("모자이크 {}px", block),
+            Self::Blur(sigma) => format!("흐리게 {}", sigma),
+            Self::DeepFry => "튀기기".to_string(),
+        }
+    }
+}
+
+//"fliph rotate:45 hue:120" 처럼 공백, 쉼표, >로 나눈 효과들을 순서대로 읽음
+pub fn parse_effects(input: &str) -> Result<Vec<Effect>, String> {
+    let effects = input
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '>')
+        .filter(|token| !token.is_empty())
+        .map(parse_effect)
+        .collect::<Result<Vec<Effect>, String>>()?;
+
+    if effects.is_empty() {
+        return Err("효과를 하나 이상 입력해주세요".to_string());
+    }
+    if effects.len() > MAX_CHAINED_EFFECTS {
+        return Err(format!(
+            "효과는 한 번에 {}개까지만 걸 수 있습니다",
+            MAX_CHAINED_EFFECTS
+        ));
+    }
+    Ok(effects)
+}
+
+fn parse_effect(token: &str) -> Result<Effect, String> {
+    let (name, value) = match token.split_once([':', '=']) {
+        Some((name, value)) => (name, Some(value)),
+        None => (token, None),
+    };
+    let number = |default: f32| -> Result<f32, String> {
+        match value {
+            Some(value) => value
+                .parse::<f32>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .ok_or(format!("\"{}\"의 값이 숫자가 아닙니다", token)),
+            None => Ok(default),
+        }
+    };
+    let out_of_range = || format!("\"{}\"의 값이 허용 범위를 벗어났습니다", token);
+
+    match name.to_lowercase().as_str() {
+        "fliph" | "flip_h" | "hflip" | "mirror" => Ok(Effect::FlipHorizontal),
+        "flipv" | "flip_v" | "vflip" => Ok(Effect::FlipVertical),
+        "rotate" | "rot" => Ok(Effect::Rotate(number(90.0)?.rem_euclid(360.0))),
+        "grayscale" | "gray" | "grey" => Ok(Effect::Grayscale),
+        "invert" => Ok(Effect::Invert),
+        "hue" => Ok(Effect::HueShift(number(180.0)?.rem_euclid(360.0))),
+        "saturate" | "saturation" | "sat" => {
+            let factor = number(2.0)?;
+            match (0.0..=MAX_SATURATION).contains(&factor) {
+                true => Ok(Effect::Saturation(factor)),
+                false => Err(out_of_range()),
+            }
+        }
+        "pixelate" | "pixel" => {
+            let block = number(8.0)?.round() as u32;
+            match (2..=MAX_PIXELATE_BLOCK).contains(&block) {
+                true => Ok(Effect::Pixelate(block)),
+                false => Err(out_of_range()),
+            }
+        }
+        "blur" => {
+            let sigma = number(2.0)?;
+            match sigma > 0.0 && sigma <= MAX_BLUR_SIGMA {
+                true => Ok(Effect::Blur(sigma)),
+                false => Err(out_of_range()),
+            }
+        }
+        "deepfry" | "deep_fry" | "fry" => Ok(Effect::DeepFry),
+        _ => Err(format!("\"{}\"는 모르는 효과입니다", name)),
+    }
+}
+
+//정적 이모지는 png로, 움짤은 프레임마다 효과를 건 gif로 보냄
+pub async fn transform_emoji(
+    img_url: &str,
+    effects: &[Effect],
+    upload_limit: u64,
+) -> Result<(CreateAttachment, Option<SizeCompromise>), ImageTransferError> {
+    let img_bytes =
+        fetch_image_bytes(&format!("{}?size={}", img_url, TRANSFORM_FETCH_SIZE)).await?;
+    let effects = effects.to_vec();
+
+    let (transformed, compromise) = tokio::task::spawn_blocking(move || {
+        let frames = decode_emoji_frames(&img_bytes)?
+            .into_iter()
+            .map(|(frame, delay)| Ok((apply_effects(frame, &effects)?, delay)))
+            .collect::<Result<EmojiFrames, ImageTransferError>>()?;
+
+        let transformed = match frames.len() {
+            1 => CachedImage {
+                bytes: encode_png(&frames[0].0)?,
+                filename: "transformed.png".to_string(),
+            },
+            _ => CachedImage {
+                bytes: encode_gif_frames(frames)?,
+                filename: "transformed.gif".to_string(),
+            },
+        };
+        fit_to_upload_limit(transformed, upload_limit)
+    })
+    .await
+    .map_err(|_| ImageTransferError::Mollu)??;
+
+    Ok((
+        CreateAttachment::bytes(transformed.bytes, transformed.filename),
+        compromise,
+    ))
+}
+
+pub fn apply_effects(
+    mut frame: RgbaImage,
+    effects: &[Effect],
+) -> Result<RgbaImage, ImageTransferError> {
+    for effect in effects {
+        frame = match *effect {
+            Effect::FlipHorizontal => imageops::flip_horizontal(&frame),
+            Effect::FlipVertical => imageops::flip_vertical(&frame),
+            Effect::Rotate(degrees) => rotate(&frame, degrees)?,
+            Effect::Grayscale => saturate(frame, 0.0),
+            Effect::Invert => map_rgb(frame, |[r, g, b]| [255 - r, 255 - g, 255 - b]),
+            Effect::HueShift(degrees) => imageops::huerotate(&frame, degrees.round() as i32),
+            Effect::Saturation(factor) => saturate(frame, factor),
+            Effect::Pixelate(block) => pixelate(&frame, block),
+            Effect::Blur(sigma) => blur(&frame, sigma),
+            Effect::DeepFry => deep_fry(frame)?,
+        };
+        //회전은 효과마다 캔버스가 최대 √2배씩 커지니까, 이어서 걸어도 커지지 않게 매번 줄여둠
+        frame = clamp_canvas(frame);
+    }
+    Ok(frame)
+}
+
+fn clamp_canvas(frame: RgbaImage) -> RgbaImage {
+    let (width, height) = frame.dimensions();
+    if width <= MAX_CUSTOM_SIZE && height <= MAX_CUSTOM_SIZE {
+        return frame;
+    }
+    let ratio = MAX_CUSTOM_SIZE as f32 / width.max(height) as f32;
+    imageops::resize(
+        &frame,
+        ((width as f32 * ratio).round() as u32).clamp(1, MAX_CUSTOM_SIZE),
+        ((height as f32 * ratio).round() as u32).clamp(1, MAX_CUSTOM_SIZE),
+        imageops::FilterType::Triangle,
+    )
+}
+
+fn map_rgb(mut frame: RgbaImage, map: impl Fn([u8; 3]) -> [u8; 3]) -> RgbaImage {
+    for pixel in frame.pixels_mut() {
+        let [r, g, b] = map([pixel[0], pixel[1], pixel[2]]);
+        pixel.0 = [r, g, b, pixel[3]];
+    }
+    frame
+}
+
+//밝기(Rec. 601)는 그대로 두고 색만 factor배로 벌리거나 좁힘
+fn saturate(frame: RgbaImage, factor: f32) -> RgbaImage {
+    map_rgb(frame, |rgb| {
+        let luma = 0.299 * rgb[0] as f32 + 0.587 * rgb[1] as f32 + 0.114 * rgb[2] as f32;
+        rgb.map(|channel| (luma + (channel as f32 - luma) * factor).clamp(0.0, 255.0) as u8)
+    })
+}
+
+//90도 단위는 그대로 돌리고, 나머지 각도는 잘리지 않게 캔버스를 키워서 돌림
+fn rotate(frame: &RgbaImage, degrees: f32) -> Result<RgbaImage, ImageTransferError> {
+    if degrees.fract() == 0.0 {
+        match degrees as u32 {
+            0 => return Ok(frame.clone()),
+            90 => return Ok(imageops::rotate90(frame)),
+            180 => return Ok(imageops::rotate180(frame)),
+            270 => return Ok(imageops::rotate270(frame)),
+            _ => {}
+        }
+    }
+
+    let (width, height) = (frame.width() as f32, frame.height() as f32);
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let dst_width = (width * cos.abs() + height * sin.abs()).ceil() as u32;
+    let dst_height = (width * sin.abs() + height * cos.abs()).ceil() as u32;
+
+    let source = to_pixmap(frame).ok_or(ImageTransferError::ResizeError)?;
+    let mut pixmap = Pixmap::new(dst_width, dst_height).ok_or(ImageTransferError::ResizeError)?;
+    let transform = Transform::from_translate(-width / 2.0, -height / 2.0)
+        .post_rotate(degrees)
+        .post_translate(dst_width as f32 / 2.0, dst_height as f32 / 2.0);
+    pixmap.draw_pixmap(
+        0,
+        0,
+        source.as_ref(),
+        &PixmapPaint {
+            quality: FilterQuality::Bicubic,
+            ..PixmapPaint::default()
+        },
+        transform,
+        None,
+    );
+
+    Ok(from_pixmap(&pixmap))
+}
+
+//tiny-skia는 알파를 곱한 픽셀로 그리니까 오갈 때마다 바꿔줌
+pub fn to_pixmap(frame: &RgbaImage) -> Option<Pixmap> {
+    let mut pixmap = Pixmap::new(frame.width(), frame.height())?;
+    for (dst, src) in pixmap.pixels_mut().iter_mut().zip(frame.pixels()) {
+        *dst = tiny_skia::ColorU8::from_rgba(src[0], src[1], src[2], src[3]).premultiply();
+    }
+    Some(pixmap)
+}
+
+pub fn from_pixmap(pixmap: &Pixmap) -> RgbaImage {
+    let mut frame = RgbaImage::new(pixmap.width(), pixmap.height());
+    for (dst, src) in frame.pixels_mut().zip(pixmap.pixels()) {
+        let color = src.demultiply();
+        dst.0 = [color.red(), color.green(), color.blue(), color.alpha()];
+    }
+    frame
+}
+
+//블록마다 평균 색으로 칠함. 투명한 픽셀은 색 평균에 안 넣음
+fn pixelate(frame: &RgbaImage, block: u32) -> RgbaImage {
+    let mut pixelated = frame.clone();
+    for block_top in (0..frame.height()).step_by(block as usize) {
+        for block_left in (0..frame.width()).step_by(block as usize) {
+            let bottom = (block_top + block).min(frame.height());
+            let right = (block_left + block).min(frame.width());
+
+            let mut sum = [0u64; 4];
+            for y in block_top..bottom {
+                for x in block_left..right {
+                    let pixel = frame.get_pixel(x, y);
+                    let alpha = pixel[3] as u64;
+                    for channel in 0..3 {
+                        sum[channel] += pixel[channel] as u64 * alpha;
+                    }
+                    sum[3] += alpha;
+                }
+            }
+
+            let count = ((bottom - block_top) * (right - block_left)) as u64;
+            let average = match sum[3] {
+                0 => [0, 0, 0, 0],
+                alpha_sum => [
+                    (sum[0] / alpha_sum) as u8,
+                    (sum[1] / alpha_sum) as u8,
+                    (sum[2] / alpha_sum) as u8,
+                    (alpha_sum / count) as u8,
+                ],
+            };
+            for y in block_top..bottom {
+                for x in block_left..right {
+                    pixelated.put_pixel(x, y, image::Rgba(average));
+                }
+            }
+        }
+    }
+    pixelated
+}
+
+//그냥 흐리게 하면 투명한 부분의 검은색이 가장자리로 번지니까 알파를 곱한 상태로 흐림
+fn blur(frame: &RgbaImage, sigma: f32) -> RgbaImage {
+    let mut premultiplied = frame.clone();
+    for pixel in premultiplied.pixels_mut() {
+        let alpha = pixel[3] as u32;
+        for channel in 0..3 {
+            pixel[channel] = ((pixel[channel] as u32 * alpha + 127) / 255) as u8;
+        }
+    }
+
+    let mut blurred = imageops::blur(&premultiplied, sigma);
+    for pixel in blurred.pixels_mut() {
+        let alpha = pixel[3] as u32;
+        if alpha == 0 {
+            continue;
+        }
+        for channel in 0..3 {
+            pixel[channel] = ((pixel[channel] as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+        }
+    }
+    blurred
+}
+
+//채도와 대비를 확 올리고 저화질 JPEG로 한 번 구웠다가 다시 읽음. 투명도는 원래 걸 씀
+fn deep_fry(frame: RgbaImage) -> Result<RgbaImage, ImageTransferError> {
+    let fried = map_rgb(saturate(frame, 2.5), |rgb| {
+        rgb.map(|channel| ((channel as f32 - 128.0) * 1.6 + 128.0).clamp(0.0, 255.0) as u8)
+    });
+    let fried = imageops::unsharpen(&fried, 1.5, 4);
+
+    let rgb = image::DynamicImage::ImageRgba8(fried.clone()).to_rgb8();
+    let mut jpeg = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, DEEP_FRY_JPEG_QUALITY)
+        .write_image(
+            rgb.as_raw(),
+            rgb.width(),
+            rgb.height(),
+            image::ColorType::Rgb8,
+        )
+        .map_err(|_| ImageTransferError::PngEncodingError)?;
+    let crunchy = image::load(Cursor::new(jpeg), image::ImageFormat::Jpeg)
+        .map_err(|_| ImageTransferError::DecodingError)?
+        .to_rgb8();
+
+    let mut result = fried;
+    for (dst, src) in result.pixels_mut().zip(crunchy.pixels()) {
+        dst.0 = [src[0], src[1], src[2], dst[3]];
+    }
+    Ok(result)
+}
+
+fn encode_png(frame: &RgbaImage) -> Result<Vec<u8>, ImageTransferError> {
+    let mut result_buf = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut result_buf)
+        .write_image(
+            frame.as_raw(),
+            frame.width(),
+            frame.height(),
+            image::ColorType::Rgba8,
+        )
+        .map_err(|_| ImageTransferError::PngEncodingError)?;
+    Ok(result_buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //회전을 이어서 걸어도 캔버스가 MAX_CUSTOM_SIZE를 넘지 않아야 함
+    //조금만 돌려도 가로가 넘치도록 가로로 긴 이미지를 씀
+    #[test]
+    fn chained_rotation_stays_within_canvas_limit() {
+        let frame = RgbaImage::from_pixel(MAX_CUSTOM_SIZE, 16, image::Rgba([255, 0, 0, 255]));
+        let effects = [Effect::Rotate(1.0); MAX_CHAINED_EFFECTS];
+        let rotated = apply_effects(frame, &effects).unwrap();
+        assert!(rotated.width() <= MAX_CUSTOM_SIZE && rotated.height() <= MAX_CUSTOM_SIZE);
+    }
+}
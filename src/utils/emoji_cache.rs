@@ -0,0 +1,488 @@
+//변환한 이모지 이미지를 메모리(+디스크)에 들고 있는 캐시
+//같은 이모지를 도배해도 CDN에서 다시 받아서 다시 리사이즈하지 않게 함
+use futures::future::{BoxFuture, FutureExt, Shared};
+use log::{error, info};
+use lru::LruCache;
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+
+use crate::utils::scene_core::{
+    AnimatedFormat, FitMode, ImageSize, ImageTransferError, ResizeFilter,
+};
+
+const DEFAULT_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+const DEFAULT_DISK_LIMIT: u64 = 512 * 1024 * 1024;
+//몇 번 조회할 때마다 적중률을 로그로 남길지
+const STATS_LOG_INTERVAL: u64 = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EmojiCacheKey {
+    pub emoji_id: u64,
+    pub animated: bool,
+    pub size: ImageSize,
+    pub filter: ResizeFilter,
+    pub fit: FitMode,
+    pub output: AnimatedFormat,
+}
+
+impl EmojiCacheKey {
+    fn file_stem(&self) -> String {
+        format!(
+            "{}_{}_{}_{}_{}_{}",
+            self.emoji_id,
+            if self.animated { "a" } else { "s" },
+            ImageSize::value_to_string(&self.size),
+            ResizeFilter::value_to_string(&self.filter),
+            FitMode::value_to_string(&self.fit),
+            AnimatedFormat::value_to_string(&self.output)
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedImage {
+    pub bytes: Vec<u8>,
+    pub filename: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EmojiCacheStats {
+    pub memory_hits: u64,
+    pub disk_hits: u64,
+    //이미 진행중인 변환에 같이 붙은 횟수
+    pub shared_conversions: u64,
+    pub misses: u64,
+}
+
+type PendingConversion = Shared<BoxFuture<'static, Result<CachedImage, ImageTransferError>>>;
+
+struct CacheState {
+    entries: LruCache<EmojiCacheKey, CachedImage>,
+    memory_bytes: usize,
+    //디스크 캐시는 파일 이름 기준으로 추적함. 재시작해도 폴더를 다시 읽어서 이어감
+    disk_entries: LruCache<String, u64>,
+    disk_bytes: u64,
+    in_flight: HashMap<EmojiCacheKey, PendingConversion>,
+}
+
+pub struct EmojiCache {
+    state: Mutex<CacheState>,
+    memory_limit: usize,
+    disk_dir: Option<PathBuf>,
+    disk_limit: u64,
+    memory_hits: AtomicU64,
+    disk_hits: AtomicU64,
+    shared_conversions: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl EmojiCache {
+    pub fn new(memory_limit: usize, disk_dir: Option<PathBuf>, disk_limit: u64) -> Self {
+        let mut disk_entries = LruCache::unbounded();
+        let mut disk_bytes = 0;
+
+        if let Some(dir) = &disk_dir {
+            if let Err(why) = std::fs::create_dir_all(dir) {
+                error!("couldn't create emoji cache directory. {:?}", why);
+            }
+
+            //오래된 파일부터 넣어야 제일 먼저 지워짐
+            let mut files = std::fs::read_dir(dir)
+                .map(|entries| {
+                    entries
+                        .filter_map(|entry| entry.ok())
+                        .filter_map(|entry| {
+                            let metadata = entry.metadata().ok()?;
+                            Some((
+                                metadata.modified().ok()?,
+                                entry.file_name().to_string_lossy().to_string(),
+                                metadata.len(),
+                            ))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            files.sort();
+
+            for (_, filename, len) in files {
+                disk_bytes += len;
+                disk_entries.put(filename, len);
+            }
+        }
+
+        Self {
+            state: Mutex::new(CacheState {
+                entries: LruCache::unbounded(),
+                memory_bytes: 0,
+                disk_entries,
+                disk_bytes,
+                in_flight: HashMap::new(),
+            }),
+            memory_limit,
+            disk_dir,
+            disk_limit,
+            memory_hits: AtomicU64::new(0),
+            disk_hits: AtomicU64::new(0),
+            shared_conversions: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    //EMOJI_CACHE_DIR이 없으면 메모리 캐시만 씀
+    pub fn from_env() -> Self {
+        let memory_limit = std::env::var("EMOJI_CACHE_MAX_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MEMORY_LIMIT);
+        let disk_dir = std::env::var("EMOJI_CACHE_DIR").ok().map(PathBuf::from);
+        let disk_limit = std::env::var("EMOJI_CACHE_DISK_MAX_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_DISK_LIMIT);
+
+        Self::new(memory_limit, disk_dir, disk_limit)
+    }
+
+    pub fn stats(&self) -> EmojiCacheStats {
+        EmojiCacheStats {
+            memory_hits: self.memory_hits.load(Ordering::Relaxed),
+            disk_hits: self.disk_hits.load(Ordering::Relaxed),
+            shared_conversions: self.shared_conversions.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    //캐시에 있으면 바로 돌려주고, 없으면 convert를 한 번만 돌림
+    //같은 키로 동시에 들어온 요청들은 진행중인 변환 하나를 같이 기다림
+    pub async fn get_or_convert<F, Fut>(
+        &self,
+        key: EmojiCacheKey,
+        convert: F,
+    ) -> Result<CachedImage, ImageTransferError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<CachedImage, ImageTransferError>> + Send + 'static,
+    {
+        let pending = {
+            let mut state = self.state.lock().unwrap();
+            if let Some(cached) = state.entries.get(&key) {
+                let cached = cached.clone();
+                drop(state);
+                self.record(&self.memory_hits);
+                return Ok(cached);
+            }
+
+            match state.in_flight.get(&key).cloned() {
+                Some(pending) => {
+                    self.record(&self.shared_conversions);
+                    pending
+                }
+                None => {
+                    let disk_path = self.disk_path(&key, &mut state);
+                    let pending = match disk_path {
+                        Some((path, filename)) => {
+                            self.record(&self.disk_hits);
+                            //누가 파일을 지웠으면 그냥 다시 변환함
+                            let converting = convert();
+                            async move {
+                                match tokio::fs::read(path).await {
+                                    Ok(bytes) => Ok(CachedImage { bytes, filename }),
+                                    Err(_) => converting.await,
+                                }
+                            }
+                            .boxed()
+                        }
+                        None => {
+                            self.record(&self.misses);
+                            convert().boxed()
+                        }
+                    }
+                    .shared();
+                    state.in_flight.insert(key.clone(), pending.clone());
+                    pending
+                }
+            }
+        };
+
+        let result = pending.await;
+
+        //제일 먼저 끝난 쪽이 캐시에 넣고 진행중 목록에서 뺌
+        let finished_first = self.state.lock().unwrap().in_flight.remove(&key).is_some();
+        if finished_first {
+            if let Ok(cached) = &result {
+                self.insert(key, cached.clone()).await;
+            }
+        }
+
+        result
+    }
+
+    fn disk_path(&self, key: &EmojiCacheKey, state: &mut CacheState) -> Option<(PathBuf, String)> {
+        let dir = self.disk_dir.as_ref()?;
+        ["png", "gif", "webp"].iter().find_map(|ext| {
+            let filename = format!("{}.{}", key.file_stem(), ext);
+            state
+                .disk_entries
+                .get(&filename)
+                .map(|_| (dir.join(&filename), format!("resized.{}", ext)))
+        })
+    }
+
+    async fn insert(&self, key: EmojiCacheKey, cached: CachedImage) {
+        let ext = cached
+            .filename
+            .rsplit('.')
+            .next()
+            .unwrap_or("png")
+            .to_string();
+        let disk_filename = format!("{}.{}", key.file_stem(), ext);
+        let bytes_len = cached.bytes.len();
+        let disk_bytes = cached.bytes.clone();
+
+        let evicted_files = {
+            let mut state = self.state.lock().unwrap();
+            if let Some(old) = state.entries.put(key, cached) {
+                state.memory_bytes -= old.bytes.len();
+            }
+            state.memory_bytes += bytes_len;
+            while state.memory_bytes > self.memory_limit {
+                match state.entries.pop_lru() {
+                    Some((_, evicted)) => state.memory_bytes -= evicted.bytes.len(),
+                    None => break,
+                }
+            }
+
+            let mut evicted_files = Vec::new();
+            if self.disk_dir.is_some() && !state.disk_entries.contains(&disk_filename) {
+                state
+                    .disk_entries
+                    .put(disk_filename.clone(), bytes_len as u64);
+                state.disk_bytes += bytes_len as u64;
+                while state.disk_bytes > self.disk_limit {
+                    match state.disk_entries.pop_lru() {
+                        Some((filename, len)) => {
+                            state.disk_bytes -= len;
+                            evicted_files.push(filename);
+                        }
+                        None => break,
+                    }
+                }
+            }
+            evicted_files
+        };
+
+        let Some(dir) = &self.disk_dir else {
+            return;
+        };
+
+        //디스크 한도보다 큰 파일이면 방금 넣은 것도 바로 밀려나니까 쓰지 않음
+        let mut write_to_disk = true;
+        for filename in evicted_files {
+            if filename == disk_filename {
+                write_to_disk = false;
+                continue;
+            }
+            if let Err(why) = tokio::fs::remove_file(dir.join(filename)).await {
+                error!("couldn't remove cached emoji file. {:?}", why);
+            }
+        }
+
+        if !write_to_disk {
+            return;
+        }
+
+        if let Err(why) = tokio::fs::write(dir.join(&disk_filename), disk_bytes).await {
+            error!("couldn't write cached emoji file. {:?}", why);
+        }
+    }
+
+    fn record(&self, counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        let stats = self.stats();
+        let total = stats.memory_hits + stats.disk_hits + stats.shared_conversions + stats.misses;
+        if total.is_multiple_of(STATS_LOG_INTERVAL) {
+            info!(
+                "emoji cache: {} memory hits, {} disk hits, {} shared conversions, {} misses",
+                stats.memory_hits, stats.disk_hits, stats.shared_conversions, stats.misses
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    fn key(emoji_id: u64) -> EmojiCacheKey {
+        EmojiCacheKey {
+            emoji_id,
+            animated: false,
+            size: ImageSize::Medium,
+            filter: ResizeFilter::Auto,
+            fit: FitMode::Contain,
+            output: AnimatedFormat::Gif,
+        }
+    }
+
+    fn image(len: usize) -> CachedImage {
+        CachedImage {
+            bytes: vec![0; len],
+            filename: "resized.png".to_string(),
+        }
+    }
+
+    //실제로 변환을 돌린 횟수를 셈. 디스크에서 읽을 때 만들어두기만 한 변환은 세지 않음
+    fn counting(
+        calls: &Arc<AtomicUsize>,
+        len: usize,
+    ) -> impl FnOnce() -> BoxFuture<'static, Result<CachedImage, ImageTransferError>> {
+        gated(calls, len, async {}.boxed().shared())
+    }
+
+    //gate가 열릴 때까지 변환이 끝나지 않게 붙잡아둠
+    fn gated(
+        calls: &Arc<AtomicUsize>,
+        len: usize,
+        gate: Shared<BoxFuture<'static, ()>>,
+    ) -> impl FnOnce() -> BoxFuture<'static, Result<CachedImage, ImageTransferError>> {
+        let calls = calls.clone();
+        move || {
+            async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                gate.await;
+                Ok(image(len))
+            }
+            .boxed()
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("emoji_cache_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn concurrent_requests_share_one_conversion() {
+        let cache = EmojiCache::new(1024, None, 0);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let (open, gate) = futures::channel::oneshot::channel::<()>();
+        let gate = gate.map(|_| ()).boxed().shared();
+
+        //join!은 순서대로 poll하니까 세 요청이 다 붙은 다음에 gate가 열림
+        let (first, second, third, _) = futures::join!(
+            cache.get_or_convert(key(1), gated(&calls, 10, gate.clone())),
+            cache.get_or_convert(key(1), gated(&calls, 10, gate.clone())),
+            cache.get_or_convert(key(1), gated(&calls, 10, gate.clone())),
+            async move { open.send(()).unwrap() },
+        );
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(first.is_ok() && second.is_ok() && third.is_ok());
+        let stats = cache.stats();
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.shared_conversions, 2);
+    }
+
+    #[tokio::test]
+    async fn counts_memory_hits_and_misses() {
+        let cache = EmojiCache::new(1024, None, 0);
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        cache
+            .get_or_convert(key(1), counting(&calls, 10))
+            .await
+            .unwrap();
+        cache
+            .get_or_convert(key(1), counting(&calls, 10))
+            .await
+            .unwrap();
+        cache
+            .get_or_convert(key(2), counting(&calls, 10))
+            .await
+            .unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        let stats = cache.stats();
+        assert_eq!(stats.memory_hits, 1);
+        assert_eq!(stats.misses, 2);
+        assert_eq!(stats.disk_hits, 0);
+        assert_eq!(stats.shared_conversions, 0);
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used_from_memory() {
+        //두 개까지만 들어감
+        let cache = EmojiCache::new(25, None, 0);
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        for emoji_id in [1, 2, 1, 3] {
+            cache
+                .get_or_convert(key(emoji_id), counting(&calls, 10))
+                .await
+                .unwrap();
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        //1은 방금 썼으니 남아있고 2가 밀려남
+        cache
+            .get_or_convert(key(1), counting(&calls, 10))
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        cache
+            .get_or_convert(key(2), counting(&calls, 10))
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        assert_eq!(cache.state.lock().unwrap().memory_bytes, 20);
+    }
+
+    #[tokio::test]
+    async fn evicts_oldest_files_past_disk_limit() {
+        let dir = temp_dir("disk_limit");
+        //메모리에는 하나만 남기고 디스크에는 두 개까지 남김
+        let cache = EmojiCache::new(10, Some(dir.clone()), 25);
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        for emoji_id in [1, 2, 3] {
+            cache
+                .get_or_convert(key(emoji_id), counting(&calls, 10))
+                .await
+                .unwrap();
+        }
+
+        let file = |emoji_id| dir.join(format!("{}.png", key(emoji_id).file_stem()));
+        assert!(!file(1).exists());
+        assert!(file(2).exists());
+        assert!(file(3).exists());
+        assert_eq!(cache.state.lock().unwrap().disk_bytes, 20);
+
+        //메모리에서는 밀려났지만 디스크에서 다시 읽음
+        let cached = cache
+            .get_or_convert(key(2), counting(&calls, 10))
+            .await
+            .unwrap();
+        assert_eq!(cached.bytes.len(), 10);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(cache.stats().disk_hits, 1);
+
+        //한도보다 큰 파일은 쓰지 않고 있던 파일도 그대로 둠
+        cache
+            .get_or_convert(key(4), counting(&calls, 30))
+            .await
+            .unwrap();
+        assert!(!file(4).exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,374 @@
+//메시지 내용을 커스텀 이모지, 유니코드 이모지, 스포일러, 공백, 글자로 나누는 부분
+//자동 확대, 두 이모지 합치기, /send가 다 이걸로 메시지를 읽음
+use twemoji_assets::svg::SvgTwemojiAsset;
+
+use crate::utils::twemoji::find_twemoji;
+
+//Twemoji에서 제일 긴 이모지(ZWJ 조합)의 글자 수
+const MAX_UNICODE_EMOJI_CHARS: usize = 10;
+const SPOILER_MARK: &str = "||";
+
+//<:이름:ID> 또는 움짤이면 <a:이름:ID>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CustomEmoji<'a> {
+    pub animated: bool,
+    pub name: &'a str,
+    pub id: u64,
+}
+
+impl CustomEmoji<'_> {
+    pub fn url(&self) -> String {
+        format!(
+            "https://cdn.discordapp.com/emojis/{}.{}",
+            self.id,
+            if self.animated { "gif" } else { "webp" }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmojiToken<'a> {
+    Custom(CustomEmoji<'a>),
+    Unicode {
+        text: &'a str,
+        emoji: &'static SvgTwemojiAsset,
+    },
+    //||로 감싼 부분. 안쪽도 똑같이 나눠서 들고 있음 (스포일러 안에 스포일러는 없음)
+    Spoiler(Vec<EmojiToken<'a>>),
+    Whitespace(&'a str),
+    Text(&'a str),
+}
+
+impl EmojiToken<'_> {
+    pub fn is_emoji(&self) -> bool {
+        matches!(self, Self::Custom(_) | Self::Unicode { .. })
+    }
+}
+
+pub fn tokenize(content: &str) -> Vec<EmojiToken<'_>> {
+    tokenize_with(content, true)
+}
+
+//이모지만 있는 메시지에서 꺼낸 이모지들. 하나라도 ||로 가려져 있으면 spoiler
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmojiOnly<'a> {
+    pub emojis: Vec<EmojiToken<'a>>,
+    pub spoiler: bool,
+}
+
+//공백만 빼고 전부 이모지면 이모지만 순서대로 돌려줌. 스포일러 안쪽도 이모지만 있어야 함
+pub fn only_emojis<'a>(tokens: &[EmojiToken<'a>]) -> Option<EmojiOnly<'a>> {
+    let mut emojis = Vec::new();
+    let mut spoiler = false;
+    for token in tokens {
+        match token {
+            EmojiToken::Whitespace(_) => {}
+            EmojiToken::Spoiler(inner) => {
+                emojis.extend(only_emojis(inner)?.emojis);
+                spoiler = true;
+            }
+            token if token.is_emoji() => emojis.push(token.clone()),
+            _ => return None,
+        }
+    }
+    Some(EmojiOnly { emojis, spoiler })
+}
+
+//스포일러 안쪽은 ||를 따로 보지 않고 나눔
+fn tokenize_with(content: &str, spoilers: bool) -> Vec<EmojiToken<'_>> {
+    let mut tokens = Vec::new();
+    //아직 토큰으로 안 넣은 글자가 시작된 위치. 붙어있는 글자는 한 토큰으로 합침
+    let mut text_start = None;
+    let mut pos = 0;
+
+    while pos < content.len() {
+        let rest = &content[pos..];
+
+        let spoiler = rest
+            .strip_prefix(SPOILER_MARK)
+            .filter(|_| spoilers)
+            //닫는 ||가 없으면 그냥 글자로 둠
+            .and_then(|inner| inner.find(SPOILER_MARK).map(|end| &inner[..end]));
+        let (token, len) = match spoiler {
+            Some(inner) => (
+                Some(EmojiToken::Spoiler(tokenize_with(inner, false))),
+                inner.len() + SPOILER_MARK.len() * 2,
+            ),
+            None => next_plain_token(rest),
+        };
+
+        match token {
+            Some(token) => {
+                if let Some(start) = text_start.take() {
+                    tokens.push(EmojiToken::Text(&content[start..pos]));
+                }
+                tokens.push(token);
+            }
+            None => {
+                text_start.get_or_insert(pos);
+            }
+        }
+        pos += len;
+    }
+
+    if let Some(start) = text_start {
+        tokens.push(EmojiToken::Text(&content[start..]));
+    }
+    tokens
+}
+
+//맨 앞 토큰 하나와 그 길이(바이트). 글자 한 개면 토큰 없이 길이만 돌려줌
+fn next_plain_token(rest: &str) -> (Option<EmojiToken<'_>>, usize) {
+    if let Some((emoji, len)) = parse_custom_emoji(rest) {
+        return (Some(EmojiToken::Custom(emoji)), len);
+    }
+
+    let whitespace_len = rest
+        .find(|c: char| !c.is_whitespace())
+        .unwrap_or(rest.len());
+    if whitespace_len > 0 {
+        return (
+            Some(EmojiToken::Whitespace(&rest[..whitespace_len])),
+            whitespace_len,
+        );
+    }
+
+    if let Some((emoji, len)) = parse_unicode_emoji(rest) {
+        return (
+            Some(EmojiToken::Unicode {
+                text: &rest[..len],
+                emoji,
+            }),
+            len,
+        );
+    }
+
+    (None, rest.chars().next().map_or(1, char::len_utf8))
+}
+
+//이름은 :, <, >, 공백만 아니면 다 받음
+fn parse_custom_emoji(rest: &str) -> Option<(CustomEmoji<'_>, usize)> {
+    let body = rest.strip_prefix('<')?;
+    let (animated, body) = match body.strip_prefix("a:") {
+        Some(body) => (true, body),
+        None => (false, body.strip_prefix(':')?),
+    };
+
+    let name_len = body.find(|c: char| matches!(c, ':' | '<' | '>') || c.is_whitespace())?;
+    if name_len == 0 || !body[name_len..].starts_with(':') {
+        return None;
+    }
+    let name = &body[..name_len];
+
+    let id_part = &body[name_len + 1..];
+    let id_len = id_part.find(|c: char| !c.is_ascii_digit())?;
+    if id_len == 0 || !id_part[id_len..].starts_with('>') {
+        return None;
+    }
+    let id = id_part[..id_len].parse().ok()?;
+
+    let len = rest.len() - id_part.len() + id_len + 1;
+    Some((CustomEmoji { animated, name, id }, len))
+}
+
+//제일 길게 맞는 Twemoji를 찾음. 국기 두 개가 붙어 있어도 하나씩 끊김
+fn parse_unicode_emoji(rest: &str) -> Option<(&'static SvgTwemojiAsset, usize)> {
+    let first = rest.chars().next()?;
+    //키캡(#️⃣, 1️⃣ 등)이 아니면 ASCII는 볼 필요 없음
+    if first.is_ascii() && !matches!(first, '#' | '*' | '0'..='9') {
+        return None;
+    }
+
+    let ends: Vec<usize> = rest
+        .char_indices()
+        .skip(1)
+        .map(|(index, _)| index)
+        .chain(std::iter::once(rest.len()))
+        .take(MAX_UNICODE_EMOJI_CHARS)
+        .collect();
+
+    ends.into_iter()
+        .rev()
+        .find_map(|end| find_twemoji(&rest[..end]).map(|emoji| (emoji, end)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    //토큰을 다시 이어붙인 원래 문자열
+    fn source(tokens: &[EmojiToken]) -> String {
+        tokens
+            .iter()
+            .map(|token| match token {
+                EmojiToken::Custom(emoji) => format!(
+                    "<{}:{}:{}>",
+                    if emoji.animated { "a" } else { "" },
+                    emoji.name,
+                    emoji.id
+                ),
+                EmojiToken::Unicode { text, .. } => text.to_string(),
+                EmojiToken::Spoiler(inner) => format!("||{}||", source(inner)),
+                EmojiToken::Whitespace(text) | EmojiToken::Text(text) => text.to_string(),
+            })
+            .collect()
+    }
+
+    fn custom(animated: bool, name: &str, id: u64) -> EmojiToken<'_> {
+        EmojiToken::Custom(CustomEmoji { animated, name, id })
+    }
+
+    #[test]
+    fn splits_mixed_content() {
+        let tokens = tokenize("hi <:kal:123> ||<a:b_b:45>|| 👍🏽🇰🇷");
+        assert_eq!(tokens[0], EmojiToken::Text("hi"));
+        assert_eq!(tokens[2], custom(false, "kal", 123));
+        assert_eq!(
+            tokens[4],
+            EmojiToken::Spoiler(vec![custom(true, "b_b", 45)])
+        );
+        assert!(matches!(
+            tokens[6],
+            EmojiToken::Unicode {
+                text: "👍🏽", ..
+            }
+        ));
+        assert!(matches!(
+            tokens[7],
+            EmojiToken::Unicode {
+                text: "🇰🇷", ..
+            }
+        ));
+        assert_eq!(tokens.len(), 8);
+    }
+
+    #[test]
+    fn keeps_broken_markup_as_text() {
+        for content in ["<:a:>", "<::1>", "<a:b:1", "<:a b:1>", "<:a:1x>"] {
+            assert!(
+                tokenize(content)
+                    .iter()
+                    .all(|token| !matches!(token, EmojiToken::Custom(_) | EmojiToken::Spoiler(_))),
+                "{content:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn unclosed_spoiler_is_text() {
+        assert_eq!(
+            tokenize("||<:a:1>"),
+            vec![EmojiToken::Text("||"), custom(false, "a", 1)]
+        );
+        assert_eq!(
+            tokenize("||a|| ||"),
+            vec![
+                EmojiToken::Spoiler(vec![EmojiToken::Text("a")]),
+                EmojiToken::Whitespace(" "),
+                EmojiToken::Text("||"),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_emojis_skips_whitespace() {
+        let tokens = tokenize(" <:a:1>\n😂  ");
+        let only = only_emojis(&tokens).unwrap();
+        assert_eq!(only.emojis.len(), 2);
+        assert!(!only.spoiler);
+
+        assert!(only_emojis(&tokenize("<:a:1> ㅋ")).is_none());
+        assert!(only_emojis(&tokenize("||<:a:1> ㅋ||")).is_none());
+    }
+
+    #[test]
+    fn only_emojis_unwraps_spoilers() {
+        let tokens = tokenize("||<:a:1>|| <a:b:2>");
+        let only = only_emojis(&tokens).unwrap();
+        assert_eq!(
+            only.emojis,
+            vec![custom(false, "a", 1), custom(true, "b", 2)]
+        );
+        assert!(only.spoiler);
+    }
+
+    proptest! {
+        //어떤 문자열이 들어와도 토큰을 이어붙이면 원래 문자열
+        #[test]
+        fn round_trips_any_content(content in "\\PC{0,40}") {
+            prop_assert_eq!(source(&tokenize(&content)), content);
+        }
+
+        #[test]
+        fn round_trips_emoji_soup(
+            parts in prop::collection::vec(
+                prop_oneof![
+                    Just("<:kal:1>".to_string()),
+                    Just("<a:x:99>".to_string()),
+                    Just("||".to_string()),
+                    Just(" ".to_string()),
+                    Just("👨‍👩‍👧‍👦".to_string()),
+                    Just("❤️".to_string()),
+                    Just("<".to_string()),
+                    Just(":".to_string()),
+                    "[a-z0-9]{1,3}",
+                ],
+                0..12,
+            )
+        ) {
+            let content = parts.concat();
+            prop_assert_eq!(source(&tokenize(&content)), content);
+        }
+
+        //이름에 이상한 글자가 있어도 이모지로 읽음
+        #[test]
+        fn parses_any_custom_emoji(
+            animated in any::<bool>(),
+            name in "[^:<>\\s]{1,32}",
+            id in any::<u64>(),
+        ) {
+            let content = format!("<{}:{}:{}>", if animated { "a" } else { "" }, name, id);
+            prop_assert_eq!(tokenize(&content), vec![custom(animated, &name, id)]);
+        }
+
+        //이모지만 있는 메시지를 ||로 가려도 꺼내는 이모지는 같음
+        #[test]
+        fn spoiler_keeps_same_emojis(
+            parts in prop::collection::vec(
+                prop_oneof![
+                    Just("<:kal:1>"),
+                    Just("<a:x:99>"),
+                    Just(" "),
+                    Just("😂"),
+                    Just("🇰🇷"),
+                ],
+                1..8,
+            )
+        ) {
+            let content = parts.concat();
+            let spoiled = format!("||{}||", content);
+            let (plain_tokens, spoiled_tokens) = (tokenize(&content), tokenize(&spoiled));
+            let plain = only_emojis(&plain_tokens).unwrap();
+            let hidden = only_emojis(&spoiled_tokens).unwrap();
+
+            prop_assert_eq!(plain.emojis, hidden.emojis);
+            prop_assert!(!plain.spoiler && hidden.spoiler);
+        }
+
+        //공백 토큰엔 공백만, 글자 토큰은 연달아 나오지 않음
+        #[test]
+        fn token_kinds_are_consistent(content in "\\PC{0,40}") {
+            let tokens = tokenize(&content);
+            for token in &tokens {
+                if let EmojiToken::Whitespace(text) = token {
+                    prop_assert!(text.chars().all(char::is_whitespace));
+                }
+            }
+            for pair in tokens.windows(2) {
+                prop_assert!(!matches!(pair, [EmojiToken::Text(_), EmojiToken::Text(_)]));
+                prop_assert!(!matches!(pair, [EmojiToken::Whitespace(_), EmojiToken::Whitespace(_)]));
+            }
+        }
+    }
+}
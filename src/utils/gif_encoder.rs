@@ -0,0 +1,462 @@
+//캔버스 전체 크기의 프레임들을 작고 깔끔한 gif로 묶는 부분
+//바뀐 영역만 잘라서 넣고, 안 바뀐 픽셀은 투명으로 두고, 똑같은 프레임은 하나로 합침
+use gif::{DisposalMethod, Repeat};
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::utils::scene_core::{EmojiFrames, ImageTransferError};
+
+//전체 팔레트를 뽑을 때 보는 픽셀 수 상한. 프레임이 많으면 건너뛰면서 봄
+const GLOBAL_PALETTE_SAMPLES: usize = 1 << 20;
+//NeuQuant 샘플링 간격. 1이 제일 정확하고 30이 제일 빠름
+const QUANTIZE_SAMPLE_FACTOR: i32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteMode {
+    //모든 프레임이 팔레트 하나를 같이 씀. 색이 안 깜빡이고 파일도 작음
+    Global,
+    //프레임마다 바뀐 픽셀로 팔레트를 따로 뽑음. 색이 많이 바뀌는 움짤에 유리함
+    PerFrame,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GifEncodeOptions {
+    pub palette: PaletteMode,
+    //투명색 하나를 포함한 팔레트 크기 (2~256)
+    pub colors: usize,
+    //색을 줄여야 할 때 플로이드-스타인버그 디더링을 쓸지. 도트 이모지는 끄는게 깔끔함
+    pub dithering: bool,
+    pub repeat: Repeat,
+}
+
+impl Default for GifEncodeOptions {
+    fn default() -> Self {
+        Self {
+            palette: PaletteMode::Global,
+            colors: 256,
+            dithering: true,
+            repeat: Repeat::Infinite,
+        }
+    }
+}
+
+//프레임 안에서 실제로 새로 그리는 영역
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rect {
+    left: u32,
+    top: u32,
+    right: u32,
+    bottom: u32,
+}
+
+impl Rect {
+    fn union(self, other: Rect) -> Rect {
+        Rect {
+            left: self.left.min(other.left),
+            top: self.top.min(other.top),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+        }
+    }
+}
+
+pub fn encode(
+    frames: &EmojiFrames,
+    options: &GifEncodeOptions,
+) -> Result<Vec<u8>, ImageTransferError> {
+    let (width, height) = match frames.first() {
+        Some((img, _)) => img.dimensions(),
+        None => (1, 1),
+    };
+    if width == 0 || height == 0 || width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(ImageTransferError::InvalidImageSize);
+    }
+
+    let canvases = normalize_frames(frames, width, height);
+    let colors = options.colors.clamp(2, 256) - 1;
+
+    let global_palette = match options.palette {
+        PaletteMode::Global => Some(global_palette(&canvases, colors)),
+        PaletteMode::PerFrame => None,
+    };
+
+    let mut result_buffer = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(
+            &mut result_buffer,
+            width as u16,
+            height as u16,
+            global_palette
+                .as_ref()
+                .map_or(&[][..], |palette| &palette.rgb),
+        )
+        .map_err(|_| ImageTransferError::GifEncodingError)?;
+        encoder
+            .set_repeat(options.repeat)
+            .map_err(|_| ImageTransferError::SetRepeatError)?;
+
+        let mut display = vec![0; (width * height * 4) as usize];
+        for (index, (rgba, delay)) in canvases.iter().enumerate() {
+            //마지막 프레임은 다시 첫 프레임으로 넘어가니까 첫 프레임을 다음으로 봄
+            let next = &canvases[(index + 1) % canvases.len()].0;
+
+            let mut rect = diff_rect(&display, rgba, width, height);
+            //다음 프레임에서 투명해지는 픽셀은 투명 픽셀로 덮을 수가 없으니까
+            //그 영역까지 이번 프레임에 넣고 다 그린 다음 지우게 함
+            let clear = clear_rect(rgba, next, width, height);
+            let dispose = match clear {
+                Some(clear) => {
+                    rect = Some(rect.map_or(clear, |rect| rect.union(clear)));
+                    DisposalMethod::Background
+                }
+                None => DisposalMethod::Keep,
+            };
+            //바뀐게 없어도 딜레이는 지켜야 하니까 투명 픽셀 하나로 대신함
+            let rect = rect.unwrap_or(Rect {
+                left: 0,
+                top: 0,
+                right: 1,
+                bottom: 1,
+            });
+
+            let frame_palette = match &global_palette {
+                Some(_) => None,
+                None => Some(frame_palette(&display, rgba, width, rect, colors)),
+            };
+            let palette = global_palette
+                .as_ref()
+                .or(frame_palette.as_ref())
+                .ok_or(ImageTransferError::GifEncodingError)?;
+
+            let indices = index_frame(&display, rgba, width, rect, palette, options.dithering);
+            let frame = gif::Frame {
+                left: rect.left as u16,
+                top: rect.top as u16,
+                width: (rect.right - rect.left) as u16,
+                height: (rect.bottom - rect.top) as u16,
+                delay: (*delay).min(u16::MAX as u32) as u16,
+                dispose,
+                transparent: Some(palette.transparent),
+                palette: frame_palette.as_ref().map(|palette| palette.rgb.clone()),
+                buffer: Cow::Owned(indices),
+                ..gif::Frame::default()
+            };
+            encoder
+                .write_frame(&frame)
+                .map_err(|_| ImageTransferError::GifEncodingError)?;
+
+            display.copy_from_slice(rgba);
+            if dispose == DisposalMethod::Background {
+                fill_rect(&mut display, width, rect);
+            }
+        }
+    }
+
+    Ok(result_buffer)
+}
+
+//반투명은 반 잘라서 투명/불투명으로 나누고, 투명 픽셀은 색을 0으로 맞춤
+//바로 앞이랑 똑같은 프레임은 딜레이만 더해서 하나로 합침
+fn normalize_frames(frames: &EmojiFrames, width: u32, height: u32) -> Vec<(Vec<u8>, u32)> {
+    let mut canvases: Vec<(Vec<u8>, u32)> = Vec::with_capacity(frames.len());
+    for (img, delay) in frames {
+        if img.dimensions() != (width, height) {
+            continue;
+        }
+
+        let mut rgba = img.as_raw().clone();
+        for pixel in rgba.chunks_exact_mut(4) {
+            if pixel[3] < 128 {
+                pixel.fill(0);
+            } else {
+                pixel[3] = 255;
+            }
+        }
+
+        match canvases.last_mut() {
+            Some((last, last_delay)) if *last == rgba => *last_delay += delay,
+            _ => canvases.push((rgba, *delay)),
+        }
+    }
+
+    if canvases.is_empty() {
+        canvases.push((vec![0; (width * height * 4) as usize], 0));
+    }
+    canvases
+}
+
+fn opaque_pixels(rgba: &[u8]) -> impl Iterator<Item = [u8; 3]> + '_ {
+    rgba.chunks_exact(4)
+        .filter(|pixel| pixel[3] != 0)
+        .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+}
+
+//화면(display)과 다른 픽셀들을 감싸는 영역
+fn diff_rect(display: &[u8], rgba: &[u8], width: u32, height: u32) -> Option<Rect> {
+    bounding_rect(width, height, |offset| {
+        display[offset..offset + 4] != rgba[offset..offset + 4]
+    })
+}
+
+//이번엔 보이는데 다음 프레임에서 투명해지는 픽셀들을 감싸는 영역
+fn clear_rect(rgba: &[u8], next: &[u8], width: u32, height: u32) -> Option<Rect> {
+    bounding_rect(width, height, |offset| {
+        rgba[offset + 3] != 0 && next[offset + 3] == 0
+    })
+}
+
+fn bounding_rect(width: u32, height: u32, hit: impl Fn(usize) -> bool) -> Option<Rect> {
+    let mut rect: Option<Rect> = None;
+    for y in 0..height {
+        for x in 0..width {
+            if hit(((y * width + x) * 4) as usize) {
+                let pixel = Rect {
+                    left: x,
+                    top: y,
+                    right: x + 1,
+                    bottom: y + 1,
+                };
+                rect = Some(rect.map_or(pixel, |rect| rect.union(pixel)));
+            }
+        }
+    }
+    rect
+}
+
+fn fill_rect(rgba: &mut [u8], width: u32, rect: Rect) {
+    for y in rect.top..rect.bottom {
+        let start = ((y * width + rect.left) * 4) as usize;
+        let end = ((y * width + rect.right) * 4) as usize;
+        rgba[start..end].fill(0);
+    }
+}
+
+enum ColorLookup {
+    Exact(HashMap<[u8; 3], u8>),
+    Quantized(color_quant::NeuQuant),
+}
+
+//불투명한 색들 뒤에 투명색 하나가 붙은 팔레트
+struct Palette {
+    rgb: Vec<u8>,
+    transparent: u8,
+    lookup: ColorLookup,
+}
+
+impl Palette {
+    fn is_exact(&self) -> bool {
+        matches!(self.lookup, ColorLookup::Exact(_))
+    }
+
+    fn index_of(&self, color: [u8; 3]) -> u8 {
+        match &self.lookup {
+            ColorLookup::Exact(colors) => colors.get(&color).copied().unwrap_or(0),
+            ColorLookup::Quantized(quantizer) => {
+                quantizer.index_of(&[color[0], color[1], color[2], 255]) as u8
+            }
+        }
+    }
+
+    fn color(&self, index: u8) -> [u8; 3] {
+        let offset = index as usize * 3;
+        [self.rgb[offset], self.rgb[offset + 1], self.rgb[offset + 2]]
+    }
+}
+
+//색 수가 colors개 이하면 그대로 팔레트로 씀
+fn exact_palette(pixels: impl Iterator<Item = [u8; 3]>, colors: usize) -> Option<Palette> {
+    let mut lookup = HashMap::new();
+    let mut rgb = Vec::new();
+    for color in pixels {
+        if lookup.contains_key(&color) {
+            continue;
+        }
+        if lookup.len() == colors {
+            return None;
+        }
+        lookup.insert(color, lookup.len() as u8);
+        rgb.extend_from_slice(&color);
+    }
+
+    let transparent = lookup.len() as u8;
+    rgb.extend_from_slice(&[0, 0, 0]);
+    Some(Palette {
+        rgb,
+        transparent,
+        lookup: ColorLookup::Exact(lookup),
+    })
+}
+
+fn quantized_palette(pixels: &[u8], colors: usize) -> Palette {
+    let quantizer = color_quant::NeuQuant::new(QUANTIZE_SAMPLE_FACTOR, colors, pixels);
+    let mut rgb: Vec<u8> = quantizer
+        .color_map_rgba()
+        .chunks_exact(4)
+        .flat_map(|color| [color[0], color[1], color[2]])
+        .collect();
+
+    let transparent = (rgb.len() / 3) as u8;
+    rgb.extend_from_slice(&[0, 0, 0]);
+    Palette {
+        rgb,
+        transparent,
+        lookup: ColorLookup::Quantized(quantizer),
+    }
+}
+
+//색이 다 들어가면 그대로 쓰고, 아니면 모든 프레임에서 골고루 뽑아서 줄임
+fn global_palette(canvases: &[(Vec<u8>, u32)], colors: usize) -> Palette {
+    if let Some(palette) = exact_palette(
+        canvases.iter().flat_map(|(rgba, _)| opaque_pixels(rgba)),
+        colors,
+    ) {
+        return palette;
+    }
+
+    let total: usize = canvases.iter().map(|(rgba, _)| rgba.len() / 4).sum();
+    let step = total.div_ceil(GLOBAL_PALETTE_SAMPLES).max(1);
+    let samples: Vec<u8> = canvases
+        .iter()
+        .flat_map(|(rgba, _)| opaque_pixels(rgba))
+        .step_by(step)
+        .flat_map(|[r, g, b]| [r, g, b, 255])
+        .collect();
+    quantized_palette(&samples, colors)
+}
+
+//이번 프레임에서 새로 그리는 픽셀들로만 팔레트를 뽑음
+fn frame_palette(display: &[u8], rgba: &[u8], width: u32, rect: Rect, colors: usize) -> Palette {
+    let mut changed = Vec::new();
+    for y in rect.top..rect.bottom {
+        for x in rect.left..rect.right {
+            let offset = ((y * width + x) * 4) as usize;
+            let pixel = &rgba[offset..offset + 4];
+            if pixel[3] != 0 && display[offset..offset + 4] != *pixel {
+                changed.push([pixel[0], pixel[1], pixel[2]]);
+            }
+        }
+    }
+
+    exact_palette(changed.iter().copied(), colors).unwrap_or_else(|| {
+        let samples: Vec<u8> = changed
+            .iter()
+            .flat_map(|&[r, g, b]| [r, g, b, 255])
+            .collect();
+        quantized_palette(&samples, colors)
+    })
+}
+
+//안 바뀐 픽셀은 투명색으로 두고 앞 프레임이 그대로 보이게 함
+fn index_frame(
+    display: &[u8],
+    rgba: &[u8],
+    width: u32,
+    rect: Rect,
+    palette: &Palette,
+    dithering: bool,
+) -> Vec<u8> {
+    let rect_width = (rect.right - rect.left) as usize;
+    let rect_height = (rect.bottom - rect.top) as usize;
+    let mut indices = vec![palette.transparent; rect_width * rect_height];
+    //팔레트에 색이 다 들어가 있으면 오차가 없으니 디더링할 필요도 없음
+    let dithering = dithering && !palette.is_exact();
+    //이번 줄과 다음 줄의 RGB 오차
+    let mut errors = vec![[0i32; 3]; rect_width * 2 + 4];
+
+    for row in 0..rect_height {
+        let (current, next) = errors.split_at_mut(rect_width + 2);
+        next.fill([0; 3]);
+        for column in 0..rect_width {
+            let x = rect.left as usize + column;
+            let y = rect.top as usize + row;
+            let offset = (y * width as usize + x) * 4;
+            let pixel = &rgba[offset..offset + 4];
+            if pixel[3] == 0 || display[offset..offset + 4] == *pixel {
+                continue;
+            }
+
+            let color = if dithering {
+                let error = current[column + 1];
+                [0, 1, 2].map(|channel| {
+                    (pixel[channel] as i32 + error[channel] / 16).clamp(0, 255) as u8
+                })
+            } else {
+                [pixel[0], pixel[1], pixel[2]]
+            };
+            let index = palette.index_of(color);
+            indices[row * rect_width + column] = index;
+
+            if dithering {
+                let chosen = palette.color(index);
+                for channel in 0..3 {
+                    let diff = color[channel] as i32 - chosen[channel] as i32;
+                    current[column + 2][channel] += diff * 7;
+                    next[column][channel] += diff * 3;
+                    next[column + 1][channel] += diff * 5;
+                    next[column + 2][channel] += diff;
+                }
+            }
+        }
+        current.copy_from_slice(&next[..rect_width + 2]);
+    }
+
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::scene_core::decode_emoji_frames;
+    use image::{Rgba, RgbaImage};
+
+    //배경 위로 네모가 움직이고, 마지막 두 프레임은 똑같은 움짤
+    fn moving_square() -> EmojiFrames {
+        let frame = |x: u32| {
+            let mut img = RgbaImage::from_pixel(16, 16, Rgba([0, 0, 255, 255]));
+            for y in 4..8 {
+                for dx in 0..4 {
+                    img.put_pixel(x + dx, y, Rgba([255, 0, 0, 255]));
+                }
+            }
+            //투명한 픽셀도 그대로 남아야 함
+            img.put_pixel(15, 15, Rgba([0, 0, 0, 0]));
+            img
+        };
+        vec![(frame(0), 5), (frame(4), 7), (frame(8), 3), (frame(8), 4)]
+    }
+
+    fn assert_round_trip(options: &GifEncodeOptions) {
+        let frames = moving_square();
+        let decoded = decode_emoji_frames(&encode(&frames, options).unwrap()).unwrap();
+
+        //똑같은 마지막 두 프레임은 하나로 합쳐지고 딜레이는 더해짐
+        assert_eq!(
+            decoded.iter().map(|(_, delay)| *delay).collect::<Vec<_>>(),
+            vec![5, 7, 7]
+        );
+        for ((decoded, _), (original, _)) in decoded.iter().zip(&frames) {
+            assert_eq!(decoded.dimensions(), original.dimensions());
+            for (decoded, original) in decoded.pixels().zip(original.pixels()) {
+                match original[3] {
+                    0 => assert_eq!(decoded[3], 0),
+                    _ => assert_eq!(decoded, original),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn global_palette_round_trip() {
+        assert_round_trip(&GifEncodeOptions::default());
+    }
+
+    #[test]
+    fn per_frame_palette_round_trip() {
+        assert_round_trip(&GifEncodeOptions {
+            palette: PaletteMode::PerFrame,
+            colors: 16,
+            ..GifEncodeOptions::default()
+        });
+    }
+}
@@ -1,23 +1,124 @@
-use crate::{utils::scene_core::ImageSize, GlobalGuildConfigs};
+use crate::{
+    utils::scene_core::{
+        AnimatedFormat, DoubleEmojiArrangement, DoubleEmojiLayout, EmojiAlignment, EmojiLayout,
+        ImageSize, ResizeFilter, DEFAULT_AUTO_SIZE_MAX, DEFAULT_AUTO_SIZE_MIN,
+    },
+    GlobalGuildConfigs,
+};
 use log::{error, info};
 
 use mongodb::bson::doc;
 use serde::{Deserialize, Serialize};
 use serenity::{
-    all::Permissions, builder::CreateCommand, client::Context, gateway::ActivityData,
-    model::id::GuildId,
+    all::Permissions,
+    builder::CreateCommand,
+    client::Context,
+    gateway::ActivityData,
+    model::id::{ChannelId, GuildId, RoleId},
 };
-use std::num::NonZeroU64;
+use std::{collections::HashMap, num::NonZeroU64};
 
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GuildConfig {
     pub guild_id: NonZeroU64,
     pub auto_magnitute_enable: bool,
     pub auto_magnitute_config: ImageSize,
     pub auto_transfer_webp: bool,
+    pub multi_emoji_layout: EmojiLayout,
+    pub multi_emoji_spacing: u32,
+    pub resize_filter: ResizeFilter,
+    pub auto_size_min: u32,
+    pub auto_size_max: u32,
+    pub apng_output_format: AnimatedFormat,
+    pub double_emoji_layout: DoubleEmojiLayout,
+    pub webhook_repost: bool,
+    pub magnify_replies: bool,
+    pub repost_expire_seconds: u32,
+    //두 개 이상 보낸 이모지를 합쳐서 보낼지. 서버 전체 설정은 없어서 채널 설정으로만 끔
+    pub merge_emoji_enable: bool,
+    //채널이나 카테고리마다 따로 정한 설정
+    pub channel_overrides: HashMap<ChannelId, ChannelOverride>,
+    //비어있지 않으면 이 중 하나라도 가진 사람만 자동 확대
+    pub auto_allowed_roles: Vec<RoleId>,
+    //이 중 하나라도 가진 사람은 자동 확대 안 함. 허용 목록보다 먼저 봄
+    pub auto_denied_roles: Vec<RoleId>,
+}
+
+//채널이나 카테고리에서 서버 설정을 덮어쓰는 값. None이면 위(카테고리, 서버)의 설정을 따름
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ChannelOverride {
+    pub auto_magnitute_enable: Option<bool>,
+    pub auto_magnitute_config: Option<ImageSize>,
+    pub double_emoji_arrangement: Option<DoubleEmojiArrangement>,
+    pub merge_emoji_enable: Option<bool>,
+    pub auto_transfer_webp: Option<bool>,
+}
+
+impl ChannelOverride {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn apply(&self, config: &mut GuildConfig) {
+        if let Some(enable) = self.auto_magnitute_enable {
+            config.auto_magnitute_enable = enable;
+        }
+        if let Some(size) = &self.auto_magnitute_config {
+            config.auto_magnitute_config = size.clone();
+        }
+        if let Some(arrangement) = self.double_emoji_arrangement {
+            config.double_emoji_layout.arrangement = arrangement;
+        }
+        if let Some(merge) = self.merge_emoji_enable {
+            config.merge_emoji_enable = merge;
+        }
+        if let Some(webp) = self.auto_transfer_webp {
+            config.auto_transfer_webp = webp;
+        }
+    }
+
+    //정한 값만 저장함
+    fn to_document(&self) -> bson::Document {
+        let mut document = bson::Document::new();
+        if let Some(enable) = self.auto_magnitute_enable {
+            document.insert("auto_magnitute_enable", enable);
+        }
+        if let Some(size) = &self.auto_magnitute_config {
+            document.insert("auto_magnitute_config", ImageSize::value_to_string(size));
+        }
+        if let Some(arrangement) = &self.double_emoji_arrangement {
+            document.insert(
+                "double_emoji_arrangement",
+                DoubleEmojiArrangement::value_to_string(arrangement),
+            );
+        }
+        if let Some(merge) = self.merge_emoji_enable {
+            document.insert("merge_emoji_enable", merge);
+        }
+        if let Some(webp) = self.auto_transfer_webp {
+            document.insert("auto_transfer_webp", webp);
+        }
+        document
+    }
+
+    fn load(document: &bson::Document) -> Self {
+        Self {
+            auto_magnitute_enable: document.get_bool("auto_magnitute_enable").ok(),
+            auto_magnitute_config: document
+                .get_str("auto_magnitute_config")
+                .ok()
+                .map(ImageSize::string_to_value),
+            double_emoji_arrangement: document
+                .get_str("double_emoji_arrangement")
+                .ok()
+                .map(DoubleEmojiArrangement::string_to_value),
+            merge_emoji_enable: document.get_bool("merge_emoji_enable").ok(),
+            auto_transfer_webp: document.get_bool("auto_transfer_webp").ok(),
+        }
+    }
 }
 
 impl GuildConfig {
@@ -27,23 +128,96 @@ impl GuildConfig {
             auto_magnitute_enable: false,
             auto_magnitute_config: ImageSize::Auto,
             auto_transfer_webp: false,
+            multi_emoji_layout: EmojiLayout::Grid,
+            multi_emoji_spacing: 0,
+            resize_filter: ResizeFilter::Auto,
+            auto_size_min: DEFAULT_AUTO_SIZE_MIN,
+            auto_size_max: DEFAULT_AUTO_SIZE_MAX,
+            apng_output_format: AnimatedFormat::Gif,
+            double_emoji_layout: DoubleEmojiLayout::default(),
+            webhook_repost: false,
+            magnify_replies: false,
+            repost_expire_seconds: 0,
+            merge_emoji_enable: true,
+            channel_overrides: HashMap::new(),
+            auto_allowed_roles: vec![],
+            auto_denied_roles: vec![],
         }
     }
 
-    pub fn load(
-        guild: &GuildId,
-        auto_magnitute_enable_input: bool,
-        auto_magnitute_config_input: ImageSize,
-        auto_transfer_webp_input: bool,
-    ) -> Self {
+    //DB에 없는 값은 기본값으로 채움
+    pub fn load(guild: &GuildId, document: &bson::Document) -> Self {
         Self {
             guild_id: NonZeroU64::new(guild.get()).unwrap(),
-            auto_magnitute_enable: auto_magnitute_enable_input,
-            auto_magnitute_config: auto_magnitute_config_input,
-            auto_transfer_webp: auto_transfer_webp_input,
+            auto_magnitute_enable: document.get_bool("auto_magnitute_enable").unwrap_or(false),
+            auto_magnitute_config: ImageSize::string_to_value(
+                document.get_str("auto_magnitute_config").unwrap_or("Auto"),
+            ),
+            auto_transfer_webp: document.get_bool("auto_transfer_webp").unwrap_or(false),
+            multi_emoji_layout: EmojiLayout::string_to_value(
+                document.get_str("multi_emoji_layout").unwrap_or("Grid"),
+            ),
+            multi_emoji_spacing: document.get_i32("multi_emoji_spacing").unwrap_or(0) as u32,
+            resize_filter: ResizeFilter::string_to_value(
+                document.get_str("resize_filter").unwrap_or("Auto"),
+            ),
+            auto_size_min: document
+                .get_i32("auto_size_min")
+                .unwrap_or(DEFAULT_AUTO_SIZE_MIN as i32) as u32,
+            auto_size_max: document
+                .get_i32("auto_size_max")
+                .unwrap_or(DEFAULT_AUTO_SIZE_MAX as i32) as u32,
+            apng_output_format: AnimatedFormat::string_to_value(
+                document.get_str("apng_output_format").unwrap_or("Gif"),
+            ),
+            double_emoji_layout: load_double_emoji_layout(document),
+            webhook_repost: document.get_bool("webhook_repost").unwrap_or(false),
+            magnify_replies: document.get_bool("magnify_replies").unwrap_or(false),
+            repost_expire_seconds: document.get_i32("repost_expire_seconds").unwrap_or(0) as u32,
+            merge_emoji_enable: true,
+            channel_overrides: load_channel_overrides(document),
+            auto_allowed_roles: load_roles(document, "auto_allowed_roles"),
+            auto_denied_roles: load_roles(document, "auto_denied_roles"),
         }
     }
 
+    //메시지를 보낸 사람의 역할로 자동 확대를 해도 되는지
+    pub fn allows_roles(&self, roles: &[RoleId]) -> bool {
+        !roles
+            .iter()
+            .any(|role| self.auto_denied_roles.contains(role))
+            && (self.auto_allowed_roles.is_empty()
+                || roles
+                    .iter()
+                    .any(|role| self.auto_allowed_roles.contains(role)))
+    }
+
+    //lineage는 카테고리 → 채널 → 스레드 순서. 안쪽 설정이 바깥 설정을 덮어씀
+    pub fn for_channel(&self, lineage: &[ChannelId]) -> GuildConfig {
+        let mut config = self.clone();
+        for channel_override in lineage
+            .iter()
+            .filter_map(|channel_id| self.channel_overrides.get(channel_id))
+        {
+            channel_override.apply(&mut config);
+        }
+        config
+    }
+
+    //DB에는 채널 ID를 키로 하는 문서로 저장함
+    pub fn channel_overrides_document(&self) -> bson::Document {
+        self.channel_overrides
+            .iter()
+            .filter(|(_, channel_override)| !channel_override.is_empty())
+            .map(|(channel_id, channel_override)| {
+                (
+                    channel_id.get().to_string(),
+                    bson::Bson::Document(channel_override.to_document()),
+                )
+            })
+            .collect()
+    }
+
     pub async fn delete(guild: &GuildId, ctx: &Context) -> Result<(), ()> {
         let counter_lock = {
             let data_read = ctx.data.read().await;
@@ -114,14 +288,7 @@ impl GuildConfig {
                 match find_result {
                     Ok(x) => {
                         let new_config = match x {
-                            Some(document) => GuildConfig::load(
-                                &guild,
-                                document.get_bool("auto_magnitute_enable").unwrap_or(false),
-                                ImageSize::string_to_value(
-                                    document.get_str("auto_magnitute_config").unwrap_or("Auto"),
-                                ),
-                                document.get_bool("auto_transfer_webp").unwrap_or(false),
-                            ),
+                            Some(document) => GuildConfig::load(&guild, &document),
                             None => {
                                 if let Err(why) = collections
                                     .insert_one(
@@ -130,6 +297,25 @@ impl GuildConfig {
                                                 "auto_magnitute_enable" : false,
                                                 "auto_magnitute_config" : "Auto",
                                                 "auto_transfer_webp": false,
+                                                "multi_emoji_layout" : "Grid",
+                                                "multi_emoji_spacing" : 0,
+                                                "resize_filter" : "Auto",
+                                                "auto_size_min" : DEFAULT_AUTO_SIZE_MIN as i32,
+                                                "auto_size_max" : DEFAULT_AUTO_SIZE_MAX as i32,
+                                                "apng_output_format" : "Gif",
+                                                "double_emoji_arrangement" : "Horizontal",
+                                                "double_emoji_alignment" : "Center",
+                                                "double_emoji_gap" : 0,
+                                                "double_emoji_background" : "None",
+                                                "double_emoji_overlay_scale" : 50,
+                                                "double_emoji_overlay_offset_x" : 25,
+                                                "double_emoji_overlay_offset_y" : 25,
+                                                "webhook_repost" : false,
+                                                "magnify_replies" : false,
+                                                "repost_expire_seconds" : 0,
+                                                "channel_overrides" : {},
+                                                "auto_allowed_roles" : [],
+                                                "auto_denied_roles" : [],
                                         },
                                         None,
                                     )
@@ -172,3 +358,180 @@ impl GuildConfig {
         info!("booting complete.");
     }
 }
+
+//메시지가 올라온 채널부터 카테고리까지 거슬러 올라가서, 카테고리 → 채널 → 스레드 순서로 돌려줌
+//캐시에 없는 채널은 거기서 멈춤
+pub fn channel_lineage(ctx: &Context, guild_id: GuildId, channel_id: ChannelId) -> Vec<ChannelId> {
+    let mut lineage = vec![channel_id];
+    if let Some(guild) = ctx.cache.guild(guild_id) {
+        let mut current = channel_id;
+        while let Some(parent_id) = guild
+            .channels
+            .get(&current)
+            .or_else(|| guild.threads.iter().find(|thread| thread.id == current))
+            .and_then(|channel| channel.parent_id)
+        {
+            if lineage.contains(&parent_id) {
+                break;
+            }
+            lineage.push(parent_id);
+            current = parent_id;
+        }
+    }
+    lineage.reverse();
+    lineage
+}
+
+fn load_channel_overrides(document: &bson::Document) -> HashMap<ChannelId, ChannelOverride> {
+    let Ok(overrides) = document.get_document("channel_overrides") else {
+        return HashMap::new();
+    };
+    overrides
+        .iter()
+        .filter_map(|(channel_id, channel_override)| {
+            let channel_id = channel_id.parse().ok().filter(|id| *id > 0)?;
+            Some((
+                ChannelId::new(channel_id),
+                ChannelOverride::load(channel_override.as_document()?),
+            ))
+        })
+        .collect()
+}
+
+//역할 ID 목록은 i64 배열로 저장함
+fn load_roles(document: &bson::Document, key: &str) -> Vec<RoleId> {
+    document
+        .get_array(key)
+        .map(|roles| {
+            roles
+                .iter()
+                .filter_map(|role| role.as_i64())
+                .filter(|role| *role > 0)
+                .map(|role| RoleId::new(role as u64))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+//두 이모지 배치는 DB에 필드별로 나눠서 저장함
+fn load_double_emoji_layout(document: &bson::Document) -> DoubleEmojiLayout {
+    let default = DoubleEmojiLayout::default();
+    DoubleEmojiLayout {
+        arrangement: DoubleEmojiArrangement::string_to_value(
+            document
+                .get_str("double_emoji_arrangement")
+                .unwrap_or("Horizontal"),
+        ),
+        alignment: EmojiAlignment::string_to_value(
+            document
+                .get_str("double_emoji_alignment")
+                .unwrap_or("Center"),
+        ),
+        gap: document.get_i32("double_emoji_gap").unwrap_or(0) as u32,
+        background: DoubleEmojiLayout::background_from_string(
+            document
+                .get_str("double_emoji_background")
+                .unwrap_or("None"),
+        ),
+        overlay_scale: document
+            .get_i32("double_emoji_overlay_scale")
+            .unwrap_or(default.overlay_scale as i32) as u32,
+        overlay_offset: (
+            document
+                .get_i32("double_emoji_overlay_offset_x")
+                .unwrap_or(default.overlay_offset.0),
+            document
+                .get_i32("double_emoji_overlay_offset_y")
+                .unwrap_or(default.overlay_offset.1),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channel_override_beats_category_override() {
+        let category = ChannelId::new(1);
+        let channel = ChannelId::new(2);
+        let mut config = GuildConfig::new(&GuildId::new(3));
+        config.auto_magnitute_enable = true;
+        config.channel_overrides.insert(
+            category,
+            ChannelOverride {
+                auto_magnitute_enable: Some(false),
+                merge_emoji_enable: Some(false),
+                auto_transfer_webp: Some(true),
+                ..Default::default()
+            },
+        );
+        config.channel_overrides.insert(
+            channel,
+            ChannelOverride {
+                auto_magnitute_enable: Some(true),
+                auto_magnitute_config: Some(ImageSize::Large),
+                ..Default::default()
+            },
+        );
+
+        let resolved = config.for_channel(&[category, channel]);
+        assert!(resolved.auto_magnitute_enable);
+        assert!(resolved.auto_transfer_webp);
+        assert!(!resolved.merge_emoji_enable);
+        assert_eq!(resolved.auto_magnitute_config, ImageSize::Large);
+
+        let in_category = config.for_channel(&[category, ChannelId::new(4)]);
+        assert!(!in_category.auto_magnitute_enable);
+        assert_eq!(in_category.auto_magnitute_config, ImageSize::Auto);
+        assert!(config.for_channel(&[ChannelId::new(4)]).merge_emoji_enable);
+    }
+
+    #[test]
+    fn denied_role_wins_over_allowed_role() {
+        let booster = RoleId::new(1);
+        let muted = RoleId::new(2);
+        let mut config = GuildConfig::new(&GuildId::new(3));
+        assert!(config.allows_roles(&[]));
+
+        config.auto_allowed_roles = vec![booster];
+        assert!(!config.allows_roles(&[]));
+        assert!(config.allows_roles(&[booster]));
+
+        config.auto_denied_roles = vec![muted];
+        assert!(!config.allows_roles(&[booster, muted]));
+
+        let document = doc! {
+            "auto_allowed_roles" : [1_i64, 0_i64],
+            "auto_denied_roles" : "broken",
+        };
+        assert_eq!(load_roles(&document, "auto_allowed_roles"), vec![booster]);
+        assert!(load_roles(&document, "auto_denied_roles").is_empty());
+    }
+
+    #[test]
+    fn channel_overrides_round_trip() {
+        let mut config = GuildConfig::new(&GuildId::new(3));
+        config.channel_overrides.insert(
+            ChannelId::new(5),
+            ChannelOverride {
+                double_emoji_arrangement: Some(DoubleEmojiArrangement::Overlay),
+                merge_emoji_enable: Some(false),
+                ..Default::default()
+            },
+        );
+        config
+            .channel_overrides
+            .insert(ChannelId::new(6), ChannelOverride::default());
+
+        let document = doc! { "channel_overrides" : config.channel_overrides_document() };
+        let loaded = load_channel_overrides(&document);
+
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(
+            loaded[&ChannelId::new(5)].double_emoji_arrangement,
+            Some(DoubleEmojiArrangement::Overlay)
+        );
+        assert_eq!(loaded[&ChannelId::new(5)].merge_emoji_enable, Some(false));
+    }
+}
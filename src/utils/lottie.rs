@@ -0,0 +1,1011 @@
+//디스코드 로티(Lottie) 스티커를 CPU로 그리는 작은 렌더러
+//스티커에 주로 쓰이는 셰이프/null/솔리드/프리컴프 레이어랑 부모 관계, 키프레임 이징까지만 지원함
+//트림 패스, 텍스트 같은 건 무시하고, 그라디언트는 첫 색으로 칠함
+//매트, 마스크, 타임 리맵은 빼고 그리면 모양이 완전히 달라지니까 그런 스티커는 아예 실패로 처리함
+use serde_json::Value;
+use tiny_skia::{
+    Color, FillRule, LineCap, LineJoin, Paint, Path, PathBuilder, Pixmap, Rect, Stroke, Transform,
+};
+
+use std::collections::HashMap;
+
+pub struct LottieAnimation {
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: f32,
+    in_point: f32,
+    out_point: f32,
+    layers: Vec<Layer>,
+    precomps: HashMap<String, Vec<Layer>>,
+}
+
+impl LottieAnimation {
+    pub fn parse(json: &[u8]) -> Option<Self> {
+        let root: Value = serde_json::from_slice(json).ok()?;
+
+        //이미지 에셋은 레이어가 없으니 건너뜀
+        let mut precomps = HashMap::new();
+        for asset in root["assets"].as_array().into_iter().flatten() {
+            if let (Some(id), Some(layers)) = (asset["id"].as_str(), asset["layers"].as_array()) {
+                precomps.insert(id.to_string(), parse_layers(layers)?);
+            }
+        }
+
+        let animation = Self {
+            width: root["w"].as_f64()? as u32,
+            height: root["h"].as_f64()? as u32,
+            frame_rate: root["fr"].as_f64()? as f32,
+            in_point: root["ip"].as_f64().unwrap_or(0.0) as f32,
+            out_point: root["op"].as_f64()? as f32,
+            layers: parse_layers(root["layers"].as_array()?)?,
+            precomps,
+        };
+
+        (animation.width > 0
+            && animation.height > 0
+            && animation.frame_rate > 0.0
+            && animation.out_point > animation.in_point)
+            .then_some(animation)
+    }
+
+    //재생 시간(초)
+    pub fn duration(&self) -> f32 {
+        (self.out_point - self.in_point) / self.frame_rate
+    }
+
+    //시작부터 seconds초 지난 장면을 transform을 적용해서 그림. 결과는 알파를 곱하지 않은 RGBA
+    pub fn render(
+        &self,
+        seconds: f32,
+        transform: Transform,
+        width: u32,
+        height: u32,
+    ) -> Option<Vec<u8>> {
+        let mut pixmap = Pixmap::new(width, height)?;
+        let frame = self.in_point + seconds * self.frame_rate;
+        self.render_layers(&self.layers, frame, transform, &mut pixmap, 0);
+
+        Some(
+            pixmap
+                .pixels()
+                .iter()
+                .flat_map(|pixel| {
+                    let color = pixel.demultiply();
+                    [color.red(), color.green(), color.blue(), color.alpha()]
+                })
+                .collect(),
+        )
+    }
+
+    fn render_layers(
+        &self,
+        layers: &[Layer],
+        frame: f32,
+        transform: Transform,
+        pixmap: &mut Pixmap,
+        depth: usize,
+    ) {
+        //프리컴프가 자기 자신을 참조하는 파일이 있어도 멈추지 않게
+        if depth > 8 {
+            return;
+        }
+
+        //배열 앞쪽 레이어가 위에 그려짐
+        for layer in layers.iter().rev() {
+            if layer.hidden || frame < layer.in_point || frame >= layer.out_point {
+                continue;
+            }
+
+            let layer_transform = transform.pre_concat(world_transform(layers, layer, frame, 0));
+            let opacity = layer.transform.opacity(frame);
+            if opacity <= 0.0 {
+                continue;
+            }
+
+            match &layer.content {
+                LayerContent::Shapes(shapes) => {
+                    let mut ops = Vec::new();
+                    collect_shapes(shapes, frame, layer_transform, opacity, &mut ops);
+                    for op in ops.iter().rev() {
+                        op.draw(pixmap);
+                    }
+                }
+                LayerContent::Solid {
+                    color,
+                    width,
+                    height,
+                } => {
+                    if let Some(rect) = Rect::from_xywh(0.0, 0.0, *width, *height) {
+                        let mut paint = Paint::default();
+                        paint.set_color(with_opacity(*color, opacity));
+                        pixmap.fill_rect(rect, &paint, layer_transform, None);
+                    }
+                }
+                LayerContent::Precomp(ref_id) => {
+                    if let Some(precomp) = self.precomps.get(ref_id) {
+                        self.render_layers(
+                            precomp,
+                            frame - layer.start_time,
+                            layer_transform,
+                            pixmap,
+                            depth + 1,
+                        );
+                    }
+                }
+                LayerContent::Null => {}
+            }
+        }
+    }
+}
+
+struct Layer {
+    index: Option<i64>,
+    parent: Option<i64>,
+    in_point: f32,
+    out_point: f32,
+    start_time: f32,
+    hidden: bool,
+    transform: ShapeTransform,
+    content: LayerContent,
+}
+
+enum LayerContent {
+    Shapes(Vec<Shape>),
+    Solid {
+        color: Color,
+        width: f32,
+        height: f32,
+    },
+    Precomp(String),
+    Null,
+}
+
+//지원하지 않는 기능을 쓰는 레이어가 하나라도 있으면 None
+fn parse_layers(layers: &[Value]) -> Option<Vec<Layer>> {
+    if layers.iter().any(uses_unsupported_features) {
+        return None;
+    }
+
+    let layers = layers
+        .iter()
+        .map(|layer| Layer {
+            index: layer["ind"].as_i64(),
+            parent: layer["parent"].as_i64(),
+            in_point: layer["ip"].as_f64().unwrap_or(0.0) as f32,
+            out_point: layer["op"].as_f64().unwrap_or(f64::MAX) as f32,
+            start_time: layer["st"].as_f64().unwrap_or(0.0) as f32,
+            hidden: layer["hd"].as_bool().unwrap_or(false),
+            transform: ShapeTransform::parse(&layer["ks"]),
+            content: match layer["ty"].as_i64() {
+                Some(0) => LayerContent::Precomp(layer["refId"].as_str().unwrap_or("").to_string()),
+                Some(1) => LayerContent::Solid {
+                    color: parse_hex_color(layer["sc"].as_str().unwrap_or("#000000")),
+                    width: layer["sw"].as_f64().unwrap_or(0.0) as f32,
+                    height: layer["sh"].as_f64().unwrap_or(0.0) as f32,
+                },
+                Some(4) => LayerContent::Shapes(parse_shapes(layer["shapes"].as_array())),
+                _ => LayerContent::Null,
+            },
+        })
+        .collect();
+    Some(layers)
+}
+
+//트랙 매트(td, tt), 마스크(masksProperties), 타임 리맵(tm)
+fn uses_unsupported_features(layer: &Value) -> bool {
+    layer["td"].as_i64().unwrap_or(0) != 0
+        || layer["tt"].as_i64().unwrap_or(0) != 0
+        || layer["hasMask"].as_bool().unwrap_or(false)
+        || layer["masksProperties"]
+            .as_array()
+            .is_some_and(|masks| !masks.is_empty())
+        || !layer["tm"].is_null()
+}
+
+//부모 레이어를 따라 올라가면서 변환을 합침. 불투명도는 부모한테서 물려받지 않음
+fn world_transform(layers: &[Layer], layer: &Layer, frame: f32, depth: usize) -> Transform {
+    let local = layer.transform.matrix(frame);
+    let parent = layer.parent.filter(|_| depth < 16).and_then(|parent| {
+        layers
+            .iter()
+            .find(|candidate| candidate.index == Some(parent))
+    });
+
+    match parent {
+        Some(parent) => world_transform(layers, parent, frame, depth + 1).pre_concat(local),
+        None => local,
+    }
+}
+
+enum Shape {
+    Group(Vec<Shape>),
+    Transform(ShapeTransform),
+    Rect {
+        position: Property,
+        size: Property,
+        roundness: Property,
+    },
+    Ellipse {
+        position: Property,
+        size: Property,
+    },
+    Star {
+        position: Property,
+        points: Property,
+        rotation: Property,
+        outer_radius: Property,
+        inner_radius: Option<Property>,
+    },
+    Path {
+        vertices: Property,
+        closed: bool,
+    },
+    Fill {
+        color: Property,
+        opacity: Property,
+        rule: FillRule,
+    },
+    Stroke {
+        color: Property,
+        opacity: Property,
+        width: Property,
+        cap: LineCap,
+        join: LineJoin,
+    },
+}
+
+fn parse_shapes(shapes: Option<&Vec<Value>>) -> Vec<Shape> {
+    shapes
+        .map(|shapes| {
+            shapes
+                .iter()
+                .filter(|shape| !shape["hd"].as_bool().unwrap_or(false))
+                .filter_map(parse_shape)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn parse_shape(shape: &Value) -> Option<Shape> {
+    let property = |key: &str| Property::parse(&shape[key], parse_numbers);
+
+    Some(match shape["ty"].as_str()? {
+        "gr" => Shape::Group(parse_shapes(shape["it"].as_array())),
+        "tr" => Shape::Transform(ShapeTransform::parse(shape)),
+        "rc" => Shape::Rect {
+            position: property("p"),
+            size: property("s"),
+            roundness: property("r"),
+        },
+        "el" => Shape::Ellipse {
+            position: property("p"),
+            size: property("s"),
+        },
+        "sr" => Shape::Star {
+            position: property("p"),
+            points: property("pt"),
+            rotation: property("r"),
+            outer_radius: property("or"),
+            //다각형(sy == 2)은 안쪽 반지름이 없음
+            inner_radius: (shape["sy"].as_i64() != Some(2)).then(|| property("ir")),
+        },
+        "sh" => {
+            let vertices = Property::parse(&shape["ks"], parse_bezier);
+            Shape::Path {
+                closed: bezier_closed(&shape["ks"]),
+                vertices,
+            }
+        }
+        "fl" => Shape::Fill {
+            color: property("c"),
+            opacity: property("o"),
+            rule: fill_rule(shape),
+        },
+        "st" => Shape::Stroke {
+            color: property("c"),
+            opacity: property("o"),
+            width: property("w"),
+            cap: line_cap(shape),
+            join: line_join(shape),
+        },
+        //그라디언트는 첫 번째 색으로만 칠함
+        "gf" => Shape::Fill {
+            color: Property::parse(&shape["g"]["k"], parse_gradient_first_color),
+            opacity: property("o"),
+            rule: fill_rule(shape),
+        },
+        "gs" => Shape::Stroke {
+            color: Property::parse(&shape["g"]["k"], parse_gradient_first_color),
+            opacity: property("o"),
+            width: property("w"),
+            cap: line_cap(shape),
+            join: line_join(shape),
+        },
+        _ => return None,
+    })
+}
+
+fn fill_rule(shape: &Value) -> FillRule {
+    match shape["r"].as_i64() {
+        Some(2) => FillRule::EvenOdd,
+        _ => FillRule::Winding,
+    }
+}
+
+fn line_cap(shape: &Value) -> LineCap {
+    match shape["lc"].as_i64() {
+        Some(2) => LineCap::Round,
+        Some(3) => LineCap::Square,
+        _ => LineCap::Butt,
+    }
+}
+
+fn line_join(shape: &Value) -> LineJoin {
+    match shape["lj"].as_i64() {
+        Some(2) => LineJoin::Round,
+        Some(3) => LineJoin::Bevel,
+        _ => LineJoin::Miter,
+    }
+}
+
+//스타일 하나가 그때까지 모인 도형 전부에 적용된 결과
+enum DrawOp {
+    Fill {
+        path: Path,
+        color: Color,
+        rule: FillRule,
+    },
+    Stroke {
+        path: Path,
+        color: Color,
+        stroke: Stroke,
+    },
+}
+
+impl DrawOp {
+    fn draw(&self, pixmap: &mut Pixmap) {
+        let mut paint = Paint::default();
+        match self {
+            Self::Fill { path, color, rule } => {
+                paint.set_color(*color);
+                pixmap.fill_path(path, &paint, *rule, Transform::identity(), None);
+            }
+            Self::Stroke {
+                path,
+                color,
+                stroke,
+            } => {
+                paint.set_color(*color);
+                pixmap.stroke_path(path, &paint, stroke, Transform::identity(), None);
+            }
+        }
+    }
+}
+
+//그룹 안의 항목은 앞쪽이 위에 그려지고, 채우기/선은 자기보다 앞에 나온 도형(하위 그룹 포함)에 적용됨
+//도형은 미리 화면 좌표로 변환해두고, 그리는 순서대로(위에서부터) ops에 넣음
+//반환값은 이 그룹의 도형들이라 바깥 그룹의 스타일도 여기에 적용될 수 있음
+fn collect_shapes(
+    shapes: &[Shape],
+    frame: f32,
+    parent_transform: Transform,
+    parent_opacity: f32,
+    ops: &mut Vec<DrawOp>,
+) -> Vec<Path> {
+    let (transform, opacity) = match shapes.iter().find_map(|shape| match shape {
+        Shape::Transform(transform) => Some(transform),
+        _ => None,
+    }) {
+        Some(group_transform) => (
+            parent_transform.pre_concat(group_transform.matrix(frame)),
+            parent_opacity * group_transform.opacity(frame),
+        ),
+        None => (parent_transform, parent_opacity),
+    };
+
+    let mut paths = Vec::new();
+    for shape in shapes {
+        match shape {
+            Shape::Group(items) => {
+                paths.extend(collect_shapes(items, frame, transform, opacity, ops));
+            }
+            Shape::Fill {
+                color,
+                opacity: fill_opacity,
+                rule,
+            } => {
+                if let Some(path) = merge_paths(&paths) {
+                    ops.push(DrawOp::Fill {
+                        path,
+                        color: property_color(color, fill_opacity, opacity, frame),
+                        rule: *rule,
+                    });
+                }
+            }
+            Shape::Stroke {
+                color,
+                opacity: stroke_opacity,
+                width,
+                cap,
+                join,
+            } => {
+                if let Some(path) = merge_paths(&paths) {
+                    //도형을 미리 변환해뒀으니 선 두께도 같은 비율로 늘려줌
+                    let (scale_x, scale_y) = transform.get_scale();
+                    let stroke = Stroke {
+                        width: width.scalar(frame) * (scale_x * scale_y).sqrt(),
+                        line_cap: *cap,
+                        line_join: *join,
+                        ..Stroke::default()
+                    };
+                    ops.push(DrawOp::Stroke {
+                        path,
+                        color: property_color(color, stroke_opacity, opacity, frame),
+                        stroke,
+                    });
+                }
+            }
+            Shape::Transform(_) => {}
+            geometry => {
+                if let Some(path) =
+                    build_geometry(geometry, frame).and_then(|p| p.transform(transform))
+                {
+                    paths.push(path);
+                }
+            }
+        }
+    }
+
+    paths
+}
+
+fn merge_paths(paths: &[Path]) -> Option<Path> {
+    let mut builder = PathBuilder::new();
+    for path in paths {
+        builder.push_path(path);
+    }
+    builder.finish()
+}
+
+fn build_geometry(shape: &Shape, frame: f32) -> Option<Path> {
+    match shape {
+        Shape::Rect {
+            position,
+            size,
+            roundness,
+        } => {
+            let [x, y] = position.point(frame);
+            let [width, height] = size.point(frame);
+            let rect = Rect::from_xywh(x - width / 2.0, y - height / 2.0, width, height)?;
+            let radius = roundness.scalar(frame).min(width / 2.0).min(height / 2.0);
+            if radius <= 0.0 {
+                return Some(PathBuilder::from_rect(rect));
+            }
+            rounded_rect(rect, radius)
+        }
+        Shape::Ellipse { position, size } => {
+            let [x, y] = position.point(frame);
+            let [width, height] = size.point(frame);
+            PathBuilder::from_oval(Rect::from_xywh(
+                x - width / 2.0,
+                y - height / 2.0,
+                width,
+                height,
+            )?)
+        }
+        Shape::Star {
+            position,
+            points,
+            rotation,
+            outer_radius,
+            inner_radius,
+        } => {
+            let [x, y] = position.point(frame);
+            let points = points.scalar(frame).round().max(3.0) as usize;
+            let outer_radius = outer_radius.scalar(frame);
+            let inner_radius = inner_radius.as_ref().map(|radius| radius.scalar(frame));
+            //꼭짓점이 위쪽(-90도)에서 시작함
+            let start = (rotation.scalar(frame) - 90.0).to_radians();
+
+            let corners: Vec<(f32, f32)> = match inner_radius {
+                Some(inner_radius) => (0..points * 2)
+                    .map(|i| {
+                        let radius = if i % 2 == 0 {
+                            outer_radius
+                        } else {
+                            inner_radius
+                        };
+                        let angle = start + std::f32::consts::PI * i as f32 / points as f32;
+                        (x + radius * angle.cos(), y + radius * angle.sin())
+                    })
+                    .collect(),
+                None => (0..points)
+                    .map(|i| {
+                        let angle = start + std::f32::consts::TAU * i as f32 / points as f32;
+                        (
+                            x + outer_radius * angle.cos(),
+                            y + outer_radius * angle.sin(),
+                        )
+                    })
+                    .collect(),
+            };
+
+            let mut builder = PathBuilder::new();
+            builder.move_to(corners[0].0, corners[0].1);
+            for (corner_x, corner_y) in &corners[1..] {
+                builder.line_to(*corner_x, *corner_y);
+            }
+            builder.close();
+            builder.finish()
+        }
+        Shape::Path { vertices, closed } => {
+            //꼭짓점마다 [x, y, 들어오는 핸들 x, y, 나가는 핸들 x, y]
+            let vertices = vertices.value(frame);
+            let points: Vec<&[f32]> = vertices.chunks_exact(6).collect();
+            let first = points.first()?;
+
+            let mut builder = PathBuilder::new();
+            builder.move_to(first[0], first[1]);
+            let segments = points.windows(2).map(|pair| (pair[0], pair[1]));
+            let closing = closed.then(|| (*points.last().unwrap(), *first));
+            for (from, to) in segments.chain(closing) {
+                builder.cubic_to(
+                    from[0] + from[4],
+                    from[1] + from[5],
+                    to[0] + to[2],
+                    to[1] + to[3],
+                    to[0],
+                    to[1],
+                );
+            }
+            if *closed {
+                builder.close();
+            }
+            builder.finish()
+        }
+        _ => None,
+    }
+}
+
+fn rounded_rect(rect: Rect, radius: f32) -> Option<Path> {
+    //원을 베지어 곡선 4개로 근사할 때 쓰는 값
+    let handle = radius * (1.0 - 0.552_284_8);
+    let (left, top, right, bottom) = (rect.left(), rect.top(), rect.right(), rect.bottom());
+
+    let mut builder = PathBuilder::new();
+    builder.move_to(left + radius, top);
+    builder.line_to(right - radius, top);
+    builder.cubic_to(
+        right - handle,
+        top,
+        right,
+        top + handle,
+        right,
+        top + radius,
+    );
+    builder.line_to(right, bottom - radius);
+    builder.cubic_to(
+        right,
+        bottom - handle,
+        right - handle,
+        bottom,
+        right - radius,
+        bottom,
+    );
+    builder.line_to(left + radius, bottom);
+    builder.cubic_to(
+        left + handle,
+        bottom,
+        left,
+        bottom - handle,
+        left,
+        bottom - radius,
+    );
+    builder.line_to(left, top + radius);
+    builder.cubic_to(left, top + handle, left + handle, top, left + radius, top);
+    builder.close();
+    builder.finish()
+}
+
+struct ShapeTransform {
+    anchor: Property,
+    position: Position,
+    scale: Property,
+    rotation: Property,
+    opacity: Property,
+}
+
+//위치는 x, y를 따로 움직이게 나눠서 저장되기도 함
+enum Position {
+    Combined(Property),
+    Split(Property, Property),
+}
+
+impl ShapeTransform {
+    fn parse(transform: &Value) -> Self {
+        let property = |value: &Value| Property::parse(value, parse_numbers);
+        let position = &transform["p"];
+
+        Self {
+            anchor: property(&transform["a"]),
+            position: match position["s"].as_bool() {
+                Some(true) => Position::Split(property(&position["x"]), property(&position["y"])),
+                _ => Position::Combined(property(position)),
+            },
+            scale: Property::parse(&transform["s"], parse_numbers).or(vec![100.0, 100.0]),
+            rotation: property(&transform["r"]),
+            opacity: Property::parse(&transform["o"], parse_numbers).or(vec![100.0]),
+        }
+    }
+
+    //앵커를 원점으로 옮김 -> 크기 -> 회전 -> 위치 순서
+    fn matrix(&self, frame: f32) -> Transform {
+        let [anchor_x, anchor_y] = self.anchor.point(frame);
+        let [position_x, position_y] = match &self.position {
+            Position::Combined(position) => position.point(frame),
+            Position::Split(x, y) => [x.scalar(frame), y.scalar(frame)],
+        };
+        let [scale_x, scale_y] = self.scale.point(frame);
+
+        Transform::from_translate(-anchor_x, -anchor_y)
+            .post_scale(scale_x / 100.0, scale_y / 100.0)
+            .post_rotate(self.rotation.scalar(frame))
+            .post_translate(position_x, position_y)
+    }
+
+    fn opacity(&self, frame: f32) -> f32 {
+        (self.opacity.scalar(frame) / 100.0).clamp(0.0, 1.0)
+    }
+}
+
+//애니메이션 되는 값. 숫자 여러 개로 펴서 들고 있고, 도형 패스도 꼭짓점을 펴서 같이 보간함
+enum Property {
+    Static(Vec<f32>),
+    Animated(Vec<Keyframe>),
+    Missing,
+}
+
+struct Keyframe {
+    time: f32,
+    start: Option<Vec<f32>>,
+    end: Option<Vec<f32>>,
+    hold: bool,
+    //이징 곡선의 두 제어점 (o가 앞, i가 뒤)
+    ease_out: (f32, f32),
+    ease_in: (f32, f32),
+}
+
+impl Property {
+    fn parse(property: &Value, parse_value: fn(&Value) -> Option<Vec<f32>>) -> Self {
+        let value = &property["k"];
+        let keyframes = value.as_array().filter(|keyframes| {
+            keyframes
+                .first()
+                .is_some_and(|first| first.get("t").is_some())
+        });
+
+        match keyframes {
+            Some(keyframes) => Self::Animated(
+                keyframes
+                    .iter()
+                    .map(|keyframe| Keyframe {
+                        time: keyframe["t"].as_f64().unwrap_or(0.0) as f32,
+                        start: parse_value(&keyframe["s"]),
+                        end: parse_value(&keyframe["e"]),
+                        hold: keyframe["h"].as_i64() == Some(1),
+                        ease_out: ease_point(&keyframe["o"], (0.0, 0.0)),
+                        ease_in: ease_point(&keyframe["i"], (1.0, 1.0)),
+                    })
+                    .collect(),
+            ),
+            None => match parse_value(value) {
+                Some(value) => Self::Static(value),
+                None => Self::Missing,
+            },
+        }
+    }
+
+    //값이 아예 없을 때 쓸 기본값
+    fn or(self, default: Vec<f32>) -> Self {
+        match self {
+            Self::Missing => Self::Static(default),
+            property => property,
+        }
+    }
+
+    fn value(&self, frame: f32) -> Vec<f32> {
+        let keyframes = match self {
+            Self::Static(value) => return value.clone(),
+            Self::Animated(keyframes) => keyframes,
+            Self::Missing => return Vec::new(),
+        };
+
+        //마지막 키프레임에는 s 없이 시간만 있는 경우가 있어서 앞 키프레임의 끝값으로 대신함
+        let start_of = |index: usize| -> Vec<f32> {
+            keyframes[index]
+                .start
+                .clone()
+                .or_else(|| {
+                    index
+                        .checked_sub(1)
+                        .and_then(|prev| keyframes[prev].end.clone())
+                })
+                .unwrap_or_default()
+        };
+
+        let Some(index) = keyframes
+            .iter()
+            .rposition(|keyframe| keyframe.time <= frame)
+        else {
+            return start_of(0);
+        };
+        let Some(next) = keyframes.get(index + 1) else {
+            return start_of(index);
+        };
+
+        let keyframe = &keyframes[index];
+        let start = start_of(index);
+        if keyframe.hold || next.time <= keyframe.time {
+            return start;
+        }
+        let end = keyframe
+            .end
+            .clone()
+            .or_else(|| next.start.clone())
+            .unwrap_or_else(|| start.clone());
+
+        let progress = (frame - keyframe.time) / (next.time - keyframe.time);
+        let eased = cubic_ease(keyframe.ease_out, keyframe.ease_in, progress);
+        start
+            .iter()
+            .zip(end.iter())
+            .map(|(from, to)| from + (to - from) * eased)
+            .collect()
+    }
+
+    fn scalar(&self, frame: f32) -> f32 {
+        self.value(frame).first().copied().unwrap_or(0.0)
+    }
+
+    fn point(&self, frame: f32) -> [f32; 2] {
+        let value = self.value(frame);
+        [
+            value.first().copied().unwrap_or(0.0),
+            value.get(1).copied().unwrap_or(0.0),
+        ]
+    }
+}
+
+fn ease_point(point: &Value, default: (f32, f32)) -> (f32, f32) {
+    //축마다 따로 이징이 있을 수 있지만 첫 번째 것만 씀
+    let first = |value: &Value| match value {
+        Value::Array(values) => values.first().and_then(|v| v.as_f64()),
+        value => value.as_f64(),
+    };
+    match (first(&point["x"]), first(&point["y"])) {
+        (Some(x), Some(y)) => (x as f32, y as f32),
+        _ => default,
+    }
+}
+
+//(0,0) - out - in - (1,1) 베지어 곡선에서 x가 progress일 때의 y
+fn cubic_ease(ease_out: (f32, f32), ease_in: (f32, f32), progress: f32) -> f32 {
+    let progress = progress.clamp(0.0, 1.0);
+    let bezier = |t: f32, p1: f32, p2: f32| {
+        let inverse = 1.0 - t;
+        3.0 * inverse * inverse * t * p1 + 3.0 * inverse * t * t * p2 + t * t * t
+    };
+
+    //x(t)는 단조증가라서 이분법으로 충분함
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..24 {
+        let middle = (low + high) / 2.0;
+        if bezier(middle, ease_out.0, ease_in.0) < progress {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    bezier((low + high) / 2.0, ease_out.1, ease_in.1)
+}
+
+fn parse_numbers(value: &Value) -> Option<Vec<f32>> {
+    match value {
+        Value::Number(number) => Some(vec![number.as_f64()? as f32]),
+        Value::Array(values) => values
+            .iter()
+            .map(|v| v.as_f64().map(|v| v as f32))
+            .collect(),
+        _ => None,
+    }
+}
+
+//패스 값은 {i, o, v, c} 객체거나, 키프레임 안에서는 그게 하나 든 배열임
+fn bezier_object(value: &Value) -> Option<&Value> {
+    match value {
+        Value::Array(values) => values.first().filter(|v| v.is_object()),
+        Value::Object(_) => Some(value),
+        _ => None,
+    }
+}
+
+fn parse_bezier(value: &Value) -> Option<Vec<f32>> {
+    let bezier = bezier_object(value)?;
+    let point = |key: &str, index: usize| -> Option<(f32, f32)> {
+        let point = bezier[key].get(index)?;
+        Some((
+            point.get(0)?.as_f64()? as f32,
+            point.get(1)?.as_f64()? as f32,
+        ))
+    };
+
+    let count = bezier["v"].as_array()?.len();
+    let mut flattened = Vec::with_capacity(count * 6);
+    for index in 0..count {
+        let (x, y) = point("v", index)?;
+        let (in_x, in_y) = point("i", index).unwrap_or((0.0, 0.0));
+        let (out_x, out_y) = point("o", index).unwrap_or((0.0, 0.0));
+        flattened.extend([x, y, in_x, in_y, out_x, out_y]);
+    }
+    Some(flattened)
+}
+
+fn bezier_closed(property: &Value) -> bool {
+    let value = &property["k"];
+    let bezier = match value {
+        Value::Array(keyframes) if keyframes.first().is_some_and(|k| k.get("t").is_some()) => {
+            bezier_object(&keyframes[0]["s"])
+        }
+        value => bezier_object(value),
+    };
+    bezier.and_then(|b| b["c"].as_bool()).unwrap_or(false)
+}
+
+//그라디언트 값은 [위치, r, g, b, 위치, r, g, b, ...] 순서
+fn parse_gradient_first_color(value: &Value) -> Option<Vec<f32>> {
+    let values = parse_numbers(value)?;
+    Some(values.get(1..4)?.to_vec())
+}
+
+fn parse_hex_color(hex: &str) -> Color {
+    let hex = hex.trim_start_matches('#');
+    let channel = |index: usize| {
+        hex.get(index..index + 2)
+            .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+            .unwrap_or(0)
+    };
+    Color::from_rgba8(channel(0), channel(2), channel(4), 255)
+}
+
+fn property_color(color: &Property, opacity: &Property, group_opacity: f32, frame: f32) -> Color {
+    let mut rgb = color.value(frame);
+    //예전 버전 파일은 0~255 범위로 저장돼있음
+    if rgb.iter().take(3).any(|channel| *channel > 1.0) {
+        rgb.iter_mut().for_each(|channel| *channel /= 255.0);
+    }
+    let channel = |index: usize| rgb.get(index).copied().unwrap_or(0.0).clamp(0.0, 1.0);
+    let alpha = opacity.value(frame).first().copied().unwrap_or(100.0) / 100.0;
+
+    Color::from_rgba(channel(0), channel(1), channel(2), 1.0)
+        .map(|color| with_opacity(color, alpha * group_opacity))
+        .unwrap_or(Color::TRANSPARENT)
+}
+
+fn with_opacity(mut color: Color, opacity: f32) -> Color {
+    color.apply_opacity(opacity.clamp(0.0, 1.0));
+    color
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //32x32 캔버스에서 빨간 8x8 네모가 왼쪽에서 오른쪽으로 1초 동안 움직이는 최소한의 로티
+    const MOVING_SQUARE: &str = r#"{
+        "v": "5.7.0", "w": 32, "h": 32, "fr": 10, "ip": 0, "op": 10,
+        "layers": [{
+            "ty": 4, "ind": 1, "ip": 0, "op": 10, "st": 0,
+            "ks": {
+                "p": {"a": 1, "k": [
+                    {"t": 0, "s": [8, 16], "o": {"x": [0], "y": [0]}, "i": {"x": [1], "y": [1]}},
+                    {"t": 10, "s": [24, 16]}
+                ]}
+            },
+            "shapes": [{
+                "ty": "gr",
+                "it": [
+                    {"ty": "rc", "p": {"a": 0, "k": [0, 0]}, "s": {"a": 0, "k": [8, 8]}, "r": {"a": 0, "k": 0}},
+                    {"ty": "fl", "c": {"a": 0, "k": [1, 0, 0, 1]}, "o": {"a": 0, "k": 100}},
+                    {"ty": "tr"}
+                ]
+            }]
+        }]
+    }"#;
+
+    fn pixel(rgba: &[u8], x: usize, y: usize) -> &[u8] {
+        let offset = (y * 32 + x) * 4;
+        &rgba[offset..offset + 4]
+    }
+
+    #[test]
+    fn renders_minimal_fixture() {
+        let animation = LottieAnimation::parse(MOVING_SQUARE.as_bytes()).unwrap();
+        assert_eq!((animation.width, animation.height), (32, 32));
+        assert_eq!(animation.duration(), 1.0);
+
+        let first = animation
+            .render(0.0, Transform::identity(), 32, 32)
+            .unwrap();
+        assert_eq!(pixel(&first, 8, 16), [255, 0, 0, 255]);
+        assert_eq!(pixel(&first, 24, 16)[3], 0);
+
+        //키프레임 사이에서는 위치가 보간됨
+        let middle = animation
+            .render(0.5, Transform::identity(), 32, 32)
+            .unwrap();
+        assert_eq!(pixel(&middle, 16, 16), [255, 0, 0, 255]);
+        assert_eq!(pixel(&middle, 8, 16)[3], 0);
+    }
+
+    //위 레이어(td)가 아래 레이어(tt)의 알파 매트인 로티. 매트를 빼고 그리면 네모 전체가 보임
+    const MATTED_SQUARE: &str = r##"{
+        "v": "5.7.0", "w": 32, "h": 32, "fr": 10, "ip": 0, "op": 10,
+        "layers": [
+            {
+                "ty": 4, "ind": 1, "td": 1, "ip": 0, "op": 10, "st": 0, "ks": {},
+                "shapes": [{"ty": "gr", "it": [
+                    {"ty": "el", "p": {"a": 0, "k": [16, 16]}, "s": {"a": 0, "k": [8, 8]}},
+                    {"ty": "fl", "c": {"a": 0, "k": [1, 1, 1, 1]}, "o": {"a": 0, "k": 100}},
+                    {"ty": "tr"}
+                ]}]
+            },
+            {
+                "ty": 1, "ind": 2, "tt": 1, "ip": 0, "op": 10, "st": 0, "ks": {},
+                "sc": "#ff0000", "sw": 32, "sh": 32
+            }
+        ]
+    }"##;
+
+    #[test]
+    fn rejects_matted_layers() {
+        assert!(LottieAnimation::parse(MATTED_SQUARE.as_bytes()).is_none());
+
+        //프리컴프 안에 있어도 똑같이 거절함
+        let nested = format!(
+            r#"{{
+                "w": 32, "h": 32, "fr": 10, "ip": 0, "op": 10,
+                "assets": [{{"id": "comp", "layers": {}}}],
+                "layers": [{{"ty": 0, "refId": "comp", "ip": 0, "op": 10, "ks": {{}}}}]
+            }}"#,
+            serde_json::from_str::<Value>(MATTED_SQUARE).unwrap()["layers"]
+        );
+        assert!(LottieAnimation::parse(nested.as_bytes()).is_none());
+    }
+
+    #[test]
+    fn rejects_masks_and_time_remap() {
+        let with_layer_field = |field: &str| {
+            let mut root: Value = serde_json::from_str(MOVING_SQUARE).unwrap();
+            let (key, value) = field.split_once('=').unwrap();
+            root["layers"][0][key] = serde_json::from_str(value).unwrap();
+            serde_json::to_vec(&root).unwrap()
+        };
+
+        assert!(LottieAnimation::parse(&with_layer_field("hasMask=true")).is_none());
+        assert!(LottieAnimation::parse(&with_layer_field(
+            r#"masksProperties=[{"mode": "a", "pt": {"a": 0, "k": {}}}]"#
+        ))
+        .is_none());
+        assert!(LottieAnimation::parse(&with_layer_field(r#"tm={"a": 0, "k": 0}"#)).is_none());
+        assert!(LottieAnimation::parse(&with_layer_field("masksProperties=[]")).is_some());
+    }
+
+    #[test]
+    fn rejects_invalid_json() {
+        assert!(LottieAnimation::parse(
+            b"{\"w\": 0, \"h\": 32, \"fr\": 30, \"op\": 10, \"layers\": []}"
+        )
+        .is_none());
+        assert!(LottieAnimation::parse(b"not json").is_none());
+    }
+}
@@ -0,0 +1,407 @@
+//정적 이모지 하나로 쓰다듬기, 흔들기, 돌리기 같은 움짤을 만드는 부분
+//템플릿마다 프레임별 위치/크기/회전만 정해두고, 그리는 건 tiny-skia로 한 번에 함
+use image::RgbaImage;
+use serenity::builder::CreateAttachment;
+use tiny_skia::{FilterQuality, Pixmap, PixmapPaint, Transform};
+
+use std::f32::consts::PI;
+
+use crate::utils::effects::{from_pixmap, to_pixmap};
+use crate::utils::emoji_cache::CachedImage;
+use crate::utils::scene_core::{
+    decode_emoji_frames, encode_gif_frames, fetch_image_bytes, EmojiFrames, ImageTransferError,
+};
+use crate::utils::upload_limit::{fit_to_upload_limit, SizeCompromise};
+
+//결과 움짤 크기
+const MEME_CANVAS_SIZE: u32 = 128;
+//캔버스 안에서 이모지가 차지하는 기본 크기
+const MEME_SPRITE_SIZE: f32 = 96.0;
+
+pub const MIN_MEME_INTENSITY: u32 = 1;
+pub const MAX_MEME_INTENSITY: u32 = 10;
+pub const DEFAULT_MEME_INTENSITY: u32 = 5;
+pub const MIN_MEME_DELAY: u32 = 2;
+pub const MAX_MEME_DELAY: u32 = 50;
+
+//쓰다듬는 손 프레임. 손바닥 아랫면이 HAND_CONTACT_Y에 오게 그려져 있음
+const PETPET_HAND_FRAMES: [&[u8]; 5] = [
+    include_bytes!("../../assets/petpet/hand_0.png"),
+    include_bytes!("../../assets/petpet/hand_1.png"),
+    include_bytes!("../../assets/petpet/hand_2.png"),
+    include_bytes!("../../assets/petpet/hand_3.png"),
+    include_bytes!("../../assets/petpet/hand_4.png"),
+];
+const HAND_CONTACT_Y: f32 = 44.0;
+//손 프레임마다 이모지가 눌리는 정도. 세기 5 기준
+const PETPET_SQUISH: [f32; 5] = [0.0, 0.12, 0.24, 0.16, 0.06];
+
+//흔들기는 랜덤하게 보이도록 미리 정한 방향으로 움직임
+const SHAKE_OFFSETS: [(f32, f32); 8] = [
+    (0.0, 0.0),
+    (1.0, -0.6),
+    (-0.8, 0.9),
+    (0.7, 0.8),
+    (-1.0, -0.5),
+    (0.4, -1.0),
+    (-0.5, 0.3),
+    (0.9, 0.4),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemeTemplate {
+    PetPet,
+    Shake,
+    Spin,
+    Bounce,
+    ZoomIn,
+    Rainbow,
+}
+
+impl MemeTemplate {
+    pub fn string_to_value(input_str: &str) -> Self {
+        match input_str {
+            "Shake" => Self::Shake,
+            "Spin" => Self::Spin,
+            "Bounce" => Self::Bounce,
+            "ZoomIn" => Self::ZoomIn,
+            "Rainbow" => Self::Rainbow,
+            _ => Self::PetPet,
+        }
+    }
+
+    pub fn value_to_string(input_value: &MemeTemplate) -> String {
+        let st = match input_value {
+            Self::PetPet => "PetPet",
+            Self::Shake => "Shake",
+            Self::Spin => "Spin",
+            Self::Bounce => "Bounce",
+            Self::ZoomIn => "ZoomIn",
+            Self::Rainbow => "Rainbow",
+        };
+        st.to_string()
+    }
+
+    //프레임 간격을 안 정했을 때 쓰는 값 (1/100초)
+    fn default_delay(&self) -> u32 {
+        match self {
+            Self::PetPet | Self::Rainbow => 6,
+            Self::ZoomIn => 5,
+            Self::Shake | Self::Spin | Self::Bounce => 4,
+        }
+    }
+
+    fn file_name(&self) -> String {
+        format!("{}.gif", Self::value_to_string(self).to_lowercase())
+    }
+}
+
+//세기는 흔들림 폭, 회전 속도, 눌리는 정도처럼 템플릿마다 뜻이 조금씩 다름
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemeParams {
+    pub intensity: u32,
+    pub delay: Option<u32>,
+}
+
+impl Default for MemeParams {
+    fn default() -> Self {
+        Self {
+            intensity: DEFAULT_MEME_INTENSITY,
+            delay: None,
+        }
+    }
+}
+
+//이모지가 움짤이어도 첫 프레임만 씀
+pub async fn generate_meme(
+    img_url: &str,
+    template: MemeTemplate,
+    params: MemeParams,
+    upload_limit: u64,
+) -> Result<(CreateAttachment, Option<SizeCompromise>), ImageTransferError> {
+    //크기 128x128로 가져옴
+    let img_bytes = fetch_image_bytes(&format!("{}?size=128", img_url)).await?;
+
+    let (generated, compromise) = tokio::task::spawn_blocking(move || {
+        let (sprite, _) = decode_emoji_frames(&img_bytes)?
+            .into_iter()
+            .next()
+            .ok_or(ImageTransferError::DecodingError)?;
+        let frames = render_meme_frames(&sprite, template, params)?;
+
+        fit_to_upload_limit(
+            CachedImage {
+                bytes: encode_gif_frames(frames)?,
+                filename: template.file_name(),
+            },
+            upload_limit,
+        )
+    })
+    .await
+    .map_err(|_| ImageTransferError::Mollu)??;
+
+    Ok((
+        CreateAttachment::bytes(generated.bytes, generated.filename),
+        compromise,
+    ))
+}
+
+pub fn render_meme_frames(
+    sprite: &RgbaImage,
+    template: MemeTemplate,
+    params: MemeParams,
+) -> Result<EmojiFrames, ImageTransferError> {
+    let source = to_pixmap(sprite).ok_or(ImageTransferError::InvalidImageSize)?;
+    let intensity = params
+        .intensity
+        .clamp(MIN_MEME_INTENSITY, MAX_MEME_INTENSITY) as f32;
+    let delay = params
+        .delay
+        .unwrap_or(template.default_delay())
+        .clamp(MIN_MEME_DELAY, MAX_MEME_DELAY);
+
+    let canvas = MEME_CANVAS_SIZE as f32;
+    let (width, height) = (source.width() as f32, source.height() as f32);
+    let longer_side = width.max(height);
+
+    let frame_count = match template {
+        MemeTemplate::PetPet => PETPET_HAND_FRAMES.len(),
+        MemeTemplate::Shake => SHAKE_OFFSETS.len(),
+        //세기가 셀수록 한 바퀴를 적은 프레임으로 돎
+        MemeTemplate::Spin => (60.0 / intensity).round().clamp(6.0, 60.0) as usize,
+        MemeTemplate::Bounce | MemeTemplate::ZoomIn | MemeTemplate::Rainbow => 12,
+    };
+
+    let hands = match template {
+        MemeTemplate::PetPet => PETPET_HAND_FRAMES
+            .iter()
+            .map(|hand| Pixmap::decode_png(hand).map_err(|_| ImageTransferError::DecodingError))
+            .collect::<Result<Vec<Pixmap>, ImageTransferError>>()?,
+        _ => Vec::new(),
+    };
+
+    let mut frames = Vec::with_capacity(frame_count);
+    for index in 0..frame_count {
+        let progress = index as f32 / frame_count as f32;
+        let mut pixmap = Pixmap::new(MEME_CANVAS_SIZE, MEME_CANVAS_SIZE)
+            .ok_or(ImageTransferError::InvalidImageSize)?;
+
+        let placement = match template {
+            MemeTemplate::PetPet => {
+                //바닥에 붙인 채로 위에서 누르면 옆으로 퍼짐
+                let squish = PETPET_SQUISH[index] * intensity / DEFAULT_MEME_INTENSITY as f32;
+                let scale = MEME_SPRITE_SIZE / longer_side;
+                let (scale_x, scale_y) = (scale * (1.0 + squish * 0.5), scale * (1.0 - squish));
+                let bottom = canvas - 4.0;
+                SpritePlacement {
+                    center: (canvas / 2.0 + 8.0, bottom - height * scale_y / 2.0),
+                    scale: (scale_x, scale_y),
+                    rotation: 0.0,
+                }
+            }
+            MemeTemplate::Shake => {
+                let (offset_x, offset_y) = SHAKE_OFFSETS[index];
+                let amplitude = intensity * 1.5;
+                SpritePlacement {
+                    center: (
+                        canvas / 2.0 + offset_x * amplitude,
+                        canvas / 2.0 + offset_y * amplitude,
+                    ),
+                    scale: uniform((canvas - 2.0 * amplitude) / longer_side),
+                    rotation: offset_x * intensity,
+                }
+            }
+            MemeTemplate::Spin => SpritePlacement {
+                center: (canvas / 2.0, canvas / 2.0),
+                //대각선 길이가 캔버스에 들어가야 돌려도 안 잘림
+                scale: uniform((canvas - 4.0) / width.hypot(height)),
+                rotation: 360.0 * progress,
+            },
+            MemeTemplate::Bounce => {
+                let lift = (PI * progress).sin();
+                let jump = intensity * 4.0;
+                //바닥에 닿을 때만 살짝 찌그러짐
+                let squash = (1.0 - lift * 4.0).max(0.0) * 0.15;
+                let scale =
+                    ((canvas - 4.0 - jump) / longer_side).min(MEME_SPRITE_SIZE / longer_side);
+                let (scale_x, scale_y) = (scale * (1.0 + squash), scale * (1.0 - squash));
+                let bottom = canvas - 2.0 - lift * jump;
+                SpritePlacement {
+                    center: (canvas / 2.0, bottom - height * scale_y / 2.0),
+                    scale: (scale_x, scale_y),
+                    rotation: 0.0,
+                }
+            }
+            MemeTemplate::ZoomIn => {
+                //점점 빨라지게 확대했다가 처음으로 돌아감
+                let zoom = 1.0 + intensity * 0.3 * progress * progress;
+                SpritePlacement {
+                    center: (canvas / 2.0, canvas / 2.0),
+                    scale: uniform(canvas / longer_side * zoom),
+                    rotation: 0.0,
+                }
+            }
+            MemeTemplate::Rainbow => SpritePlacement {
+                center: (canvas / 2.0, canvas / 2.0),
+                scale: uniform(canvas / longer_side),
+                rotation: 0.0,
+            },
+        };
+        placement.draw(&mut pixmap, &source, (width, height));
+
+        if let Some(hand) = hands.get(index) {
+            //손바닥이 눌린 이모지 윗면에 닿게 내림
+            let sprite_top = placement.center.1 - height * placement.scale.1 / 2.0;
+            pixmap.draw_pixmap(
+                0,
+                0,
+                hand.as_ref(),
+                &PixmapPaint::default(),
+                Transform::from_translate(0.0, sprite_top - HAND_CONTACT_Y),
+                None,
+            );
+        }
+
+        let mut frame = from_pixmap(&pixmap);
+        if template == MemeTemplate::Rainbow {
+            tint_rainbow(&mut frame, 360.0 * progress, intensity);
+        }
+        frames.push((frame, delay));
+    }
+
+    Ok(frames)
+}
+
+//원본 이미지 중심을 center에 두고 크기, 회전을 적용함
+struct SpritePlacement {
+    center: (f32, f32),
+    scale: (f32, f32),
+    //시계 방향, 도 단위
+    rotation: f32,
+}
+
+impl SpritePlacement {
+    fn draw(&self, pixmap: &mut Pixmap, source: &Pixmap, (width, height): (f32, f32)) {
+        let transform = Transform::from_translate(-width / 2.0, -height / 2.0)
+            .post_scale(self.scale.0, self.scale.1)
+            .post_rotate(self.rotation)
+            .post_translate(self.center.0, self.center.1);
+        pixmap.draw_pixmap(
+            0,
+            0,
+            source.as_ref(),
+            &PixmapPaint {
+                quality: FilterQuality::Bicubic,
+                ..PixmapPaint::default()
+            },
+            transform,
+            None,
+        );
+    }
+}
+
+fn uniform(scale: f32) -> (f32, f32) {
+    (scale, scale)
+}
+
+//색조를 돌리고 그 색을 살짝 덮어서, 회색 이모지도 무지개색으로 보이게 함
+fn tint_rainbow(frame: &mut RgbaImage, hue: f32, intensity: f32) {
+    *frame = image::imageops::huerotate(frame, hue.round() as i32);
+
+    let tint = hue_to_rgb(hue);
+    let strength = intensity * 0.06;
+    for pixel in frame.pixels_mut() {
+        for channel in 0..3 {
+            pixel[channel] =
+                (pixel[channel] as f32 * (1.0 - strength) + tint[channel] * strength) as u8;
+        }
+    }
+}
+
+//채도, 명도가 최대인 색
+fn hue_to_rgb(hue: f32) -> [f32; 3] {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let rising = 255.0 * sector.fract();
+    let falling = 255.0 - rising;
+    match sector as u32 {
+        0 => [255.0, rising, 0.0],
+        1 => [falling, 255.0, 0.0],
+        2 => [0.0, 255.0, rising],
+        3 => [0.0, falling, 255.0],
+        4 => [rising, 0.0, 255.0],
+        _ => [255.0, 0.0, falling],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATES: [MemeTemplate; 6] = [
+        MemeTemplate::PetPet,
+        MemeTemplate::Shake,
+        MemeTemplate::Spin,
+        MemeTemplate::Bounce,
+        MemeTemplate::ZoomIn,
+        MemeTemplate::Rainbow,
+    ];
+
+    fn sprite() -> RgbaImage {
+        RgbaImage::from_pixel(32, 32, image::Rgba([200, 40, 40, 255]))
+    }
+
+    fn render(template: MemeTemplate, delay: Option<u32>) -> EmojiFrames {
+        let params = MemeParams {
+            delay,
+            ..MemeParams::default()
+        };
+        render_meme_frames(&sprite(), template, params).unwrap()
+    }
+
+    #[test]
+    fn renders_each_template_on_fixed_canvas() {
+        for template in TEMPLATES {
+            let frames = render(template, None);
+            let expected = match template {
+                MemeTemplate::PetPet => 5,
+                MemeTemplate::Shake => 8,
+                _ => 12,
+            };
+            assert_eq!(frames.len(), expected, "{:?}", template);
+            for (frame, delay) in &frames {
+                assert_eq!(
+                    frame.dimensions(),
+                    (MEME_CANVAS_SIZE, MEME_CANVAS_SIZE),
+                    "{:?}",
+                    template
+                );
+                assert_eq!(*delay, template.default_delay(), "{:?}", template);
+            }
+        }
+    }
+
+    #[test]
+    fn clamps_delay() {
+        for template in TEMPLATES {
+            let too_fast = render(template, Some(0));
+            assert!(too_fast.iter().all(|(_, delay)| *delay == MIN_MEME_DELAY));
+            let too_slow = render(template, Some(1000));
+            assert!(too_slow.iter().all(|(_, delay)| *delay == MAX_MEME_DELAY));
+        }
+    }
+
+    #[test]
+    fn petpet_draws_the_hand() {
+        //투명한 이모지로 그리면 보이는 건 손뿐임
+        let transparent = RgbaImage::new(32, 32);
+        let is_drawn = |template| {
+            render_meme_frames(&transparent, template, MemeParams::default())
+                .unwrap()
+                .iter()
+                .all(|(frame, _)| frame.pixels().any(|pixel| pixel[3] > 0))
+        };
+
+        assert!(is_drawn(MemeTemplate::PetPet));
+        assert!(!is_drawn(MemeTemplate::Bounce));
+    }
+}
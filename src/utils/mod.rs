@@ -1,3 +1,15 @@
+pub mod apng;
+pub mod caption;
+pub mod effects;
+pub mod emoji_cache;
+pub mod emoji_tokenizer;
 pub mod frameworks;
+pub mod gif_encoder;
 pub mod guild_config;
+pub mod lottie;
+pub mod meme;
+pub mod repost_control;
 pub mod scene_core;
+pub mod twemoji;
+pub mod upload_limit;
+pub mod webhook_repost;
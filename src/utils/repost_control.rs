@@ -0,0 +1,302 @@
+//자동 확대로 다시 보낸 메시지에 붙이는 삭제 버튼과, 원래 누가 보낸 건지 남기는 기록
+//기록은 DB에 남겨둬서 봇이 다시 켜져도 버튼과 자동 삭제가 그대로 동작함
+use bson::{doc, Document};
+use futures::TryStreamExt;
+use log::{error, warn};
+use mongodb::{options::IndexOptions, Collection, IndexModel};
+use serenity::{
+    builder::{
+        CreateActionRow, CreateButton, CreateInteractionResponse, CreateInteractionResponseMessage,
+    },
+    client::Context,
+    model::{
+        application::{ButtonStyle, ComponentInteraction},
+        channel::Message,
+        id::{ChannelId, MessageId, UserId, WebhookId},
+    },
+};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::GlobalRepostLog;
+
+pub const REPOST_DELETE_ID: &str = "repost_delete";
+//이만큼 지난 기록은 DB에서 지움. 그 뒤로는 메시지 관리 권한이 있는 사람만 지울 수 있음
+const RECORD_TTL: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+//ready는 다시 연결할 때마다 또 오니까, 자동 삭제는 프로세스마다 한 번만 다시 걸어둠
+static EXPIRIES_RESUMED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone)]
+struct RepostRecord {
+    message_id: MessageId,
+    channel_id: ChannelId,
+    author_id: UserId,
+    //"**이름** :" 줄을 따로 보냈으면 그 메시지도 같이 지움
+    name_line_id: Option<MessageId>,
+    //웹후크로 보낸 메시지는 웹후크로 지워야 함
+    webhook_id: Option<WebhookId>,
+    //유닉스 시간(초)
+    expires_at: Option<i64>,
+}
+
+impl RepostRecord {
+    fn to_document(&self) -> Document {
+        doc! {
+            "message_id" : self.message_id.get() as i64,
+            "channel_id" : self.channel_id.get() as i64,
+            "author_id" : self.author_id.get() as i64,
+            "name_line_id" : self.name_line_id.map(|id| id.get() as i64),
+            "webhook_id" : self.webhook_id.map(|id| id.get() as i64),
+            "expires_at" : self.expires_at,
+            "created_at" : bson::DateTime::now(),
+        }
+    }
+
+    fn from_document(document: &Document) -> Option<Self> {
+        let id = |key: &str| {
+            document
+                .get_i64(key)
+                .ok()
+                .filter(|id| *id > 0)
+                .map(|id| id as u64)
+        };
+        Some(Self {
+            message_id: MessageId::new(id("message_id")?),
+            channel_id: ChannelId::new(id("channel_id")?),
+            author_id: UserId::new(id("author_id")?),
+            name_line_id: id("name_line_id").map(MessageId::new),
+            webhook_id: id("webhook_id").map(WebhookId::new),
+            expires_at: document.get_i64("expires_at").ok(),
+        })
+    }
+}
+
+pub fn delete_button() -> CreateActionRow {
+    CreateActionRow::Buttons(vec![CreateButton::new(REPOST_DELETE_ID)
+        .emoji('❌')
+        .style(ButtonStyle::Secondary)])
+}
+
+//다시 보낸 메시지를 기록하고, expire_seconds가 0이 아니면 그만큼 지나서 지움
+pub async fn record_repost(
+    ctx: &Context,
+    author_id: UserId,
+    repost: &Message,
+    name_line: Option<&Message>,
+    expire_seconds: u32,
+) {
+    let record = RepostRecord {
+        message_id: repost.id,
+        channel_id: repost.channel_id,
+        author_id,
+        name_line_id: name_line.map(|name_line| name_line.id),
+        webhook_id: repost.webhook_id,
+        expires_at: (expire_seconds > 0).then(|| unix_now() + expire_seconds as i64),
+    };
+
+    if let Err(why) = repost_log(ctx)
+        .await
+        .insert_one(record.to_document(), None)
+        .await
+    {
+        error!("couldn't record repost {}. {:?}", record.message_id, why);
+    }
+
+    if record.expires_at.is_some() {
+        schedule_expiry(ctx.clone(), record);
+    }
+}
+
+//원래 보낸 사람이거나 메시지 관리 권한이 있으면 지움
+pub async fn delete_repost(ctx: &Context, component: &ComponentInteraction) {
+    let record = find_record(ctx, component.message.id).await;
+    let is_author = record
+        .as_ref()
+        .is_some_and(|record| record.author_id == component.user.id);
+    let can_manage = component
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.manage_messages());
+
+    if !is_author && !can_manage {
+        if let Err(why) = component
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content("이모지를 보낸 사람이나 메시지 관리 권한이 있는 사람만 지울 수 있습니다.")
+                        .ephemeral(true),
+                ),
+            )
+            .await
+        {
+            error!("[repost_delete] sending error: {:?}", why);
+        }
+        return;
+    }
+
+    //버튼이 달린 메시지는 웹후크로 보냈어도 상호작용 응답으로 지울 수 있음
+    if let Err(why) = component
+        .create_response(&ctx.http, CreateInteractionResponse::Acknowledge)
+        .await
+    {
+        error!("[repost_delete] sending error: {:?}", why);
+        return;
+    }
+    if let Err(why) = component.delete_response(&ctx.http).await {
+        error!("couldn't delete repost {}. {:?}", component.message.id, why);
+        return;
+    }
+
+    if let Some(record) = record {
+        delete_name_line(ctx, &record).await;
+        forget_record(ctx, record.message_id).await;
+    }
+}
+
+//봇이 다시 켜졌을 때 아직 안 지운 자동 삭제를 다시 걸어둠
+pub async fn resume_expiries(ctx: &Context) {
+    if EXPIRIES_RESUMED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let collection = repost_log(ctx).await;
+
+    let ttl_index = IndexModel::builder()
+        .keys(doc! { "created_at" : 1 })
+        .options(IndexOptions::builder().expire_after(RECORD_TTL).build())
+        .build();
+    if let Err(why) = collection.create_index(ttl_index, None).await {
+        error!("couldn't create repost record index. {:?}", why);
+    }
+
+    let records: Vec<Document> = match collection
+        .find(doc! { "expires_at" : { "$type" : "long" } }, None)
+        .await
+    {
+        Ok(cursor) => cursor.try_collect().await.unwrap_or_default(),
+        Err(why) => {
+            error!("couldn't load repost records. {:?}", why);
+            return;
+        }
+    };
+
+    for record in records.iter().filter_map(RepostRecord::from_document) {
+        schedule_expiry(ctx.clone(), record);
+    }
+}
+
+fn schedule_expiry(ctx: Context, record: RepostRecord) {
+    let Some(expires_at) = record.expires_at else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        let remaining = (expires_at - unix_now()).max(0) as u64;
+        tokio::time::sleep(Duration::from_secs(remaining)).await;
+
+        let deleted = match record.webhook_id {
+            Some(webhook_id) => match ctx.http.get_webhook(webhook_id).await {
+                Ok(webhook) => {
+                    //스레드에 보낸 메시지는 부모 채널 웹후크로 보냈으니 스레드를 알려줘야 함
+                    let thread_id = (webhook.channel_id != Some(record.channel_id))
+                        .then_some(record.channel_id);
+                    webhook
+                        .delete_message(&ctx.http, thread_id, record.message_id)
+                        .await
+                }
+                Err(why) => Err(why),
+            },
+            None => {
+                record
+                    .channel_id
+                    .delete_message(&ctx.http, record.message_id)
+                    .await
+            }
+        };
+        //이미 누가 지웠으면 실패해도 상관없음
+        if let Err(why) = deleted {
+            warn!("couldn't expire repost {}. {:?}", record.message_id, why);
+        }
+
+        delete_name_line(&ctx, &record).await;
+        forget_record(&ctx, record.message_id).await;
+    });
+}
+
+async fn delete_name_line(ctx: &Context, record: &RepostRecord) {
+    if let Some(name_line_id) = record.name_line_id {
+        if let Err(why) = record
+            .channel_id
+            .delete_message(&ctx.http, name_line_id)
+            .await
+        {
+            warn!("couldn't delete name line {}. {:?}", name_line_id, why);
+        }
+    }
+}
+
+async fn find_record(ctx: &Context, message_id: MessageId) -> Option<RepostRecord> {
+    match repost_log(ctx)
+        .await
+        .find_one(doc! { "message_id" : message_id.get() as i64 }, None)
+        .await
+    {
+        Ok(document) => RepostRecord::from_document(&document?),
+        Err(why) => {
+            error!("couldn't find repost record {}. {:?}", message_id, why);
+            None
+        }
+    }
+}
+
+async fn forget_record(ctx: &Context, message_id: MessageId) {
+    if let Err(why) = repost_log(ctx)
+        .await
+        .delete_one(doc! { "message_id" : message_id.get() as i64 }, None)
+        .await
+    {
+        error!("couldn't delete repost record {}. {:?}", message_id, why);
+    }
+}
+
+async fn repost_log(ctx: &Context) -> Collection<Document> {
+    let data_read = ctx.data.read().await;
+    data_read
+        .get::<GlobalRepostLog>()
+        .expect("poisened")
+        .clone()
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_document_round_trip() {
+        let record = RepostRecord {
+            message_id: MessageId::new(1_234_567_890_123_456_789),
+            channel_id: ChannelId::new(2),
+            author_id: UserId::new(3),
+            name_line_id: None,
+            webhook_id: Some(WebhookId::new(4)),
+            expires_at: Some(1_700_000_000),
+        };
+        let loaded = RepostRecord::from_document(&record.to_document()).unwrap();
+
+        assert_eq!(loaded.message_id, record.message_id);
+        assert_eq!(loaded.channel_id, record.channel_id);
+        assert_eq!(loaded.author_id, record.author_id);
+        assert_eq!(loaded.name_line_id, None);
+        assert_eq!(loaded.webhook_id, record.webhook_id);
+        assert_eq!(loaded.expires_at, record.expires_at);
+    }
+}
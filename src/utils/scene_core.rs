@@ -1,7 +1,17 @@
 use serde::{Deserialize, Serialize};
 use serenity::{builder::CreateAttachment, client::Context};
 
-use std::io::BufWriter;
+use crate::utils::apng::{self, Apng};
+use crate::utils::emoji_cache::{CachedImage, EmojiCacheKey};
+use crate::utils::emoji_tokenizer::{only_emojis, tokenize, EmojiToken};
+use crate::utils::gif_encoder::{self, GifEncodeOptions};
+use crate::utils::lottie::LottieAnimation;
+use crate::utils::twemoji::{render_twemoji, TWEMOJI_SIZE};
+use crate::utils::upload_limit::{fit_to_upload_limit, SizeCompromise};
+use crate::GlobalEmojiCache;
+use twemoji_assets::svg::SvgTwemojiAsset;
+
+use std::io::{BufWriter, Cursor};
 use std::num::NonZeroU32;
 
 use fast_image_resize as fr;
@@ -14,9 +24,13 @@ use regex::Regex;
 pub trait EmojiFilter {
     fn emoji_format_filter(&self) -> Result<(bool, String), ()>;
     fn double_emoji_format_filter(&self) -> Result<(bool, String, String), ()>;
+    fn multi_emoji_format_filter(&self) -> Result<(bool, Vec<String>), ()>;
+    fn sticker_format_filter(&self) -> Result<(bool, String), ()>;
+    fn unicode_emoji_format_filter(&self) -> Result<&'static SvgTwemojiAsset, ()>;
+    fn is_spoiler_emoji(&self) -> bool;
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub enum ImageSize {
     HyperTechniqueOfLisaSuFinger,           //16x16
     Small,                                  //64x64
@@ -24,10 +38,56 @@ pub enum ImageSize {
     Large,                                  //256x256
     HyperSuperUltraSexFeaturedFuckingLarge, //300x300
     Auto,
+    Custom(u32, u32),      //가로x세로. 한쪽이 0이면 원본 비율대로 채움
+    Scale(u32),            //원본 대비 배율(%)
+    AutoBounded(u32, u32), //서버에서 정한 최소/최대 범위로 판단하는 Auto
+}
+
+//직접 정하는 크기는 가로 세로 이 값까지만 받음
+pub const MAX_CUSTOM_SIZE: u32 = 1024;
+pub const MIN_SCALE_PERCENT: u32 = 10;
+pub const MAX_SCALE_PERCENT: u32 = 800;
+
+//Auto가 이 범위 밖의 이모지만 키우거나 줄임 (긴 변 기준)
+pub const DEFAULT_AUTO_SIZE_MIN: u32 = 128;
+pub const DEFAULT_AUTO_SIZE_MAX: u32 = 512;
+//합치는 이모지 칸은 이것보다 작게 줄이지 않음
+const MIN_MERGED_CELL_SIZE: u32 = 32;
+
+//Auto가 크기를 정할 때 보는 메시지 상황
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AutoContext {
+    //이모지 하나만 보낸 메시지. 작은 이모지는 잘 보이게 최소 크기까지 키움
+    SingleEmoji,
+    //스티커 하나만 보낸 메시지. 이미 크게 보이니까 키우지는 않고 너무 큰 것만 줄임
+    Sticker,
+    //여러 이모지를 한 장으로 합치는 메시지. 합친 결과가 너무 커지지 않게 칸 하나를 작게 잡음
+    Merged(usize),
 }
 
 impl ImageSize {
     pub fn string_to_value(input_str: &str) -> Self {
+        let custom = input_str
+            .strip_prefix("Custom_")
+            .and_then(|size| size.split_once('x'))
+            .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+        if let Some((width, height)) = custom {
+            return Self::Custom(width, height);
+        }
+        let bounds = input_str
+            .strip_prefix("AutoBounded_")
+            .and_then(|bounds| bounds.split_once('x'))
+            .and_then(|(min, max)| Some((min.parse().ok()?, max.parse().ok()?)));
+        if let Some((min, max)) = bounds {
+            return Self::AutoBounded(min, max);
+        }
+        if let Some(percent) = input_str
+            .strip_prefix("Scale_")
+            .and_then(|percent| percent.parse().ok())
+        {
+            return Self::Scale(percent);
+        }
+
         match input_str {
             "HyperTechniqueOfLisaSuFinger" => Self::HyperTechniqueOfLisaSuFinger,
             "Small" => Self::Small,
@@ -50,86 +110,756 @@ impl ImageSize {
                 "HyperSuperUltraSexFeaturedFuckingLarge"
             }
             Self::Auto => "Auto",
+            Self::Custom(width, height) => return format!("Custom_{}x{}", width, height),
+            Self::Scale(percent) => return format!("Scale_{}", percent),
+            Self::AutoBounded(min, max) => return format!("AutoBounded_{}x{}", min, max),
+        };
+        st.to_string()
+    }
+
+    //서버에서 정한 Auto 범위를 메시지 상황에 맞게 바꿈
+    pub fn auto_for(context: AutoContext, min: u32, max: u32) -> Self {
+        match context {
+            AutoContext::SingleEmoji => Self::AutoBounded(min, max),
+            AutoContext::Sticker => Self::AutoBounded(0, max),
+            AutoContext::Merged(count) => {
+                //격자로 놓으면 한 줄에 들어가는 칸 수
+                let columns = (1..=count.max(1) as u32)
+                    .find(|c| (c * c) as usize >= count)
+                    .unwrap_or(1);
+                Self::AutoBounded(
+                    (min / columns).max(MIN_MERGED_CELL_SIZE),
+                    (max / columns).max(MIN_MERGED_CELL_SIZE),
+                )
+            }
+        }
+    }
+
+    //원본 크기를 보고 결과 크기랑 원본에서 쓸 영역을 정함. 변환할 필요가 없으면 None
+    fn resize_plan(
+        &self,
+        src_width: u32,
+        src_height: u32,
+        fit: FitMode,
+    ) -> Result<Option<ResizePlan>, ImageTransferError> {
+        if src_width == 0 || src_height == 0 {
+            return Err(ImageTransferError::InvalidImageSize);
+        }
+        let full = (0, 0, src_width, src_height);
+
+        let (box_width, box_height) = match *self {
+            Self::HyperTechniqueOfLisaSuFinger => (16, 16),
+            Self::Small => (64, 64),
+            Self::Medium => (128, 128),
+            Self::Large => (256, 256),
+            Self::HyperSuperUltraSexFeaturedFuckingLarge => (300, 300),
+            Self::Auto => {
+                return Self::AutoBounded(DEFAULT_AUTO_SIZE_MIN, DEFAULT_AUTO_SIZE_MAX)
+                    .resize_plan(src_width, src_height, fit)
+            }
+            //작은 이모지는 최소 크기까지 키우고, 너무 큰 건 최대 크기로 줄이고, 나머지는 그대로 둠
+            //ResizeFilter::Auto면 도트 이모지인지, 키우는지 줄이는지를 보고 필터도 같이 고름
+            Self::AutoBounded(min, max) => {
+                let longest = src_width.max(src_height);
+                let target = if longest < min {
+                    min
+                } else if longest > max {
+                    max
+                } else {
+                    return Ok(None);
+                };
+                return Self::Custom(target, target).resize_plan(
+                    src_width,
+                    src_height,
+                    FitMode::Contain,
+                );
+            }
+            Self::Scale(percent) => {
+                let percent = percent.clamp(MIN_SCALE_PERCENT, MAX_SCALE_PERCENT);
+                return ResizePlan::new(
+                    full,
+                    proportional(src_width, percent, 100),
+                    proportional(src_height, percent, 100),
+                )
+                .map(Some);
+            }
+            //한쪽만 정했으면 상자에 맞출 필요 없이 나머지를 비율대로 채움
+            Self::Custom(0, 0) => return Err(ImageTransferError::InvalidImageSize),
+            Self::Custom(width, 0) => {
+                return ResizePlan::new(full, width, proportional(src_height, width, src_width))
+                    .map(Some)
+            }
+            Self::Custom(0, height) => {
+                return ResizePlan::new(full, proportional(src_width, height, src_height), height)
+                    .map(Some)
+            }
+            Self::Custom(width, height) => (width, height),
+        };
+
+        //원본이 상자보다 가로로 긴지
+        let wider = src_width as u64 * box_height as u64 > src_height as u64 * box_width as u64;
+        match fit {
+            FitMode::Stretch => ResizePlan::new(full, box_width, box_height),
+            //상자 안에 다 들어가게 줄이고, 빈 곳은 채우지 않음
+            FitMode::Contain if wider => ResizePlan::new(
+                full,
+                box_width,
+                proportional(src_height, box_width, src_width),
+            ),
+            FitMode::Contain => ResizePlan::new(
+                full,
+                proportional(src_width, box_height, src_height),
+                box_height,
+            ),
+            //상자를 꽉 채우고 넘치는 부분은 가운데 기준으로 잘라냄
+            FitMode::Cover if wider => {
+                let crop_width = proportional(src_height, box_width, box_height).min(src_width);
+                ResizePlan::new(
+                    ((src_width - crop_width) / 2, 0, crop_width, src_height),
+                    box_width,
+                    box_height,
+                )
+            }
+            FitMode::Cover => {
+                let crop_height = proportional(src_width, box_height, box_width).min(src_height);
+                ResizePlan::new(
+                    (0, (src_height - crop_height) / 2, src_width, crop_height),
+                    box_width,
+                    box_height,
+                )
+            }
+        }
+        .map(Some)
+    }
+}
+
+//가로 세로 비율이 다른 원본을 상자 크기에 맞추는 방법
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FitMode {
+    Contain,
+    Cover,
+    Stretch,
+}
+
+impl FitMode {
+    pub fn string_to_value(input_str: &str) -> Self {
+        match input_str {
+            "Cover" => Self::Cover,
+            "Stretch" => Self::Stretch,
+            _ => Self::Contain,
+        }
+    }
+
+    pub fn value_to_string(input_value: &FitMode) -> String {
+        let st = match input_value {
+            Self::Contain => "Contain",
+            Self::Cover => "Cover",
+            Self::Stretch => "Stretch",
+        };
+        st.to_string()
+    }
+}
+
+//원본에서 잘라 쓸 영역(left, top, width, height)과 결과 크기
+#[derive(Debug, Clone, Copy)]
+struct ResizePlan {
+    crop: (u32, u32, u32, u32),
+    dst_width: NonZeroU32,
+    dst_height: NonZeroU32,
+}
+
+impl ResizePlan {
+    fn new(
+        crop: (u32, u32, u32, u32),
+        dst_width: u32,
+        dst_height: u32,
+    ) -> Result<Self, ImageTransferError> {
+        if dst_width > MAX_CUSTOM_SIZE || dst_height > MAX_CUSTOM_SIZE {
+            return Err(ImageTransferError::TargetSizeTooLarge);
+        }
+
+        Ok(Self {
+            crop,
+            dst_width: NonZeroU32::new(dst_width).ok_or(ImageTransferError::InvalidImageSize)?,
+            dst_height: NonZeroU32::new(dst_height).ok_or(ImageTransferError::InvalidImageSize)?,
+        })
+    }
+
+    fn upscales(&self) -> bool {
+        let (_, _, crop_width, crop_height) = self.crop;
+        self.dst_width.get() > crop_width || self.dst_height.get() > crop_height
+    }
+}
+
+//value * numerator / denominator를 반올림. 0이 되지는 않게 함
+fn proportional(value: u32, numerator: u32, denominator: u32) -> u32 {
+    let denominator = denominator.max(1) as u64;
+    ((value as u64 * numerator as u64 + denominator / 2) / denominator).max(1) as u32
+}
+
+//리사이즈할 때 쓰는 필터. Auto는 도트 이모지면 Nearest 정수배 확대,
+//아니면 키울 때는 CatmullRom, 줄일 때는 Lanczos3
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResizeFilter {
+    Auto,
+    Nearest,
+    Bilinear,
+    CatmullRom,
+    Mitchell,
+    Lanczos3,
+}
+
+impl ResizeFilter {
+    pub fn string_to_value(input_str: &str) -> Self {
+        match input_str {
+            "Nearest" => Self::Nearest,
+            "Bilinear" => Self::Bilinear,
+            "CatmullRom" => Self::CatmullRom,
+            "Mitchell" => Self::Mitchell,
+            "Lanczos3" => Self::Lanczos3,
+            _ => Self::Auto,
+        }
+    }
+
+    pub fn value_to_string(input_value: &ResizeFilter) -> String {
+        let st = match input_value {
+            Self::Auto => "Auto",
+            Self::Nearest => "Nearest",
+            Self::Bilinear => "Bilinear",
+            Self::CatmullRom => "CatmullRom",
+            Self::Mitchell => "Mitchell",
+            Self::Lanczos3 => "Lanczos3",
         };
         st.to_string()
     }
+
+    fn resize_alg(&self, plan: &ResizePlan) -> fr::ResizeAlg {
+        match self {
+            Self::Auto if plan.upscales() => fr::ResizeAlg::Convolution(fr::FilterType::CatmullRom),
+            Self::Auto => fr::ResizeAlg::Convolution(fr::FilterType::Lanczos3),
+            Self::Nearest => fr::ResizeAlg::Nearest,
+            Self::Bilinear => fr::ResizeAlg::Convolution(fr::FilterType::Bilinear),
+            Self::CatmullRom => fr::ResizeAlg::Convolution(fr::FilterType::CatmullRom),
+            Self::Mitchell => fr::ResizeAlg::Convolution(fr::FilterType::Mitchell),
+            Self::Lanczos3 => fr::ResizeAlg::Convolution(fr::FilterType::Lanczos3),
+        }
+    }
+}
+
+//움직이는 APNG를 변환할 때 결과로 내보낼 형식. 디스코드는 APNG를 안 움직여서 기본은 Gif
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnimatedFormat {
+    Gif,
+    Apng,
+    WebP,
 }
 
-//img_url은 항상 PNG파일임
+impl AnimatedFormat {
+    pub fn string_to_value(input_str: &str) -> Self {
+        match input_str {
+            "Apng" => Self::Apng,
+            "WebP" => Self::WebP,
+            _ => Self::Gif,
+        }
+    }
+
+    pub fn value_to_string(input_value: &AnimatedFormat) -> String {
+        let st = match input_value {
+            Self::Gif => "Gif",
+            Self::Apng => "Apng",
+            Self::WebP => "WebP",
+        };
+        st.to_string()
+    }
+
+    fn file_name(&self) -> String {
+        match self {
+            Self::Gif => "resized.gif",
+            Self::Apng => "resized.png",
+            Self::WebP => "resized.webp",
+        }
+        .to_string()
+    }
+}
+
+//img_url이 gif면 프레임별로 크기를 바꾸고, 아니면 png로 바꿔서 보냄
+//APNG는 output 형식으로 내보냄. 결과가 upload_limit를 넘으면 화질을 낮추고 뭘 포기했는지 같이 돌려줌
 pub async fn get_resized_image(
     ctx: &Context,
     img_url: &str,
     img_size: &ImageSize,
-) -> CreateAttachment {
-    match img_size {
-        //여기부터는 작동 안함. dead code인데.. 나중에 고치기
-        ImageSize::Auto => CreateAttachment::url(ctx.http.clone(), img_url)
-            .await
-            .unwrap(),
-        ImageSize::HyperTechniqueOfLisaSuFinger => {
-            resize_png(
-                img_url,
-                NonZeroU32::new(16).unwrap(),
-                NonZeroU32::new(16).unwrap(),
-            )
-            .await
+    filter: &ResizeFilter,
+    fit: &FitMode,
+    output: &AnimatedFormat,
+    upload_limit: u64,
+) -> Result<(CreateAttachment, Option<SizeCompromise>), ImageTransferError> {
+    let (owned_url, owned_size, owned_filter, owned_fit, owned_output) = (
+        img_url.to_string(),
+        img_size.clone(),
+        *filter,
+        *fit,
+        *output,
+    );
+    let convert =
+        move || convert_image(owned_url, owned_size, owned_filter, owned_fit, owned_output);
+
+    //커스텀 이모지 URL이면 캐시를 거치고, 아니면 매번 변환함
+    let converted = match emoji_cache_key(img_url, img_size, filter, fit, output) {
+        Some(key) => {
+            let emoji_cache = {
+                let data_read = ctx.data.read().await;
+                data_read
+                    .get::<GlobalEmojiCache>()
+                    .expect("poisened")
+                    .clone()
+            };
+            emoji_cache.get_or_convert(key, convert).await?
         }
-        ImageSize::Small => {
-            resize_png(
-                img_url,
-                NonZeroU32::new(64).unwrap(),
-                NonZeroU32::new(64).unwrap(),
-            )
+        None => convert().await?,
+    };
+
+    //캐시에는 원래 화질로 넣어두고, 서버마다 제한이 다르니 보내기 직전에 맞춤
+    let (fitted, compromise) =
+        tokio::task::spawn_blocking(move || fit_to_upload_limit(converted, upload_limit))
             .await
+            .map_err(|_| ImageTransferError::Mollu)??;
+
+    Ok((
+        CreateAttachment::bytes(fitted.bytes, fitted.filename),
+        compromise,
+    ))
+}
+
+//유니코드 이모지는 Twemoji SVG를 결과 크기에 바로 그려서 png로 보냄
+pub async fn get_twemoji_image(
+    emoji: &'static SvgTwemojiAsset,
+    img_size: &ImageSize,
+    fit: &FitMode,
+    upload_limit: u64,
+) -> Result<(CreateAttachment, Option<SizeCompromise>), ImageTransferError> {
+    let (owned_size, owned_fit) = (img_size.clone(), *fit);
+    let (fitted, compromise) = tokio::task::spawn_blocking(move || {
+        let rendered = render_unicode_emoji(emoji, owned_size, owned_fit)?;
+        fit_to_upload_limit(
+            CachedImage {
+                bytes: rendered,
+                filename: "resized.png".to_string(),
+            },
+            upload_limit,
+        )
+    })
+    .await
+    .map_err(|_| ImageTransferError::Mollu)??;
+
+    Ok((
+        CreateAttachment::bytes(fitted.bytes, fitted.filename),
+        compromise,
+    ))
+}
+
+fn render_unicode_emoji(
+    emoji: &SvgTwemojiAsset,
+    img_size: ImageSize,
+    fit: FitMode,
+) -> Result<Vec<u8>, ImageTransferError> {
+    //Auto여도 SVG를 그대로 보낼 순 없으니 원래 크기로 그림
+    let plan = match img_size.resize_plan(TWEMOJI_SIZE, TWEMOJI_SIZE, fit)? {
+        Some(plan) => plan,
+        None => ResizePlan::new(
+            (0, 0, TWEMOJI_SIZE, TWEMOJI_SIZE),
+            TWEMOJI_SIZE,
+            TWEMOJI_SIZE,
+        )?,
+    };
+    let (crop_left, crop_top, crop_width, crop_height) = plan.crop;
+    let (dst_width, dst_height) = (plan.dst_width.get(), plan.dst_height.get());
+    let transform = tiny_skia::Transform::from_translate(-(crop_left as f32), -(crop_top as f32))
+        .post_scale(
+            dst_width as f32 / crop_width as f32,
+            dst_height as f32 / crop_height as f32,
+        );
+
+    let frame = render_twemoji(emoji, transform, dst_width, dst_height)
+        .ok_or(ImageTransferError::DecodingError)?;
+
+    let mut result_buf = Vec::new();
+    PngEncoder::new(&mut result_buf)
+        .write_image(frame.as_raw(), dst_width, dst_height, ColorType::Rgba8)
+        .map_err(|_| ImageTransferError::PngEncodingError)?;
+    Ok(result_buf)
+}
+
+fn emoji_cache_key(
+    img_url: &str,
+    img_size: &ImageSize,
+    filter: &ResizeFilter,
+    fit: &FitMode,
+    output: &AnimatedFormat,
+) -> Option<EmojiCacheKey> {
+    //스티커 ID도 이모지 ID랑 겹치지 않으니까 같은 캐시를 씀
+    let url_regex: Regex = Regex::new(r"/(?:emojis|stickers)/(\d+)\.(\w+)").unwrap();
+    let captures = url_regex.captures(img_url)?;
+
+    Some(EmojiCacheKey {
+        emoji_id: captures[1].parse().ok()?,
+        animated: &captures[2] == "gif",
+        size: img_size.clone(),
+        filter: *filter,
+        fit: *fit,
+        output: *output,
+    })
+}
+
+async fn convert_image(
+    img_url: String,
+    img_size: ImageSize,
+    filter: ResizeFilter,
+    fit: FitMode,
+    output: AnimatedFormat,
+) -> Result<CachedImage, ImageTransferError> {
+    let img_bytes = fetch_image_bytes(&img_url).await?;
+
+    //로티 스티커는 이미지가 아니라 JSON이라 직접 그려서 gif로 만듦
+    if is_lottie(&img_bytes) {
+        let rendered =
+            tokio::task::spawn_blocking(move || render_lottie(&img_bytes, img_size, fit))
+                .await
+                .map_err(|_| ImageTransferError::Mollu)??;
+        return Ok(CachedImage {
+            bytes: rendered,
+            filename: "resized.gif".to_string(),
+        });
+    }
+
+    //gif도 프레임 말고 전체 캔버스 크기를 기준으로 맞춤
+    let (src_width, src_height) = image::io::Reader::new(Cursor::new(&img_bytes))
+        .with_guessed_format()
+        .map_err(|_| ImageTransferError::DecodingError)?
+        .into_dimensions()
+        .map_err(|_| ImageTransferError::DecodingError)?;
+
+    //Auto는 원본 그대로 보냄. 디스코드에서 안 움직이는 APNG는 크기 그대로 형식만 바꿈
+    let apng = is_apng(&img_bytes);
+    let plan = match img_size.resize_plan(src_width, src_height, fit)? {
+        Some(plan) => plan,
+        None if apng && output != AnimatedFormat::Apng => {
+            ResizePlan::new((0, 0, src_width, src_height), src_width, src_height)?
         }
-        ImageSize::Medium => {
-            resize_png(
-                img_url,
-                NonZeroU32::new(128).unwrap(),
-                NonZeroU32::new(128).unwrap(),
-            )
-            .await
+        None => {
+            let filename = img_url
+                .split('?')
+                .next()
+                .and_then(|url| url.rsplit('/').next())
+                .unwrap_or("emoji.png")
+                .to_string();
+            return Ok(CachedImage {
+                bytes: img_bytes,
+                filename,
+            });
         }
-        //여기까지 dead code
-        ImageSize::Large => {
-            resize_png(
-                img_url,
-                NonZeroU32::new(256).unwrap(),
-                NonZeroU32::new(256).unwrap(),
-            )
-            .await
+    };
+
+    match image::guess_format(&img_bytes) {
+        Ok(ImageFormat::Gif) => {
+            let resized = tokio::task::spawn_blocking(move || resize_gif(&img_bytes, plan, filter))
+                .await
+                .map_err(|_| ImageTransferError::Mollu)??;
+            Ok(CachedImage {
+                bytes: resized,
+                filename: "resized.gif".to_string(),
+            })
         }
-        ImageSize::HyperSuperUltraSexFeaturedFuckingLarge => {
-            resize_png(
-                img_url,
-                NonZeroU32::new(300).unwrap(),
-                NonZeroU32::new(300).unwrap(),
-            )
+        Ok(ImageFormat::Png) if apng => {
+            let resized =
+                tokio::task::spawn_blocking(move || resize_apng(&img_bytes, plan, filter, output))
+                    .await
+                    .map_err(|_| ImageTransferError::Mollu)??;
+            Ok(CachedImage {
+                bytes: resized,
+                filename: output.file_name(),
+            })
+        }
+        _ => {
+            //정적 이미지도 1024px, 800%까지 키울 수 있어서 오래 걸림
+            let resized = tokio::task::spawn_blocking(move || resize_png(&img_bytes, plan, filter))
+                .await
+                .map_err(|_| ImageTransferError::Mollu)??;
+            Ok(CachedImage {
+                bytes: resized,
+                filename: "resized.png".to_string(),
+            })
+        }
+    }
+}
+
+fn is_lottie(img_bytes: &[u8]) -> bool {
+    img_bytes
+        .iter()
+        .find(|byte| !byte.is_ascii_whitespace())
+        .is_some_and(|byte| *byte == b'{')
+}
+
+fn is_apng(img_bytes: &[u8]) -> bool {
+    use image::codecs::png::PngDecoder;
+
+    matches!(image::guess_format(img_bytes), Ok(ImageFormat::Png))
+        && PngDecoder::new(Cursor::new(img_bytes)).is_ok_and(|decoder| decoder.is_apng())
+}
+
+//파일 앞부분만 보고 움짤인지 판단함. APNG의 acTL과 WebP의 VP8X는 둘 다 이미지 데이터보다 앞에 있음
+pub fn is_animated_image(head: &[u8]) -> bool {
+    match image::guess_format(head) {
+        Ok(ImageFormat::Png) => is_apng(head),
+        //VP8X 플래그의 애니메이션 비트
+        Ok(ImageFormat::WebP) => {
+            head.get(12..16) == Some(b"VP8X") && head.get(20).is_some_and(|flags| flags & 0x02 != 0)
+        }
+        _ => false,
+    }
+}
+
+//로티는 프레임레이트가 60인 경우가 많은데, gif로는 너무 무거우니까 이 간격(1/100초)으로만 뽑음
+const LOTTIE_FRAME_DELAY: u32 = 4;
+
+fn render_lottie(
+    json: &[u8],
+    img_size: ImageSize,
+    fit: FitMode,
+) -> Result<Vec<u8>, ImageTransferError> {
+    let animation = LottieAnimation::parse(json).ok_or(ImageTransferError::DecodingLottieError)?;
+    let (width, height) = (animation.width, animation.height);
+
+    //Auto여도 JSON을 그대로 보낼 순 없으니 원래 크기로 그림
+    let plan = match img_size.resize_plan(width, height, fit)? {
+        Some(plan) => plan,
+        None => ResizePlan::new((0, 0, width, height), width, height)?,
+    };
+    let (crop_left, crop_top, crop_width, crop_height) = plan.crop;
+    let (dst_width, dst_height) = (plan.dst_width.get(), plan.dst_height.get());
+    let transform = tiny_skia::Transform::from_translate(-(crop_left as f32), -(crop_top as f32))
+        .post_scale(
+            dst_width as f32 / crop_width as f32,
+            dst_height as f32 / crop_height as f32,
+        );
+
+    let duration = animation.duration();
+    let frame_count = ((duration * 100.0 / LOTTIE_FRAME_DELAY as f32).round() as usize)
+        .clamp(1, MAX_MERGED_FRAMES);
+    let delay = ((duration * 100.0 / frame_count as f32).round() as u32).max(2);
+
+    let frames = (0..frame_count)
+        .map(|index| {
+            let seconds = duration * index as f32 / frame_count as f32;
+            let mut rgba = animation
+                .render(seconds, transform, dst_width, dst_height)
+                .ok_or(ImageTransferError::DecodingLottieError)?;
+            threshold_alpha(&mut rgba);
+            let frame = image::RgbaImage::from_raw(dst_width, dst_height, rgba)
+                .ok_or(ImageTransferError::DecodingLottieError)?;
+            Ok((frame, delay))
+        })
+        .collect::<Result<EmojiFrames, ImageTransferError>>()?;
+
+    encode_gif_frames(frames)
+}
+
+//APNG를 프레임별로 풀어서 크기를 바꾸고 output 형식으로 다시 묶음
+fn resize_apng(
+    img_bytes: &[u8],
+    plan: ResizePlan,
+    filter: ResizeFilter,
+    output: AnimatedFormat,
+) -> Result<Vec<u8>, ImageTransferError> {
+    let apng = Apng::decode(img_bytes)?;
+    let (frames, pixel_art) = resize_frames(apng.compose(), plan, filter)?;
+
+    encode_animated_frames(frames, apng.num_plays, output, !pixel_art)
+}
+
+//캔버스 전체 크기의 프레임들을 잘라내고 크기를 바꿈. 도트 이모지로 보고 Nearest를 썼는지도 같이 돌려줌
+fn resize_frames(
+    frames: EmojiFrames,
+    plan: ResizePlan,
+    filter: ResizeFilter,
+) -> Result<(EmojiFrames, bool), ImageTransferError> {
+    let (_, _, crop_width, crop_height) = plan.crop;
+    let width = NonZeroU32::new(crop_width).ok_or(ImageTransferError::InvalidImageSize)?;
+    let height = NonZeroU32::new(crop_height).ok_or(ImageTransferError::InvalidImageSize)?;
+
+    let mut resize_alg = None;
+    let frames = frames
+        .into_iter()
+        .map(|(frame, delay)| {
+            if crop_rect_outside(frame.dimensions(), plan.crop) {
+                return Err(ImageTransferError::InvalidImageSize);
+            }
+            let rgba = crop_rgba(frame.as_raw(), frame.width(), plan.crop);
+            //Auto는 첫 프레임을 보고 도트인지 정함
+            let resize_alg = *resize_alg.get_or_insert_with(|| {
+                if filter == ResizeFilter::Auto && is_pixel_art(&rgba) {
+                    fr::ResizeAlg::Nearest
+                } else {
+                    filter.resize_alg(&plan)
+                }
+            });
+
+            let resized = resize_rgba(
+                rgba,
+                width,
+                height,
+                plan.dst_width,
+                plan.dst_height,
+                resize_alg,
+            )?;
+            let resized =
+                image::RgbaImage::from_raw(plan.dst_width.get(), plan.dst_height.get(), resized)
+                    .ok_or(ImageTransferError::ResizeError)?;
+            Ok((resized, delay))
+        })
+        .collect::<Result<EmojiFrames, ImageTransferError>>()?;
+
+    let pixel_art = matches!(
+        resize_alg.unwrap_or(filter.resize_alg(&plan)),
+        fr::ResizeAlg::Nearest
+    );
+    Ok((frames, pixel_art))
+}
+
+fn crop_rect_outside((width, height): (u32, u32), crop: (u32, u32, u32, u32)) -> bool {
+    let (left, top, crop_width, crop_height) = crop;
+    left + crop_width > width || top + crop_height > height
+}
+
+fn encode_animated_frames(
+    frames: EmojiFrames,
+    num_plays: u32,
+    output: AnimatedFormat,
+    dithering: bool,
+) -> Result<Vec<u8>, ImageTransferError> {
+    match output {
+        AnimatedFormat::Gif => gif_encoder::encode(
+            &frames,
+            &GifEncodeOptions {
+                dithering,
+                ..GifEncodeOptions::default()
+            },
+        ),
+        AnimatedFormat::Apng => apng::encode(&frames, num_plays),
+        AnimatedFormat::WebP => encode_webp_frames(&frames, num_plays),
+    }
+}
+
+//gif는 1비트 투명도밖에 없으니까 가장자리 반투명 픽셀은 반 잘라서 처리
+pub fn threshold_alpha(rgba: &mut [u8]) {
+    for pixel in rgba.chunks_exact_mut(4) {
+        pixel[3] = if pixel[3] < 128 { 0 } else { 255 };
+    }
+}
+
+//디스코드 CDN에서 이미지를 가져옴. 지워진 이모지는 404가 오니까 따로 구분함
+pub async fn fetch_image_bytes(img_url: &str) -> Result<Vec<u8>, ImageTransferError> {
+    let response = reqwest::get(img_url)
+        .await
+        .map_err(|_| ImageTransferError::GetRequestFailed)?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(ImageTransferError::EmojiNotFound);
+    }
+
+    Ok(response
+        .error_for_status()
+        .map_err(|_| ImageTransferError::GetRequestFailed)?
+        .bytes()
+        .await
+        .map_err(|_| ImageTransferError::GetRequestFailed)?
+        .to_vec())
+}
+
+//큰 파일을 다 받기 전에 형식만 확인할 수 있도록 앞부분 len 바이트만 받아옴
+pub async fn fetch_image_head(img_url: &str, len: usize) -> Result<Vec<u8>, ImageTransferError> {
+    let mut response = reqwest::Client::new()
+        .get(img_url)
+        .header(
+            reqwest::header::RANGE,
+            format!("bytes=0-{}", len.saturating_sub(1)),
+        )
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|_| ImageTransferError::GetRequestFailed)?;
+
+    //Range를 무시하고 전체를 보내는 서버도 있으니까 len만큼 모이면 끊음
+    let mut head = Vec::with_capacity(len);
+    while head.len() < len {
+        match response
+            .chunk()
             .await
+            .map_err(|_| ImageTransferError::GetRequestFailed)?
+        {
+            Some(chunk) => head.extend_from_slice(&chunk),
+            None => break,
         }
     }
+    head.truncate(len);
+    Ok(head)
 }
 
-pub enum WebPTransferError {
+//이미지 가져오기 - 디코딩 - 크기 조절 - 인코딩 중 어디서 실패했는지 나타냄
+#[derive(Debug, Clone)]
+pub enum ImageTransferError {
     GetRequestFailed,
+    EmojiNotFound,
+    DecodingError,
     DecodingWebPError,
+    DecodingLottieError,
+    CaptionRenderError,
+    InvalidImageSize,
+    TargetSizeTooLarge,
+    ResizeError,
+    PngEncodingError,
     GifEncodingError,
+    ApngEncodingError,
+    WebPEncodingError,
     SetRepeatError,
     SizeLimitExceeded,
+    AnimationTooLarge,
+    UploadLimitExceeded,
+    TooManyEmojis,
     AutoPngNotNeeded,
     Mollu,
 }
 
-impl WebPTransferError {
+impl ImageTransferError {
     pub fn get_error_message(&self) -> String {
         match self {
             Self::GetRequestFailed => "디스코드 서버로부터 이미지를 가져오는 데 실패했습니다.",
+            Self::EmojiNotFound => "이모지를 찾을 수 없습니다. 삭제된 이모지일 수 있습니다.",
+            Self::DecodingError => {
+                "이미지를 디코딩하는데 실패했습니다. 손상된 이미지일 수 있습니다."
+            }
             Self::DecodingWebPError => "WebP 이미지를 디코딩하는데 실패했습니다.",
-            Self::GifEncodingError => "WebP 이미지를 Gif 이미지로 인코딩하는데 실패했습니다.",
+            Self::DecodingLottieError => "움직이는 스티커를 그리는데 실패했습니다.",
+            Self::CaptionRenderError => "문구를 그리는데 실패했습니다.",
+            Self::InvalidImageSize => "이미지의 가로 또는 세로 크기가 올바르지 않습니다.",
+            Self::TargetSizeTooLarge => {
+                "결과 이미지가 너무 큽니다. 가로 세로 1024px 이하로만 만들 수 있습니다."
+            }
+            Self::ResizeError => "이미지 크기를 조절하는데 실패했습니다.",
+            Self::PngEncodingError => "이미지를 Png 이미지로 인코딩하는데 실패했습니다.",
+            Self::GifEncodingError => "이미지를 Gif 이미지로 인코딩하는데 실패했습니다.",
+            Self::ApngEncodingError => "이미지를 APNG 이미지로 인코딩하는데 실패했습니다.",
+            Self::WebPEncodingError => "이미지를 WebP 이미지로 인코딩하는데 실패했습니다.",
             Self::SetRepeatError => "Gif 반복 설정을 하는데 실패했습니다.",
             Self::SizeLimitExceeded => {
-                "변환하려는 WebP의 크기가 너무 큽니다. 2MB 이하의 WebP 이미지만 지원합니다."
+                "변환하려는 움짤의 크기가 너무 큽니다. 2MB 이하의 WebP, APNG 이미지만 지원합니다."
+            }
+            Self::AnimationTooLarge => {
+                "움짤의 가로 세로 크기나 프레임 수가 너무 많아서 변환할 수 없습니다."
+            }
+            Self::UploadLimitExceeded => {
+                "결과 이미지가 서버 업로드 제한보다 커서, 화질과 크기를 줄여봐도 보낼 수 없었습니다."
             }
+            Self::TooManyEmojis => "한 번에 합칠 수 있는 이모지 개수를 넘었습니다.",
             Self::AutoPngNotNeeded => "정적 webp는 지원하니까 굳이..?", //리팩토링할때 디코더 -
             //필터 - 인코더 순으로 다시
             Self::Mollu => "에러났는데 뭔지모르겠노",
@@ -138,142 +868,1230 @@ impl WebPTransferError {
     }
 }
 
+pub const WEBP_SIZE_LIMIT: u32 = 2 * 1024 * 1024;
+
+//움짤 webp를 무한 반복하는 gif로 바꿔줌
+//정적 webp는 디스코드에서 잘 보이니까 AutoPngNotNeeded로 돌려보냄
+pub fn transfer_webp_to_gif(img_bytes: &[u8]) -> Result<Vec<u8>, ImageTransferError> {
+    use image::{codecs::webp::WebPDecoder, AnimationDecoder};
+
+    let decoder = WebPDecoder::new(Cursor::new(img_bytes))
+        .map_err(|_| ImageTransferError::DecodingWebPError)?;
+    if !decoder.has_animation() {
+        return Err(ImageTransferError::AutoPngNotNeeded);
+    }
+    if img_bytes.len() > WEBP_SIZE_LIMIT as usize {
+        return Err(ImageTransferError::SizeLimitExceeded);
+    }
+
+    let frames = decoder
+        .into_frames()
+        .collect_frames()
+        .map_err(|_| ImageTransferError::DecodingWebPError)?;
+    let (width, height) = match frames.first() {
+        Some(frame) => frame.buffer().dimensions(),
+        None => return Err(ImageTransferError::DecodingWebPError),
+    };
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(ImageTransferError::SizeLimitExceeded);
+    }
+
+    //webp 프레임은 이미 캔버스 전체로 합성돼서 나오니까 그대로 넘김
+    let frames = frames
+        .into_iter()
+        .map(|frame| {
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            let delay_ms = numer.checked_div(denom).unwrap_or(0);
+            (frame.into_buffer(), (delay_ms + 5) / 10)
+        })
+        .collect();
+
+    encode_gif_frames(frames)
+}
+
+//움직이는 APNG를 무한 반복하는 gif로 바꿔줌
+//정적 png는 디스코드에서 잘 보이니까 AutoPngNotNeeded로 돌려보냄
+pub fn transfer_apng_to_gif(img_bytes: &[u8]) -> Result<Vec<u8>, ImageTransferError> {
+    let (frames, _) = decode_apng_for_transfer(img_bytes)?;
+    let frames = frames
+        .into_iter()
+        .map(|(mut frame, delay)| {
+            threshold_alpha(&mut frame);
+            (frame, delay)
+        })
+        .collect();
+    encode_gif_frames(frames)
+}
+
+//움직이는 APNG를 반복 횟수 그대로 움짤 webp로 바꿔줌
+pub fn transfer_apng_to_webp(img_bytes: &[u8]) -> Result<Vec<u8>, ImageTransferError> {
+    let (frames, num_plays) = decode_apng_for_transfer(img_bytes)?;
+    encode_webp_frames(&frames, num_plays)
+}
+
+//합성된 프레임과 반복 횟수를 돌려줌. 캔버스 크기와 프레임 수는 Apng::decode에서 먼저 확인함
+fn decode_apng_for_transfer(img_bytes: &[u8]) -> Result<(EmojiFrames, u32), ImageTransferError> {
+    if !is_apng(img_bytes) {
+        return Err(ImageTransferError::AutoPngNotNeeded);
+    }
+    if img_bytes.len() > WEBP_SIZE_LIMIT as usize {
+        return Err(ImageTransferError::SizeLimitExceeded);
+    }
+
+    let apng = Apng::decode(img_bytes)?;
+    Ok((apng.compose(), apng.num_plays))
+}
+
+//캔버스 전체 크기의 프레임들을 움짤 webp로 인코딩
+//image는 정적 무손실 webp(VP8L)만 만들 수 있어서, 프레임마다 만든 VP8L 청크를 ANMF로 감싸서 직접 묶음
+pub fn encode_webp_frames(
+    frames: &EmojiFrames,
+    loop_count: u32,
+) -> Result<Vec<u8>, ImageTransferError> {
+    use image::codecs::webp::WebPEncoder;
+
+    let (width, height) = match frames.first() {
+        Some((img, _)) => img.dimensions(),
+        None => return Err(ImageTransferError::WebPEncodingError),
+    };
+    //webp 캔버스는 가로 세로 24비트까지
+    if width > 1 << 24 || height > 1 << 24 {
+        return Err(ImageTransferError::SizeLimitExceeded);
+    }
+
+    let mut vp8x = Vec::with_capacity(10);
+    //애니메이션 + 알파
+    vp8x.extend_from_slice(&[0x12, 0, 0, 0]);
+    vp8x.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+    vp8x.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
+
+    let mut anim = Vec::with_capacity(6);
+    anim.extend_from_slice(&[0, 0, 0, 0]);
+    anim.extend_from_slice(&(loop_count.min(u16::MAX as u32) as u16).to_le_bytes());
+
+    let mut body = b"WEBP".to_vec();
+    push_riff_chunk(&mut body, b"VP8X", &vp8x);
+    push_riff_chunk(&mut body, b"ANIM", &anim);
+
+    for (img, delay) in frames {
+        let mut still = Vec::new();
+        WebPEncoder::new_lossless(&mut still)
+            .write_image(img.as_raw(), width, height, image::ColorType::Rgba8)
+            .map_err(|_| ImageTransferError::WebPEncodingError)?;
+        //RIFF(12바이트) 다음이 바로 VP8L 청크
+        let vp8l = still
+            .get(12..)
+            .filter(|chunk| chunk.starts_with(b"VP8L"))
+            .ok_or(ImageTransferError::WebPEncodingError)?;
+
+        let mut anmf = Vec::with_capacity(16 + vp8l.len());
+        //프레임 위치(0, 0)
+        anmf.extend_from_slice(&[0; 6]);
+        anmf.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+        anmf.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
+        anmf.extend_from_slice(&(delay * 10).min(0xFF_FFFF).to_le_bytes()[..3]);
+        //알파 블렌딩 안 함, 다음 프레임 전에 지우지 않음. 프레임이 캔버스 전체라 덮어쓰면 됨
+        anmf.push(0b10);
+        anmf.extend_from_slice(vp8l);
+        push_riff_chunk(&mut body, b"ANMF", &anmf);
+    }
+
+    let mut result_buf = b"RIFF".to_vec();
+    result_buf.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    result_buf.extend_from_slice(&body);
+    Ok(result_buf)
+}
+
+//RIFF 청크는 길이가 홀수면 0 한 바이트를 덧붙임
+fn push_riff_chunk(buf: &mut Vec<u8>, fourcc: &[u8; 4], payload: &[u8]) {
+    buf.extend_from_slice(fourcc);
+    buf.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    buf.extend_from_slice(payload);
+    if payload.len() % 2 == 1 {
+        buf.push(0);
+    }
+}
+
 pub fn emoji_format_filter(emoji_string: &str) -> Result<(bool, String), ()> {
-    let msg_content_vec: Vec<&str> = emoji_string.split(':').collect();
-    let content_regex: Regex = Regex::new(r"^<a?:.+?:\d+>$").unwrap();
-    match !content_regex.is_match(emoji_string) || msg_content_vec.len() != 3 {
-        false => {
-            let mut id = msg_content_vec[2].to_string();
-            id.pop();
-            let mut is_png = false;
-            let img_url = format!(
+    let tokens = tokenize(emoji_string);
+    match only_emojis(&tokens)
+        .as_ref()
+        .map(|only| only.emojis.as_slice())
+    {
+        Some([EmojiToken::Custom(emoji)]) => Ok((
+            !emoji.animated,
+            format!(
                 "https://cdn.discordapp.com/emojis/{}.{}",
-                id,
-                if emoji_string.contains("<a:") {
-                    "gif"
-                } else {
-                    is_png = true;
-                    "png"
-                }
-            );
-            Ok((is_png, img_url))
-        }
-        true => Err(()),
+                emoji.id,
+                if emoji.animated { "gif" } else { "png" }
+            ),
+        )),
+        _ => Err(()),
     }
 }
 
-use image::{codecs::png::PngEncoder, ColorType};
+//디스코드는 SPOILER_로 시작하는 첨부파일을 가려서 보여줌
+pub fn spoiler_attachment(mut attachment: CreateAttachment) -> CreateAttachment {
+    attachment.filename = format!("SPOILER_{}", attachment.filename);
+    attachment
+}
 
-async fn resize_png(
-    img_url: &str,
+use image::{codecs::png::PngEncoder, ColorType, ImageFormat};
+
+//RGBA 버퍼 하나를 알파 곱해서 리사이즈하고 다시 나눠서 돌려줌
+//png랑 gif 프레임 둘 다 이걸로 크기 조절함
+pub fn resize_rgba(
+    rgba: Vec<u8>,
+    width: NonZeroU32,
+    height: NonZeroU32,
     dst_width: NonZeroU32,
     dst_height: NonZeroU32,
-) -> CreateAttachment {
-    let img = image::load_from_memory(&reqwest::get(img_url).await.unwrap().bytes().await.unwrap())
-        .unwrap();
-    let width = NonZeroU32::new(img.width()).unwrap();
-    let height = NonZeroU32::new(img.height()).unwrap();
-
-    let mut src_image = fr::Image::from_vec_u8(
-        width,
-        height,
-        img.to_rgba8().into_raw(),
-        fr::PixelType::U8x4,
-    )
-    .unwrap();
+    resize_alg: fr::ResizeAlg,
+) -> Result<Vec<u8>, ImageTransferError> {
+    let mut src_image = fr::Image::from_vec_u8(width, height, rgba, fr::PixelType::U8x4)
+        .map_err(|_| ImageTransferError::ResizeError)?;
 
     let alpha_mul_div = fr::MulDiv::default();
     alpha_mul_div
         .multiply_alpha_inplace(&mut src_image.view_mut())
-        .unwrap();
+        .map_err(|_| ImageTransferError::ResizeError)?;
 
     let mut dst_image = fr::Image::new(dst_width, dst_height, src_image.pixel_type());
 
     let mut dst_view = dst_image.view_mut();
 
-    let mut resizer = fr::Resizer::new(fr::ResizeAlg::Convolution(fr::FilterType::Bilinear));
-    resizer.resize(&src_image.view(), &mut dst_view).unwrap();
+    let mut resizer = fr::Resizer::new(resize_alg);
+    resizer
+        .resize(&src_image.view(), &mut dst_view)
+        .map_err(|_| ImageTransferError::ResizeError)?;
+
+    alpha_mul_div
+        .divide_alpha_inplace(&mut dst_view)
+        .map_err(|_| ImageTransferError::ResizeError)?;
+
+    Ok(dst_image.into_vec())
+}
+
+//도트 이모지는 색이 적고 반투명한 가장자리가 거의 없음
+fn is_pixel_art(rgba: &[u8]) -> bool {
+    use std::collections::HashSet;
 
-    alpha_mul_div.divide_alpha_inplace(&mut dst_view).unwrap();
+    let pixel_count = rgba.len() / 4;
+    if pixel_count == 0 {
+        return false;
+    }
+
+    let mut colors = HashSet::new();
+    let mut soft_edges = 0;
+    for pixel in rgba.chunks_exact(4) {
+        match pixel[3] {
+            0 => {}
+            255 => {
+                colors.insert((pixel[0], pixel[1], pixel[2]));
+                if colors.len() > PIXEL_ART_MAX_COLORS {
+                    return false;
+                }
+            }
+            _ => soft_edges += 1,
+        }
+    }
+
+    !colors.is_empty() && soft_edges * 50 < pixel_count
+}
+
+const PIXEL_ART_MAX_COLORS: usize = 64;
+const PIXEL_ART_MAX_BLOCK: u32 = 16;
+
+//이미 k배로 키워서 올린 도트 이모지면 원래 도트 크기(k)를 찾음
+fn pixel_block_size(rgba: &[u8], width: u32, height: u32) -> u32 {
+    let pixel = |x: u32, y: u32| {
+        let idx = ((y * width + x) * 4) as usize;
+        &rgba[idx..idx + 4]
+    };
+
+    (2..=PIXEL_ART_MAX_BLOCK)
+        .rev()
+        .find(|k| {
+            width.is_multiple_of(*k)
+                && height.is_multiple_of(*k)
+                && (0..height)
+                    .all(|y| (0..width).all(|x| pixel(x, y) == pixel(x - x % k, y - y % k)))
+        })
+        .unwrap_or(1)
+}
+
+//도트를 원래 크기로 줄인 다음 들어가는 가장 큰 정수배로 키우고 가운데에 둠
+fn integer_scale(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    dst_width: u32,
+    dst_height: u32,
+) -> Option<Vec<u8>> {
+    let block = pixel_block_size(rgba, width, height);
+    let (native_width, native_height) = (width / block, height / block);
+    let factor = (dst_width / native_width).min(dst_height / native_height);
+    if factor == 0 {
+        return None;
+    }
+
+    let offset_x = (dst_width - native_width * factor) / 2;
+    let offset_y = (dst_height - native_height * factor) / 2;
+
+    let mut result = vec![0; (dst_width * dst_height * 4) as usize];
+    for y in 0..native_height * factor {
+        for x in 0..native_width * factor {
+            let src_idx = (((y / factor * block) * width + x / factor * block) * 4) as usize;
+            let dst_idx = (((y + offset_y) * dst_width + x + offset_x) * 4) as usize;
+            result[dst_idx..dst_idx + 4].copy_from_slice(&rgba[src_idx..src_idx + 4]);
+        }
+    }
+
+    Some(result)
+}
+
+//RGBA 버퍼에서 crop 영역만 떼어냄
+fn crop_rgba(rgba: &[u8], width: u32, crop: (u32, u32, u32, u32)) -> Vec<u8> {
+    let (left, top, crop_width, crop_height) = crop;
+    (top..top + crop_height)
+        .flat_map(|y| {
+            let start = ((y * width + left) * 4) as usize;
+            rgba[start..start + (crop_width * 4) as usize]
+                .iter()
+                .copied()
+        })
+        .collect()
+}
+
+fn resize_png(
+    img_bytes: &[u8],
+    plan: ResizePlan,
+    filter: ResizeFilter,
+) -> Result<Vec<u8>, ImageTransferError> {
+    let img = image::load_from_memory(img_bytes).map_err(|_| ImageTransferError::DecodingError)?;
+    let (_, _, crop_width, crop_height) = plan.crop;
+    let width = NonZeroU32::new(crop_width).ok_or(ImageTransferError::InvalidImageSize)?;
+    let height = NonZeroU32::new(crop_height).ok_or(ImageTransferError::InvalidImageSize)?;
+    let rgba = crop_rgba(&img.to_rgba8().into_raw(), img.width(), plan.crop);
+    let (dst_width, dst_height) = (plan.dst_width, plan.dst_height);
+
+    let pixel_art = filter == ResizeFilter::Auto && is_pixel_art(&rgba);
+    let integer_scaled = if pixel_art {
+        integer_scale(
+            &rgba,
+            width.get(),
+            height.get(),
+            dst_width.get(),
+            dst_height.get(),
+        )
+    } else {
+        None
+    };
+
+    let resized = match integer_scaled {
+        Some(resized) => resized,
+        None => resize_rgba(
+            rgba,
+            width,
+            height,
+            dst_width,
+            dst_height,
+            if pixel_art {
+                fr::ResizeAlg::Nearest
+            } else {
+                filter.resize_alg(&plan)
+            },
+        )?,
+    };
 
     let mut result_buf = BufWriter::new(Vec::new());
     PngEncoder::new(&mut result_buf)
         .write_image(
-            dst_image.buffer(),
+            &resized,
             dst_width.get(),
             dst_height.get(),
             ColorType::Rgba8,
         )
-        .unwrap();
+        .map_err(|_| ImageTransferError::PngEncodingError)?;
+
+    result_buf
+        .into_inner()
+        .map_err(|_| ImageTransferError::PngEncodingError)
+}
+
+//gif는 프레임마다 캔버스 기준 위치/크기가 다르니까 비율대로 같이 늘려줌
+//그래야 딜레이, disposal, 반복 횟수를 그대로 유지할 수 있음
+//움짤은 프레임 위치가 제각각이라 정수배 확대 대신 Nearest로만 키움
+fn resize_gif(
+    img_bytes: &[u8],
+    plan: ResizePlan,
+    filter: ResizeFilter,
+) -> Result<Vec<u8>, ImageTransferError> {
+    //프레임 조각마다 따로 키우면 조각 경계가 티나니까, 합성된 캔버스 전체를 키우고 다시 조각냄
+    let (frames, pixel_art) = resize_frames(decode_emoji_frames(img_bytes)?, plan, filter)?;
 
-    CreateAttachment::bytes(
-        result_buf.into_inner().unwrap().to_vec(),
-        "resized.png".to_string(),
+    gif_encoder::encode(
+        &frames,
+        &GifEncodeOptions {
+            dithering: !pixel_art,
+            repeat: gif_repeat(img_bytes)?,
+            ..GifEncodeOptions::default()
+        },
     )
 }
 
+//반복 횟수는 NETSCAPE 확장 블록을 읽은 다음에야 알 수 있음
+fn gif_repeat(img_bytes: &[u8]) -> Result<gif::Repeat, ImageTransferError> {
+    let mut options = gif::DecodeOptions::new();
+    options.skip_frame_decoding(true);
+    let mut decoder = options
+        .read_info(Cursor::new(img_bytes))
+        .map_err(|_| ImageTransferError::DecodingError)?;
+    while decoder
+        .read_next_frame()
+        .map_err(|_| ImageTransferError::DecodingError)?
+        .is_some()
+    {}
+
+    Ok(decoder.repeat())
+}
+
+//움짤 합칠 때 최소공배수가 너무 길어지면 그냥 긴 쪽 길이에 맞춤 (1/100초 단위)
+const MAX_MERGED_DURATION: u32 = 1000;
+const MAX_MERGED_FRAMES: usize = 300;
+//경계를 하나하나 만들어볼 최대 개수. 이보다 많으면 같은 간격으로 나눔
+const MAX_BOUNDARY_SCAN: u64 = 10_000;
+
+//프레임 하나랑 그 프레임의 딜레이(1/100초). 정적 이미지는 프레임 하나에 딜레이 0
+pub type EmojiFrames = Vec<(image::RgbaImage, u32)>;
+
+//여러 이모지를 한 장으로 합칠 때 배치 방법
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum EmojiLayout {
+    Row,
+    Column,
+    Grid,
+}
+
+impl EmojiLayout {
+    pub fn string_to_value(input_str: &str) -> Self {
+        match input_str {
+            "Row" => Self::Row,
+            "Column" => Self::Column,
+            _ => Self::Grid,
+        }
+    }
+
+    pub fn value_to_string(input_value: &EmojiLayout) -> String {
+        let st = match input_value {
+            Self::Row => "Row",
+            Self::Column => "Column",
+            Self::Grid => "Grid",
+        };
+        st.to_string()
+    }
+}
+
+//이모지 2개를 합칠 때 놓는 방법. Overlay는 두 번째 이모지를 첫 번째 위에 겹침
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum DoubleEmojiArrangement {
+    Horizontal,
+    Vertical,
+    Overlay,
+}
+
+impl DoubleEmojiArrangement {
+    pub fn string_to_value(input_str: &str) -> Self {
+        match input_str {
+            "Vertical" => Self::Vertical,
+            "Overlay" => Self::Overlay,
+            _ => Self::Horizontal,
+        }
+    }
+
+    pub fn value_to_string(input_value: &DoubleEmojiArrangement) -> String {
+        let st = match input_value {
+            Self::Horizontal => "Horizontal",
+            Self::Vertical => "Vertical",
+            Self::Overlay => "Overlay",
+        };
+        st.to_string()
+    }
+}
+
+//크기가 다른 두 이모지를 맞추는 기준. 가로로 놓으면 위/가운데/아래, 세로로 놓으면 왼쪽/가운데/오른쪽
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum EmojiAlignment {
+    Start,
+    Center,
+    End,
+}
+
+impl EmojiAlignment {
+    pub fn string_to_value(input_str: &str) -> Self {
+        match input_str {
+            "Start" => Self::Start,
+            "End" => Self::End,
+            _ => Self::Center,
+        }
+    }
+
+    pub fn value_to_string(input_value: &EmojiAlignment) -> String {
+        let st = match input_value {
+            Self::Start => "Start",
+            Self::Center => "Center",
+            Self::End => "End",
+        };
+        st.to_string()
+    }
+
+    //남는 공간 free 중에서 어디에 둘지
+    fn offset(&self, free: u32) -> u32 {
+        match self {
+            Self::Start => 0,
+            Self::Center => free / 2,
+            Self::End => free,
+        }
+    }
+}
+
+pub const MIN_OVERLAY_SCALE: u32 = 10;
+pub const MAX_OVERLAY_SCALE: u32 = 100;
+//겹칠 때 가운데에서 얼마나 옮길지. 첫 번째 이모지 크기의 퍼센트
+pub const MAX_OVERLAY_OFFSET: i32 = 50;
+
+//이모지 2개만 보냈을 때 합치는 방법. 서버마다 설정함
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct DoubleEmojiLayout {
+    pub arrangement: DoubleEmojiArrangement,
+    pub alignment: EmojiAlignment,
+    pub gap: u32,
+    //None이면 투명
+    pub background: Option<[u8; 3]>,
+    //겹칠 때 두 번째 이모지 크기. 첫 번째 이모지의 긴 변 대비 퍼센트
+    pub overlay_scale: u32,
+    pub overlay_offset: (i32, i32),
+}
+
+impl Default for DoubleEmojiLayout {
+    fn default() -> Self {
+        Self {
+            arrangement: DoubleEmojiArrangement::Horizontal,
+            alignment: EmojiAlignment::Center,
+            gap: 0,
+            background: None,
+            overlay_scale: 50,
+            overlay_offset: (25, 25),
+        }
+    }
+}
+
+impl DoubleEmojiLayout {
+    //"#RRGGBB" 형식. 그 외에는 투명으로 봄
+    pub fn background_from_string(input_str: &str) -> Option<[u8; 3]> {
+        let hex = input_str.strip_prefix('#')?;
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        Some([channel(0)?, channel(2)?, channel(4)?])
+    }
+
+    pub fn background_to_string(background: &Option<[u8; 3]>) -> String {
+        match background {
+            Some([r, g, b]) => format!("#{:02X}{:02X}{:02X}", r, g, b),
+            None => "None".to_string(),
+        }
+    }
+}
+
+pub const MAX_MERGED_EMOJIS: usize = 9;
+pub const MAX_EMOJI_SPACING: u32 = 32;
+
+async fn fetch_emoji_bytes(img_url: &str) -> Result<Vec<u8>, ImageTransferError> {
+    //크기 128x128로 가져옴
+    fetch_image_bytes(&format!("{}?size=128", img_url)).await
+}
+
+//두 이모지는 서버의 두 이모지 배치 설정(가로, 세로, 겹치기)을 따름
 pub async fn merge_two_emojis(
     first_url: &str,
     second_url: &str,
-) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
-    use image::{imageops, GenericImageView, ImageFormat};
-    use std::io::Cursor;
+    layout: DoubleEmojiLayout,
+    cell_size: Option<&ImageSize>,
+    filter: ResizeFilter,
+) -> Result<(bool, Vec<u8>), ImageTransferError> {
+    // 두 이모지를 병렬로 가져오기
+    let (first_emoji_result, second_emoji_result) =
+        tokio::join!(fetch_emoji_bytes(first_url), fetch_emoji_bytes(second_url));
 
-    // 첫 번째 이모지 URL 및 두 번째 이모지 URL 생성 (크기 128x128)
-    let first_emoji_url = format!("{}?size=128", first_url);
-    let second_emoji_url = format!("{}?size=128", second_url);
+    let (first_bytes, second_bytes) = (first_emoji_result?, second_emoji_result?);
+    let cell_size = cell_size.cloned();
 
-    // 두 이모지를 병렬로 가져오기
-    let (first_emoji_result, second_emoji_result) = tokio::join!(
-        async {
-            reqwest::get(&first_emoji_url)
-                .await
-                .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?
-                .bytes()
-                .await
-                .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
-        },
-        async {
-            reqwest::get(&second_emoji_url)
-                .await
-                .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?
-                .bytes()
-                .await
-                .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
+    //디코딩부터 크기 맞추기, 합치기까지 전부 오래 걸리니 런타임 밖에서 함
+    tokio::task::spawn_blocking(move || {
+        let sequences = fit_merge_cells(
+            vec![
+                decode_emoji_frames(&first_bytes)?,
+                decode_emoji_frames(&second_bytes)?,
+            ],
+            cell_size.as_ref(),
+            filter,
+        )?;
+        let sequences = match layout.arrangement {
+            DoubleEmojiArrangement::Overlay => {
+                scale_overlay(sequences, layout.overlay_scale, filter)?
+            }
+            _ => sequences,
+        };
+
+        merge_emoji_frames(sequences, |imgs| place_pair(imgs, &layout))
+    })
+    .await
+    .map_err(|_| ImageTransferError::Mollu)?
+}
+
+//겹칠 이모지(두 번째)를 첫 번째 이모지의 긴 변 대비 scale%로 맞춰둠
+fn scale_overlay(
+    mut sequences: Vec<EmojiFrames>,
+    scale: u32,
+    filter: ResizeFilter,
+) -> Result<Vec<EmojiFrames>, ImageTransferError> {
+    let longest = match sequences.first().and_then(|frames| frames.first()) {
+        Some((img, _)) => img.width().max(img.height()),
+        None => return Err(ImageTransferError::InvalidImageSize),
+    };
+    let target = proportional(
+        longest,
+        scale.clamp(MIN_OVERLAY_SCALE, MAX_OVERLAY_SCALE),
+        100,
+    );
+
+    if let Some(overlay) = sequences.pop() {
+        let (width, height) = match overlay.first() {
+            Some((img, _)) => img.dimensions(),
+            None => return Err(ImageTransferError::InvalidImageSize),
+        };
+        let overlay =
+            match ImageSize::Custom(target, target).resize_plan(width, height, FitMode::Contain)? {
+                Some(plan) => resize_frames(overlay, plan, filter).map(|(frames, _)| frames)?,
+                None => overlay,
+            };
+        sequences.push(overlay);
+    }
+    Ok(sequences)
+}
+
+//두 이모지를 설정대로 놓음. 겹치기는 첫 번째 이모지 크기의 캔버스에 두 번째를 가운데 + 오프셋 위치에 올림
+fn place_pair(imgs: &[&image::RgbaImage], layout: &DoubleEmojiLayout) -> image::RgbaImage {
+    use image::imageops;
+
+    let (first, second) = match imgs {
+        [first, second] => (*first, *second),
+        _ => return place_in_grid(imgs, EmojiLayout::Row, layout.gap),
+    };
+    let gap = layout.gap.min(MAX_EMOJI_SPACING);
+
+    let (width, height, first_pos, second_pos) = match layout.arrangement {
+        DoubleEmojiArrangement::Horizontal => {
+            let height = first.height().max(second.height());
+            (
+                first.width() + gap + second.width(),
+                height,
+                (0, layout.alignment.offset(height - first.height()) as i64),
+                (
+                    (first.width() + gap) as i64,
+                    layout.alignment.offset(height - second.height()) as i64,
+                ),
+            )
+        }
+        DoubleEmojiArrangement::Vertical => {
+            let width = first.width().max(second.width());
+            (
+                width,
+                first.height() + gap + second.height(),
+                (layout.alignment.offset(width - first.width()) as i64, 0),
+                (
+                    layout.alignment.offset(width - second.width()) as i64,
+                    (first.height() + gap) as i64,
+                ),
+            )
         }
+        DoubleEmojiArrangement::Overlay => {
+            let (dx, dy) = (
+                layout
+                    .overlay_offset
+                    .0
+                    .clamp(-MAX_OVERLAY_OFFSET, MAX_OVERLAY_OFFSET) as i64,
+                layout
+                    .overlay_offset
+                    .1
+                    .clamp(-MAX_OVERLAY_OFFSET, MAX_OVERLAY_OFFSET) as i64,
+            );
+            let (width, height) = first.dimensions();
+            (
+                width,
+                height,
+                (0, 0),
+                (
+                    (width as i64 - second.width() as i64) / 2 + width as i64 * dx / 100,
+                    (height as i64 - second.height() as i64) / 2 + height as i64 * dy / 100,
+                ),
+            )
+        }
+    };
+
+    let mut merged_img = match layout.background {
+        Some([r, g, b]) => image::RgbaImage::from_pixel(width, height, image::Rgba([r, g, b, 255])),
+        None => image::RgbaImage::new(width, height),
+    };
+    imageops::overlay(&mut merged_img, first, first_pos.0, first_pos.1);
+    imageops::overlay(&mut merged_img, second, second_pos.0, second_pos.1);
+    merged_img
+}
+
+//이모지 여러개(최대 9개)를 한 줄, 한 칸, 격자 중 하나로 배치해서 합침
+pub async fn merge_emojis(
+    img_urls: &[String],
+    layout: EmojiLayout,
+    spacing: u32,
+    cell_size: Option<&ImageSize>,
+    filter: ResizeFilter,
+) -> Result<(bool, Vec<u8>), ImageTransferError> {
+    if img_urls.is_empty() || img_urls.len() > MAX_MERGED_EMOJIS {
+        return Err(ImageTransferError::TooManyEmojis);
+    }
+
+    let fetched =
+        futures::future::join_all(img_urls.iter().map(|url| fetch_emoji_bytes(url))).await;
+
+    let fetched = fetched.into_iter().collect::<Result<Vec<_>, _>>()?;
+    let cell_size = cell_size.cloned();
+    let spacing = spacing.min(MAX_EMOJI_SPACING);
+
+    //최대 9개를 디코딩하고 크기 맞추는 것까지 전부 런타임 밖에서 함
+    tokio::task::spawn_blocking(move || {
+        let sequences = fetched
+            .iter()
+            .map(|img_bytes| decode_emoji_frames(img_bytes))
+            .collect::<Result<Vec<_>, _>>()?;
+        let sequences = fit_merge_cells(sequences, cell_size.as_ref(), filter)?;
+
+        merge_emoji_frames(sequences, |imgs| place_in_grid(imgs, layout, spacing))
+    })
+    .await
+    .map_err(|_| ImageTransferError::Mollu)?
+}
+
+//크기가 Auto인 서버는 합치기 전에 이모지마다 칸 크기 범위에 맞춰 키우거나 줄임
+fn fit_merge_cells(
+    sequences: Vec<EmojiFrames>,
+    cell_size: Option<&ImageSize>,
+    filter: ResizeFilter,
+) -> Result<Vec<EmojiFrames>, ImageTransferError> {
+    let Some(cell_size) = cell_size else {
+        return Ok(sequences);
+    };
+
+    sequences
+        .into_iter()
+        .map(|frames| {
+            let (width, height) = match frames.first() {
+                Some((img, _)) => img.dimensions(),
+                None => return Err(ImageTransferError::InvalidImageSize),
+            };
+            match cell_size.resize_plan(width, height, FitMode::Contain)? {
+                Some(plan) => resize_frames(frames, plan, filter).map(|(frames, _)| frames),
+                None => Ok(frames),
+            }
+        })
+        .collect()
+}
+
+//전부 정적이면 PNG 한 장, 하나라도 움짤이면 공통 타임라인 위에서 gif로 합침
+fn merge_emoji_frames<F>(
+    sequences: Vec<EmojiFrames>,
+    compose: F,
+) -> Result<(bool, Vec<u8>), ImageTransferError>
+where
+    F: Fn(&[&image::RgbaImage]) -> image::RgbaImage,
+{
+    if sequences.iter().all(|frames| frames.len() == 1) {
+        let imgs = sequences
+            .iter()
+            .map(|frames| &frames[0].0)
+            .collect::<Vec<_>>();
+        let merged_img = compose(&imgs);
+
+        // 결과 이미지를 PNG로 인코딩
+        let mut result_buffer = Vec::new();
+        {
+            let mut cursor = Cursor::new(&mut result_buffer);
+            merged_img
+                .write_to(&mut cursor, ImageFormat::Png)
+                .map_err(|_| ImageTransferError::PngEncodingError)?;
+        }
+
+        return Ok((true, result_buffer));
+    }
+
+    Ok((false, merge_animated_frames(&sequences, compose)?))
+}
+
+//움짤(gif, APNG, webp)은 캔버스 전체 크기의 프레임들로, 정적 이미지는 딜레이 0인 프레임 하나로 풂
+pub fn decode_emoji_frames(img_bytes: &[u8]) -> Result<EmojiFrames, ImageTransferError> {
+    use image::{
+        codecs::{gif::GifDecoder, webp::WebPDecoder},
+        AnimationDecoder,
+    };
+
+    if is_apng(img_bytes) {
+        return Ok(Apng::decode(img_bytes)?.compose());
+    }
+
+    if let Ok(ImageFormat::WebP) = image::guess_format(img_bytes) {
+        let decoder = WebPDecoder::new(Cursor::new(img_bytes))
+            .map_err(|_| ImageTransferError::DecodingWebPError)?;
+        if decoder.has_animation() {
+            let frames = decoder
+                .into_frames()
+                .collect_frames()
+                .map_err(|_| ImageTransferError::DecodingWebPError)?;
+            return Ok(frames
+                .into_iter()
+                .map(|frame| {
+                    let (numer, denom) = frame.delay().numer_denom_ms();
+                    let delay = numer.checked_div(denom).unwrap_or(0) / 10;
+                    (frame.into_buffer(), if delay < 2 { 10 } else { delay })
+                })
+                .collect());
+        }
+    }
+
+    if let Ok(ImageFormat::Gif) = image::guess_format(img_bytes) {
+        let frames = GifDecoder::new(Cursor::new(img_bytes))
+            .and_then(|decoder| decoder.into_frames().collect_frames())
+            .map_err(|_| ImageTransferError::DecodingError)?;
+
+        if frames.len() > 1 {
+            return Ok(frames
+                .into_iter()
+                .map(|frame| {
+                    let (numer, denom) = frame.delay().numer_denom_ms();
+                    let delay = numer.checked_div(denom).unwrap_or(0) / 10;
+                    //브라우저들이랑 똑같이 너무 짧은 딜레이는 0.1초로 취급
+                    (frame.into_buffer(), if delay < 2 { 10 } else { delay })
+                })
+                .collect());
+        }
+    }
+
+    let img = image::load_from_memory(img_bytes).map_err(|_| ImageTransferError::DecodingError)?;
+    Ok(vec![(img.to_rgba8(), 0)])
+}
+
+//칸 크기는 제일 큰 이모지에 맞추고, 작은 이모지는 칸 가운데에 둠
+fn place_in_grid(
+    imgs: &[&image::RgbaImage],
+    layout: EmojiLayout,
+    spacing: u32,
+) -> image::RgbaImage {
+    use image::imageops;
+
+    let count = imgs.len() as u32;
+    let columns = match layout {
+        EmojiLayout::Row => count,
+        EmojiLayout::Column => 1,
+        EmojiLayout::Grid => (1..=count).find(|c| c * c >= count).unwrap_or(1),
+    };
+    let rows = count.div_ceil(columns);
+
+    let cell_width = imgs.iter().map(|img| img.width()).max().unwrap_or(1);
+    let cell_height = imgs.iter().map(|img| img.height()).max().unwrap_or(1);
+
+    let mut merged_img = image::RgbaImage::new(
+        columns * cell_width + (columns - 1) * spacing,
+        rows * cell_height + (rows - 1) * spacing,
     );
 
-    // 결과 처리
-    let first_emoji_bytes = first_emoji_result?.to_vec();
-    let second_emoji_bytes = second_emoji_result?.to_vec();
+    for (idx, img) in imgs.iter().enumerate() {
+        let (column, row) = (idx as u32 % columns, idx as u32 / columns);
+        let x = column * (cell_width + spacing) + (cell_width - img.width()) / 2;
+        let y = row * (cell_height + spacing) + (cell_height - img.height()) / 2;
+        imageops::overlay(&mut merged_img, *img, x as i64, y as i64);
+    }
 
-    // 이미지 로드
-    let first_img = image::load_from_memory(&first_emoji_bytes)
-        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
-    let second_img = image::load_from_memory(&second_emoji_bytes)
-        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
+    merged_img
+}
 
-    // 첫 번째와 두 번째 이모지의 크기 확인
-    let (width1, height1) = first_img.dimensions();
-    let (width2, height2) = second_img.dimensions();
+//각자 자기 딜레이대로 돌아가게 모든 움짤의 프레임 경계를 합친 타임라인을 만듦
+//전체 길이는 재생 시간들의 최소공배수라 끝에서 처음으로 자연스럽게 이어짐
+fn merge_animated_frames<F>(
+    sequences: &[EmojiFrames],
+    compose: F,
+) -> Result<Vec<u8>, ImageTransferError>
+where
+    F: Fn(&[&image::RgbaImage]) -> image::RgbaImage,
+{
+    let periods = sequences
+        .iter()
+        .map(|frames| frames.iter().map(|(_, delay)| *delay as u64).sum::<u64>())
+        .collect::<Vec<u64>>();
 
-    // 새 이미지 생성 (두 이모지를 나란히 배치)
-    let mut merged_img = image::RgbaImage::new(width1 + width2, std::cmp::max(height1, height2));
+    //전체 길이 안에 생기는 프레임 경계 수. 실제로 만들기 전에 이걸로 너무 많은지 봄
+    let boundary_count = |total: u64| {
+        sequences
+            .iter()
+            .zip(&periods)
+            .filter(|(_, period)| **period != 0)
+            .map(|(frames, period)| (total / period + 1).saturating_mul(frames.len() as u64))
+            .fold(0u64, |acc, count| acc.saturating_add(count))
+    };
 
-    // 첫 번째 이모지 복사
-    imageops::overlay(&mut merged_img, &first_img, 0, 0);
+    let boundaries = |total: u64| {
+        let mut times = Vec::new();
+        for (frames, period) in sequences.iter().zip(&periods) {
+            if *period == 0 {
+                continue;
+            }
+            let mut t = 0;
+            'outer: loop {
+                for (_, delay) in frames {
+                    t += *delay as u64;
+                    if t >= total {
+                        break 'outer;
+                    }
+                    times.push(t);
+                }
+            }
+        }
+        times
+    };
 
-    // 두 번째 이모지 복사 (첫 번째 이모지 오른쪽에 배치)
-    imageops::overlay(&mut merged_img, &second_img, width1 as i64, 0);
+    //최소공배수는 u64로 구하고, 넘치거나 너무 길면 경계를 만들기 전에 긴 쪽 길이로 바꿈
+    let lcm = periods
+        .iter()
+        .filter(|period| **period != 0)
+        .try_fold(1u64, |acc, period| {
+            (acc / gcd(acc, *period)).checked_mul(*period)
+        });
+    let total = match lcm {
+        Some(lcm)
+            if lcm <= MAX_MERGED_DURATION as u64
+                && boundary_count(lcm) < MAX_MERGED_FRAMES as u64 =>
+        {
+            lcm
+        }
+        _ => periods.iter().copied().max().unwrap_or(0),
+    };
 
-    // 결과 이미지를 PNG로 인코딩
-    let mut result_buffer = Vec::new();
-    {
-        let mut cursor = Cursor::new(&mut result_buffer);
-        merged_img
-            .write_to(&mut cursor, ImageFormat::Png)
-            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
+    //긴 쪽 길이로 바꿔도 경계가 너무 많으면 같은 간격으로 나눔
+    let mut times = if boundary_count(total) <= MAX_BOUNDARY_SCAN {
+        boundaries(total)
+    } else {
+        (1..MAX_MERGED_FRAMES as u64)
+            .map(|index| total * index / MAX_MERGED_FRAMES as u64)
+            .collect()
+    };
+    times.push(0);
+    times.push(total);
+    times.sort_unstable();
+    times.dedup();
+
+    //그래도 프레임 수 제한을 넘으면 경계를 솎아냄. 처음과 끝은 남겨서 전체 길이는 유지함
+    if times.len() > MAX_MERGED_FRAMES + 1 {
+        let step = (times.len() - 1).div_ceil(MAX_MERGED_FRAMES);
+        let last = times[times.len() - 1];
+        times = times.into_iter().step_by(step).collect();
+        if times.last() != Some(&last) {
+            times.push(last);
+        }
+    }
+
+    let frame_at = |frames: &EmojiFrames, period: u64, t: u64| -> usize {
+        if period == 0 {
+            return 0;
+        }
+        let mut t = t % period;
+        for (idx, (_, delay)) in frames.iter().enumerate() {
+            if t < *delay as u64 {
+                return idx;
+            }
+            t -= *delay as u64;
+        }
+        frames.len() - 1
+    };
+
+    let merged_frames = times
+        .windows(2)
+        .map(|window| {
+            let (start, end) = (window[0], window[1]);
+            let imgs = sequences
+                .iter()
+                .zip(&periods)
+                .map(|(frames, period)| &frames[frame_at(frames, *period, start)].0)
+                .collect::<Vec<_>>();
+            (compose(&imgs), (end - start).min(u32::MAX as u64) as u32)
+        })
+        .collect::<EmojiFrames>();
+
+    encode_gif_frames(merged_frames)
+}
+
+//캔버스 전체 크기의 프레임들을 무한 반복 gif로 인코딩
+pub fn encode_gif_frames(frames: EmojiFrames) -> Result<Vec<u8>, ImageTransferError> {
+    gif_encoder::encode(&frames, &GifEncodeOptions::default())
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid_frames(delays: &[u32]) -> EmojiFrames {
+        delays
+            .iter()
+            .enumerate()
+            .map(|(index, delay)| {
+                let shade = (index * 40) as u8;
+                (
+                    image::RgbaImage::from_pixel(4, 4, image::Rgba([shade, shade, shade, 255])),
+                    *delay,
+                )
+            })
+            .collect()
+    }
+
+    //큰 캔버스에 1x1 프레임을 잔뜩 넣은 APNG. 캔버스 x 프레임 수가 예산을 넘음
+    fn oversized_apng() -> Vec<u8> {
+        const SIDE: u32 = 2048;
+        const FRAMES: u32 = 16;
+
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, SIDE, SIDE);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_animated(FRAMES, 0).unwrap();
+        let mut writer = encoder.write_header().unwrap();
+        writer
+            .write_image_data(&vec![0; (SIDE * SIDE * 4) as usize])
+            .unwrap();
+        for _ in 1..FRAMES {
+            writer.set_frame_dimension(1, 1).unwrap();
+            writer.write_image_data(&[0; 4]).unwrap();
+        }
+        writer.finish().unwrap();
+        bytes
+    }
+
+    #[test]
+    fn apng_transfer_rejects_oversized_canvas_before_composing() {
+        let apng = oversized_apng();
+        assert!(apng.len() < WEBP_SIZE_LIMIT as usize);
+        assert!(matches!(
+            transfer_apng_to_gif(&apng),
+            Err(ImageTransferError::AnimationTooLarge)
+        ));
+    }
+
+    //파일 앞부분만으로 움짤 여부를 알 수 있어야 함
+    #[test]
+    fn is_animated_image_sniffs_head() {
+        let apng = oversized_apng();
+        assert!(is_animated_image(&apng[..1024]));
+
+        let mut png = Vec::new();
+        image::RgbaImage::new(4, 4)
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        assert!(!is_animated_image(&png));
+
+        let webp = encode_webp_frames(&solid_frames(&[10, 10]), 0).unwrap();
+        assert!(is_animated_image(&webp[..64]));
     }
 
-    Ok(result_buffer)
+    //움짤 webp로 다시 읽었을 때 프레임 수와 반복 횟수가 그대로여야 함
+    #[test]
+    fn encode_webp_frames_round_trip() {
+        use image::{codecs::webp::WebPDecoder, AnimationDecoder};
+
+        let frames = solid_frames(&[5, 10, 20]);
+        let webp = encode_webp_frames(&frames, 3).unwrap();
+
+        let decoder = WebPDecoder::new(Cursor::new(&webp)).unwrap();
+        assert!(decoder.has_animation());
+        let decoded = decoder.into_frames().collect_frames().unwrap();
+        assert_eq!(decoded.len(), frames.len());
+        for (decoded, (original, delay)) in decoded.iter().zip(&frames) {
+            assert_eq!(decoded.buffer(), original);
+            assert_eq!(decoded.delay().numer_denom_ms(), (delay * 10, 1));
+        }
+
+        //ANIM 청크: 배경색 4바이트 다음에 반복 횟수
+        let anim = webp.windows(4).position(|chunk| chunk == b"ANIM").unwrap();
+        assert_eq!(u16::from_le_bytes([webp[anim + 12], webp[anim + 13]]), 3);
+    }
+
+    fn plan_of(
+        size: ImageSize,
+        src: (u32, u32),
+        fit: FitMode,
+    ) -> Option<(u32, u32, u32, u32, u32, u32)> {
+        size.resize_plan(src.0, src.1, fit).unwrap().map(|plan| {
+            let (left, top, width, height) = plan.crop;
+            (
+                left,
+                top,
+                width,
+                height,
+                plan.dst_width.get(),
+                plan.dst_height.get(),
+            )
+        })
+    }
+
+    #[test]
+    fn resize_plan_fits_box() {
+        //가로로 긴 원본
+        assert_eq!(
+            plan_of(ImageSize::Medium, (200, 100), FitMode::Contain),
+            Some((0, 0, 200, 100, 128, 64))
+        );
+        assert_eq!(
+            plan_of(ImageSize::Medium, (200, 100), FitMode::Cover),
+            Some((50, 0, 100, 100, 128, 128))
+        );
+        assert_eq!(
+            plan_of(ImageSize::Medium, (200, 100), FitMode::Stretch),
+            Some((0, 0, 200, 100, 128, 128))
+        );
+        //세로로 긴 원본
+        assert_eq!(
+            plan_of(ImageSize::Small, (30, 90), FitMode::Contain),
+            Some((0, 0, 30, 90, 21, 64))
+        );
+        assert_eq!(
+            plan_of(ImageSize::Small, (30, 90), FitMode::Cover),
+            Some((0, 30, 30, 30, 64, 64))
+        );
+        //한쪽만 정하면 나머지는 비율대로
+        assert_eq!(
+            plan_of(ImageSize::Custom(100, 0), (50, 20), FitMode::Cover),
+            Some((0, 0, 50, 20, 100, 40))
+        );
+        assert_eq!(
+            plan_of(ImageSize::Scale(50), (101, 10), FitMode::Contain),
+            Some((0, 0, 101, 10, 51, 5))
+        );
+    }
+
+    #[test]
+    fn resize_plan_auto_bounds() {
+        //범위 안이면 그대로, 작으면 최소 크기로, 크면 최대 크기로 비율을 지켜서 맞춤
+        assert_eq!(
+            plan_of(ImageSize::AutoBounded(64, 256), (128, 100), FitMode::Cover),
+            None
+        );
+        assert_eq!(
+            plan_of(ImageSize::AutoBounded(64, 256), (32, 16), FitMode::Cover),
+            Some((0, 0, 32, 16, 64, 32))
+        );
+        assert_eq!(
+            plan_of(ImageSize::AutoBounded(64, 256), (300, 600), FitMode::Cover),
+            Some((0, 0, 300, 600, 128, 256))
+        );
+        assert!(ImageSize::Custom(2048, 16)
+            .resize_plan(16, 16, FitMode::Stretch)
+            .is_err());
+        assert!(ImageSize::Medium
+            .resize_plan(0, 16, FitMode::Contain)
+            .is_err());
+    }
+
+    #[test]
+    fn place_pair_aligns_and_overlays() {
+        let big = image::RgbaImage::from_pixel(8, 8, image::Rgba([255, 0, 0, 255]));
+        let small = image::RgbaImage::from_pixel(4, 4, image::Rgba([0, 0, 255, 255]));
+        let blue = image::Rgba([0, 0, 255, 255]);
+
+        let layout = DoubleEmojiLayout {
+            alignment: EmojiAlignment::End,
+            gap: 2,
+            ..DoubleEmojiLayout::default()
+        };
+        let merged = place_pair(&[&big, &small], &layout);
+        assert_eq!(merged.dimensions(), (14, 8));
+        //아래 정렬이라 작은 이모지는 오른쪽 아래에 붙고, 간격은 투명함
+        assert_eq!(*merged.get_pixel(10, 7), blue);
+        assert_eq!(merged.get_pixel(10, 0)[3], 0);
+        assert_eq!(merged.get_pixel(9, 7)[3], 0);
+
+        let layout = DoubleEmojiLayout {
+            arrangement: DoubleEmojiArrangement::Vertical,
+            background: Some([255, 255, 255]),
+            ..DoubleEmojiLayout::default()
+        };
+        let merged = place_pair(&[&big, &small], &layout);
+        assert_eq!(merged.dimensions(), (8, 12));
+        assert_eq!(*merged.get_pixel(2, 8), blue);
+        assert_eq!(merged.get_pixel(0, 8).0, [255, 255, 255, 255]);
+
+        //겹치기는 첫 번째 이모지 크기 그대로, 가운데에서 (25%, 25%) 옮긴 자리
+        let layout = DoubleEmojiLayout {
+            arrangement: DoubleEmojiArrangement::Overlay,
+            ..DoubleEmojiLayout::default()
+        };
+        let merged = place_pair(&[&big, &small], &layout);
+        assert_eq!(merged.dimensions(), (8, 8));
+        assert_eq!(*merged.get_pixel(4, 4), blue);
+        assert_eq!(*merged.get_pixel(7, 7), blue);
+        assert_eq!(merged.get_pixel(3, 3).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn double_layout_background_round_trip() {
+        let background = DoubleEmojiLayout::background_from_string("#31A3ff");
+        assert_eq!(background, Some([0x31, 0xA3, 0xFF]));
+        assert_eq!(
+            DoubleEmojiLayout::background_to_string(&background),
+            "#31A3FF"
+        );
+        assert_eq!(DoubleEmojiLayout::background_from_string("None"), None);
+        assert_eq!(DoubleEmojiLayout::background_from_string("#12345"), None);
+    }
+
+    //최소공배수가 수십억이 되는 조합이어도 넘치거나 경계를 다 만들지 않아야 함
+    #[test]
+    fn merge_animated_frames_bounds_huge_lcm() {
+        let sequences = [100, 110, 130, 170, 190, 230, 290, 310, 410]
+            .iter()
+            .map(|period| solid_frames(&[period / 2, period - period / 2]))
+            .collect::<Vec<EmojiFrames>>();
+
+        let gif = merge_animated_frames(&sequences, |imgs| imgs[0].clone()).unwrap();
+        let frames = decode_emoji_frames(&gif).unwrap();
+        assert!(frames.len() <= MAX_MERGED_FRAMES);
+        //긴 쪽 길이(4.1초)로 맞춰짐
+        assert_eq!(frames.iter().map(|(_, delay)| delay).sum::<u32>(), 410);
+    }
+
+    #[test]
+    fn merge_animated_frames_uses_lcm_when_short() {
+        let sequences = vec![solid_frames(&[10, 10]), solid_frames(&[10, 10, 10])];
+        let gif = merge_animated_frames(&sequences, |imgs| {
+            let mut merged = imgs[0].clone();
+            merged.put_pixel(0, 0, *imgs[1].get_pixel(0, 0));
+            merged
+        })
+        .unwrap();
+        let frames = decode_emoji_frames(&gif).unwrap();
+        assert_eq!(frames.iter().map(|(_, delay)| delay).sum::<u32>(), 60);
+        assert_eq!(frames.len(), 6);
+    }
 }
@@ -0,0 +1,91 @@
+//유니코드 이모지를 같이 넣어둔 Twemoji SVG로 그리는 부분
+//Twemoji 그림은 CC-BY 4.0 (Copyright Twitter Inc. and other contributors)
+use image::RgbaImage;
+use resvg::usvg::{Options, Tree};
+use tiny_skia::{Pixmap, Transform};
+use twemoji_assets::svg::SvgTwemojiAsset;
+
+use crate::utils::effects::from_pixmap;
+
+//Twemoji PNG 원본 크기. SVG도 이 크기짜리 원본으로 보고 크기를 정함
+pub const TWEMOJI_SIZE: u32 = 72;
+
+const VARIATION_SELECTOR: char = '\u{fe0f}';
+//이것보다 앞에 있는 글자(©, ®, ™, ‼ 등)는 보통 글자로 쓰니까 FE0F가 붙었을 때만 이모지로 봄
+const TEXT_PRESENTATION_END: char = '\u{2190}';
+
+//문자열 전체가 이모지 하나(ZWJ 조합, 피부색, 국기 포함)일 때만 찾아줌
+//Twemoji 이름에는 FE0F가 붙은 것도 있고 안 붙은 것도 있어서 빼고도 한 번 더 찾아봄
+pub fn find_twemoji(emoji: &str) -> Option<&'static SvgTwemojiAsset> {
+    let mut chars = emoji.chars();
+    let first = chars.next()?;
+    if first < TEXT_PRESENTATION_END && chars.next() != Some(VARIATION_SELECTOR) {
+        return None;
+    }
+
+    SvgTwemojiAsset::from_emoji(emoji).or_else(|| {
+        let stripped: String = emoji.chars().filter(|c| *c != VARIATION_SELECTOR).collect();
+        SvgTwemojiAsset::from_emoji(&stripped)
+    })
+}
+
+//transform은 TWEMOJI_SIZE 크기 원본 기준. 결과는 width x height
+pub fn render_twemoji(
+    emoji: &SvgTwemojiAsset,
+    transform: Transform,
+    width: u32,
+    height: u32,
+) -> Option<RgbaImage> {
+    let tree = Tree::from_str(**emoji, &Options::default()).ok()?;
+    let size = tree.size();
+    let to_source = Transform::from_scale(
+        TWEMOJI_SIZE as f32 / size.width(),
+        TWEMOJI_SIZE as f32 / size.height(),
+    );
+
+    let mut pixmap = Pixmap::new(width, height)?;
+    resvg::render(
+        &tree,
+        to_source.post_concat(transform),
+        &mut pixmap.as_mut(),
+    );
+    Some(from_pixmap(&pixmap))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_sequences_and_modifiers() {
+        //피부색, 국기, ZWJ 가족, FE0F 붙은 하트
+        for emoji in ["😂", "👍🏽", "🇰🇷", "👨‍👩‍👧‍👦", "❤️", "🏳️‍🌈"]
+        {
+            assert!(find_twemoji(emoji).is_some(), "{emoji} not found");
+        }
+        assert_eq!(find_twemoji("❤️"), find_twemoji("❤"));
+        //피부색이 붙으면 다른 그림
+        assert_ne!(find_twemoji("👍🏽"), find_twemoji("👍"));
+    }
+
+    #[test]
+    fn rejects_text_and_multiple_emojis() {
+        for text in ["", "a", "1", "™", "ㅋㅋ", "😂😂", "😂 ", "<:a:1>"] {
+            assert!(find_twemoji(text).is_none(), "{text:?} matched");
+        }
+        assert!(find_twemoji("™\u{fe0f}").is_some());
+    }
+
+    #[test]
+    fn renders_at_requested_size() {
+        let emoji = find_twemoji("🇰🇷").unwrap();
+        let scale = 300.0 / TWEMOJI_SIZE as f32;
+        let frame = render_twemoji(emoji, Transform::from_scale(scale, scale), 300, 300).unwrap();
+
+        assert_eq!(frame.dimensions(), (300, 300));
+        //태극기 바탕은 #EEE
+        assert_eq!(frame.get_pixel(150, 60).0, [0xee, 0xee, 0xee, 255]);
+        //위아래는 투명한 여백
+        assert_eq!(frame.get_pixel(150, 0)[3], 0);
+    }
+}
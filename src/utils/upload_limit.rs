@@ -0,0 +1,327 @@
+//결과 이미지가 서버 업로드 제한을 넘으면 화질이나 크기를 조금씩 포기해서 맞추는 부분
+//gif 그대로 - 팔레트 줄이기 - 프레임 솎기 - 크기 줄이기 순으로 시도하고, 뭘 포기했는지 알려줌
+//손실 webp 단계는 없음. 쓸 수 있는 인코더가 무손실 webp뿐인데, 무손실은 보통 gif보다 커서 도움이 안 됨
+use serenity::{client::Context, model::guild::PremiumTier, model::id::GuildId};
+
+use std::num::NonZeroU32;
+
+use fast_image_resize as fr;
+
+use image::ImageEncoder;
+
+use crate::utils::emoji_cache::CachedImage;
+use crate::utils::gif_encoder::{self, GifEncodeOptions, PaletteMode};
+use crate::utils::scene_core::{
+    decode_emoji_frames, encode_gif_frames, resize_rgba, threshold_alpha, EmojiFrames,
+    ImageTransferError,
+};
+
+//부스트 레벨별 업로드 제한. 2레벨부터 늘어남
+const DEFAULT_UPLOAD_LIMIT: u64 = 10 * 1024 * 1024;
+const TIER2_UPLOAD_LIMIT: u64 = 50 * 1024 * 1024;
+const TIER3_UPLOAD_LIMIT: u64 = 100 * 1024 * 1024;
+
+//메시지 본문이나 멀티파트 헤더 몫으로 조금 남겨둠
+const UPLOAD_LIMIT_MARGIN: u64 = 64 * 1024;
+
+const REDUCED_PALETTE_SIZES: [usize; 2] = [64, 16];
+const FRAME_DECIMATION_STEPS: [usize; 2] = [2, 4];
+const DOWNSCALE_PERCENTS: [u32; 3] = [75, 50, 25];
+
+//업로드 제한에 맞추려고 포기한 것
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeCompromise {
+    ConvertedToGif,
+    ReducedPalette(usize),
+    DroppedFrames(usize),
+    Downscaled(u32, u32),
+}
+
+impl SizeCompromise {
+    pub fn get_notice_message(&self) -> String {
+        match self {
+            Self::ReducedPalette(colors) => format!(
+                "서버 업로드 제한에 맞추려고 색을 {}가지로 줄였습니다.",
+                colors
+            ),
+            Self::DroppedFrames(step) => format!(
+                "서버 업로드 제한에 맞추려고 프레임을 {}개 중 1개만 남겼습니다.",
+                step
+            ),
+            Self::ConvertedToGif => "서버 업로드 제한에 맞추려고 GIF로 바꿨습니다.".to_string(),
+            Self::Downscaled(width, height) => format!(
+                "서버 업로드 제한에 맞추려고 크기를 {}x{}로 줄였습니다.",
+                width, height
+            ),
+        }
+    }
+}
+
+//캐시에 없는 서버면 제일 작은 제한으로 봄
+pub fn guild_upload_limit(ctx: &Context, guild_id: Option<GuildId>) -> u64 {
+    let premium_tier =
+        guild_id.and_then(|guild_id| ctx.cache.guild(guild_id).map(|guild| guild.premium_tier));
+
+    match premium_tier {
+        Some(PremiumTier::Tier2) => TIER2_UPLOAD_LIMIT,
+        Some(PremiumTier::Tier3) => TIER3_UPLOAD_LIMIT,
+        _ => DEFAULT_UPLOAD_LIMIT,
+    }
+}
+
+//제한 안이면 그대로 돌려주고, 넘으면 순서대로 줄여보다가 처음 맞는 걸 돌려줌
+pub fn fit_to_upload_limit(
+    image: CachedImage,
+    upload_limit: u64,
+) -> Result<(CachedImage, Option<SizeCompromise>), ImageTransferError> {
+    let limit = upload_limit.saturating_sub(UPLOAD_LIMIT_MARGIN) as usize;
+    if image.bytes.len() <= limit {
+        return Ok((image, None));
+    }
+
+    let frames = decode_emoji_frames(&image.bytes)?;
+    let fits = |bytes: &Vec<u8>| bytes.len() <= limit;
+
+    //정적 이미지는 크기 말고는 줄일 게 없음
+    if frames.len() < 2 {
+        for percent in DOWNSCALE_PERCENTS {
+            let (scaled, width, height) = downscale_frames(&frames, percent)?;
+            let png = encode_png_frame(&scaled)?;
+            if fits(&png) {
+                return Ok((
+                    CachedImage {
+                        bytes: png,
+                        filename: "resized.png".to_string(),
+                    },
+                    Some(SizeCompromise::Downscaled(width, height)),
+                ));
+            }
+        }
+        return Err(ImageTransferError::UploadLimitExceeded);
+    }
+
+    let gif_result = |bytes: Vec<u8>, compromise: SizeCompromise| {
+        (
+            CachedImage {
+                bytes,
+                filename: "resized.gif".to_string(),
+            },
+            Some(compromise),
+        )
+    };
+
+    //gif는 1비트 투명도라 미리 잘라둠
+    let gif_frames: EmojiFrames = frames
+        .iter()
+        .map(|(frame, delay)| {
+            let mut frame = frame.clone();
+            threshold_alpha(&mut frame);
+            (frame, *delay)
+        })
+        .collect();
+
+    //gif가 아니었으면 먼저 그냥 gif로 바꿔봄
+    if !image.filename.ends_with(".gif") {
+        let gif = encode_gif_frames(gif_frames.clone())?;
+        if fits(&gif) {
+            return Ok((
+                CachedImage {
+                    bytes: gif,
+                    filename: "resized.gif".to_string(),
+                },
+                Some(SizeCompromise::ConvertedToGif),
+            ));
+        }
+    }
+
+    for colors in REDUCED_PALETTE_SIZES {
+        let gif = gif_encoder::encode(
+            &gif_frames,
+            &GifEncodeOptions {
+                palette: PaletteMode::PerFrame,
+                colors,
+                ..GifEncodeOptions::default()
+            },
+        )?;
+        if fits(&gif) {
+            return Ok(gif_result(gif, SizeCompromise::ReducedPalette(colors)));
+        }
+    }
+
+    for step in FRAME_DECIMATION_STEPS {
+        let gif = encode_gif_frames(decimate_frames(&gif_frames, step))?;
+        if fits(&gif) {
+            return Ok(gif_result(gif, SizeCompromise::DroppedFrames(step)));
+        }
+    }
+
+    for percent in DOWNSCALE_PERCENTS {
+        let (mut scaled, width, height) = downscale_frames(&gif_frames, percent)?;
+        for (frame, _) in scaled.iter_mut() {
+            threshold_alpha(frame);
+        }
+        let gif = encode_gif_frames(scaled)?;
+        if fits(&gif) {
+            return Ok(gif_result(gif, SizeCompromise::Downscaled(width, height)));
+        }
+    }
+
+    Err(ImageTransferError::UploadLimitExceeded)
+}
+
+//step개마다 하나만 남기고, 버린 프레임의 딜레이는 남긴 프레임에 더해서 전체 길이는 유지함
+fn decimate_frames(frames: &EmojiFrames, step: usize) -> EmojiFrames {
+    frames
+        .chunks(step)
+        .map(|chunk| {
+            let delay = chunk.iter().map(|(_, delay)| delay).sum();
+            (chunk[0].0.clone(), delay)
+        })
+        .collect()
+}
+
+fn downscale_frames(
+    frames: &EmojiFrames,
+    percent: u32,
+) -> Result<(EmojiFrames, u32, u32), ImageTransferError> {
+    let (width, height) = match frames.first() {
+        Some((img, _)) => img.dimensions(),
+        None => return Err(ImageTransferError::InvalidImageSize),
+    };
+    let src_width = NonZeroU32::new(width).ok_or(ImageTransferError::InvalidImageSize)?;
+    let src_height = NonZeroU32::new(height).ok_or(ImageTransferError::InvalidImageSize)?;
+    let dst_width = NonZeroU32::new((width * percent / 100).max(1)).unwrap_or(NonZeroU32::MIN);
+    let dst_height = NonZeroU32::new((height * percent / 100).max(1)).unwrap_or(NonZeroU32::MIN);
+
+    let scaled = frames
+        .iter()
+        .map(|(frame, delay)| {
+            let resized = resize_rgba(
+                frame.as_raw().clone(),
+                src_width,
+                src_height,
+                dst_width,
+                dst_height,
+                fr::ResizeAlg::Convolution(fr::FilterType::Bilinear),
+            )?;
+            let resized = image::RgbaImage::from_raw(dst_width.get(), dst_height.get(), resized)
+                .ok_or(ImageTransferError::ResizeError)?;
+            Ok((resized, *delay))
+        })
+        .collect::<Result<EmojiFrames, ImageTransferError>>()?;
+
+    Ok((scaled, dst_width.get(), dst_height.get()))
+}
+
+fn encode_png_frame(frames: &EmojiFrames) -> Result<Vec<u8>, ImageTransferError> {
+    let (img, _) = frames.first().ok_or(ImageTransferError::PngEncodingError)?;
+
+    let mut result_buf = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut result_buf)
+        .write_image(
+            img.as_raw(),
+            img.width(),
+            img.height(),
+            image::ColorType::Rgba8,
+        )
+        .map_err(|_| ImageTransferError::PngEncodingError)?;
+
+    Ok(result_buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //압축이 잘 안 되게 프레임마다 다른 노이즈를 채운 움짤
+    fn noise_frames(size: u32, count: usize) -> EmojiFrames {
+        let mut seed = 0x2545_f491_u32;
+        (0..count)
+            .map(|_| {
+                let frame = image::RgbaImage::from_fn(size, size, |_, _| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 17;
+                    seed ^= seed << 5;
+                    let [r, g, b, _] = seed.to_le_bytes();
+                    image::Rgba([r, g, b, 255])
+                });
+                (frame, 10)
+            })
+            .collect()
+    }
+
+    fn noise_gif(size: u32, count: usize) -> CachedImage {
+        CachedImage {
+            bytes: encode_gif_frames(noise_frames(size, count)).unwrap(),
+            filename: "resized.gif".to_string(),
+        }
+    }
+
+    fn fitted_size(result: &(CachedImage, Option<SizeCompromise>)) -> usize {
+        result.0.bytes.len()
+    }
+
+    //노이즈 128x128 16프레임 gif는 약 350KB. 단계별 결과 크기 사이에 제한을 둬서 어느 단계에서 멈추는지 봄
+    #[test]
+    fn animated_fallback_ladder() {
+        let cases = [
+            (300_000, SizeCompromise::ReducedPalette(64)),
+            (150_000, SizeCompromise::ReducedPalette(16)),
+            (100_000, SizeCompromise::DroppedFrames(4)),
+            (50_000, SizeCompromise::Downscaled(32, 32)),
+        ];
+
+        for (budget, expected) in cases {
+            let limit = UPLOAD_LIMIT_MARGIN + budget;
+            let result = fit_to_upload_limit(noise_gif(128, 16), limit).unwrap();
+            assert_eq!(result.1, Some(expected), "budget {}", budget);
+            assert!(fitted_size(&result) <= budget as usize, "budget {}", budget);
+            assert!(result.0.filename.ends_with(".gif"));
+        }
+
+        assert!(matches!(
+            fit_to_upload_limit(noise_gif(128, 16), UPLOAD_LIMIT_MARGIN + 10_000),
+            Err(ImageTransferError::UploadLimitExceeded)
+        ));
+    }
+
+    #[test]
+    fn fitting_image_is_untouched() {
+        let gif = noise_gif(16, 2);
+        let size = gif.bytes.len();
+        let (image, compromise) = fit_to_upload_limit(gif, DEFAULT_UPLOAD_LIMIT).unwrap();
+        assert_eq!(compromise, None);
+        assert_eq!(image.bytes.len(), size);
+    }
+
+    //무손실 webp 노이즈는 gif보다 커서, gif로만 바꿔도 맞음
+    #[test]
+    fn converts_other_formats_to_gif_first() {
+        let webp = CachedImage {
+            bytes: crate::utils::scene_core::encode_webp_frames(&noise_frames(128, 16), 0).unwrap(),
+            filename: "resized.webp".to_string(),
+        };
+        let budget = 400_000;
+        assert!(webp.bytes.len() > budget as usize);
+
+        let result = fit_to_upload_limit(webp, UPLOAD_LIMIT_MARGIN + budget).unwrap();
+        assert_eq!(result.1, Some(SizeCompromise::ConvertedToGif));
+        assert!(fitted_size(&result) <= budget as usize);
+        assert_eq!(result.0.filename, "resized.gif");
+    }
+
+    #[test]
+    fn static_image_is_only_downscaled() {
+        let png = encode_png_frame(&noise_frames(256, 1)).unwrap();
+        let budget = png.len() as u64 / 3;
+        let image = CachedImage {
+            bytes: png,
+            filename: "resized.png".to_string(),
+        };
+
+        let result = fit_to_upload_limit(image, UPLOAD_LIMIT_MARGIN + budget).unwrap();
+        assert!(matches!(result.1, Some(SizeCompromise::Downscaled(..))));
+        assert!(fitted_size(&result) <= budget as usize);
+        assert_eq!(result.0.filename, "resized.png");
+    }
+}
@@ -0,0 +1,174 @@
+//확대한 이모지를 원래 보낸 사람 이름과 프로필 사진으로 다시 보내는 부분
+//채널마다 봇이 만든 웹후크 하나를 만들어두고 계속 씀. 스레드는 부모 채널의 웹후크로 보냄
+use log::{error, warn};
+use serenity::{
+    builder::{
+        CreateActionRow, CreateAllowedMentions, CreateAttachment, CreateWebhook, ExecuteWebhook,
+    },
+    client::Context,
+    model::{
+        channel::{Channel, ChannelType, Message},
+        id::ChannelId,
+        webhook::Webhook,
+    },
+};
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+
+use crate::GlobalWebhooks;
+
+const WEBHOOK_NAME: &str = "씬 이모지 확대";
+//웹후크 이름은 80자까지만 됨
+const MAX_WEBHOOK_USERNAME: usize = 80;
+//웹후크를 못 만든 채널은 이 시간 동안 다시 시도하지 않고 원래 방식으로 보냄
+const FAILURE_COOLDOWN: Duration = Duration::from_secs(60 * 10);
+
+//채널 하나의 웹후크 상태
+#[derive(Default)]
+pub enum WebhookSlot {
+    #[default]
+    Empty,
+    Ready(Box<Webhook>),
+    Failed(Instant),
+}
+
+//보낼 수 없으면 None. 그럼 원래 방식("**이름** :" 다음에 이미지)으로 보내면 됨
+pub async fn repost_as_author(
+    ctx: &Context,
+    msg: &Message,
+    display_name: &str,
+    content: Option<String>,
+    files: Vec<CreateAttachment>,
+    components: Vec<CreateActionRow>,
+) -> Option<Message> {
+    let (channel_id, thread_id) = webhook_channel(ctx, msg).await?;
+    if !can_manage_webhooks(ctx, msg, channel_id) {
+        return None;
+    }
+
+    let webhook = get_or_create_webhook(ctx, channel_id).await?;
+
+    let mut builder = ExecuteWebhook::new()
+        .username(
+            display_name
+                .chars()
+                .take(MAX_WEBHOOK_USERNAME)
+                .collect::<String>(),
+        )
+        .avatar_url(msg.author.face())
+        .allowed_mentions(CreateAllowedMentions::new())
+        .add_files(files)
+        .components(components);
+    if let Some(content) = content {
+        builder = builder.content(content);
+    }
+    if let Some(thread_id) = thread_id {
+        builder = builder.in_thread(thread_id);
+    }
+
+    match webhook.execute(&ctx.http, true, builder).await {
+        Ok(sent) => sent,
+        Err(why) => {
+            //누가 웹후크를 지웠을 수도 있으니 다음엔 새로 찾게 캐시에서 뺌
+            warn!("couldn't execute webhook in {}. {:?}", channel_id, why);
+            forget_webhook(ctx, channel_id).await;
+            None
+        }
+    }
+}
+
+//웹후크를 만들 채널과, 스레드면 스레드 ID
+async fn webhook_channel(ctx: &Context, msg: &Message) -> Option<(ChannelId, Option<ChannelId>)> {
+    let channel = match msg.channel(ctx).await {
+        Ok(Channel::Guild(channel)) => channel,
+        Ok(_) => return None,
+        Err(why) => {
+            error!("couldn't get channel {}. {:?}", msg.channel_id, why);
+            return None;
+        }
+    };
+
+    match channel.kind {
+        ChannelType::PublicThread | ChannelType::PrivateThread | ChannelType::NewsThread => {
+            Some((channel.parent_id?, Some(channel.id)))
+        }
+        _ => Some((channel.id, None)),
+    }
+}
+
+//캐시에 정보가 없으면 일단 보내보고, 실패하면 원래 방식으로 보냄
+fn can_manage_webhooks(ctx: &Context, msg: &Message, channel_id: ChannelId) -> bool {
+    let Some(guild) = msg.guild(&ctx.cache) else {
+        return true;
+    };
+    let bot_id = ctx.cache.current_user().id;
+    match (guild.members.get(&bot_id), guild.channels.get(&channel_id)) {
+        (Some(member), Some(channel)) => {
+            guild.user_permissions_in(channel, member).manage_webhooks()
+        }
+        _ => true,
+    }
+}
+
+async fn get_or_create_webhook(ctx: &Context, channel_id: ChannelId) -> Option<Webhook> {
+    //같은 채널에 웹후크가 두 개 생기지 않게 만드는 동안 그 채널만 잠가둠
+    let slot_lock = channel_slot(ctx, channel_id).await;
+    let mut slot = slot_lock.lock().await;
+    match &*slot {
+        WebhookSlot::Ready(webhook) => return Some(Webhook::clone(webhook)),
+        WebhookSlot::Failed(failed_at) if failed_at.elapsed() < FAILURE_COOLDOWN => return None,
+        _ => {}
+    }
+
+    let webhook = find_or_create_webhook(ctx, channel_id).await;
+    *slot = match &webhook {
+        Some(webhook) => WebhookSlot::Ready(Box::new(webhook.clone())),
+        None => WebhookSlot::Failed(Instant::now()),
+    };
+    webhook
+}
+
+async fn find_or_create_webhook(ctx: &Context, channel_id: ChannelId) -> Option<Webhook> {
+    let bot_id = ctx.cache.current_user().id;
+    let existing = match channel_id.webhooks(&ctx.http).await {
+        Ok(existing) => existing.into_iter().find(|webhook| {
+            webhook.token.is_some() && webhook.user.as_ref().map(|user| user.id) == Some(bot_id)
+        }),
+        Err(why) => {
+            warn!("couldn't get webhooks of {}. {:?}", channel_id, why);
+            return None;
+        }
+    };
+
+    match existing {
+        Some(webhook) => Some(webhook),
+        None => match channel_id
+            .create_webhook(&ctx.http, CreateWebhook::new(WEBHOOK_NAME))
+            .await
+        {
+            Ok(webhook) => Some(webhook),
+            Err(why) => {
+                warn!("couldn't create webhook in {}. {:?}", channel_id, why);
+                None
+            }
+        },
+    }
+}
+
+//전체 맵은 채널 자리를 찾거나 만들 때만 잠금
+async fn channel_slot(ctx: &Context, channel_id: ChannelId) -> Arc<Mutex<WebhookSlot>> {
+    let webhooks_lock = {
+        let data_read = ctx.data.read().await;
+        data_read.get::<GlobalWebhooks>().expect("poisened").clone()
+    };
+    let mut webhooks = webhooks_lock.lock().await;
+    webhooks.entry(channel_id).or_default().clone()
+}
+
+async fn forget_webhook(ctx: &Context, channel_id: ChannelId) {
+    *channel_slot(ctx, channel_id).await.lock().await = WebhookSlot::Empty;
+}